@@ -1,26 +1,125 @@
 -- Create tables for Ticket Management System
 
+-- Create tag_groups table (exclusive groups allow at most one of their tags per ticket)
+CREATE TABLE IF NOT EXISTS tag_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) NOT NULL UNIQUE,
+    exclusive BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
 -- Create tags table
 CREATE TABLE IF NOT EXISTS tags (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     name VARCHAR(50) NOT NULL UNIQUE,
-    color VARCHAR(7) DEFAULT '#6B7280',
+    color VARCHAR(7) NOT NULL DEFAULT '#6B7280' CHECK (color ~ '^#[0-9A-F]{6}$'), -- upper-case #RRGGBB
+    parent_id UUID REFERENCES tags(id) ON DELETE SET NULL CHECK (parent_id <> id), -- tag hierarchy, NULL for root tags
+    group_id UUID REFERENCES tag_groups(id) ON DELETE SET NULL,
+    version INTEGER NOT NULL DEFAULT 1, -- optimistic concurrency, exposed as ETag
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create users table
+CREATE TABLE IF NOT EXISTS users (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    avatar_color VARCHAR(7) NOT NULL DEFAULT '#6B7280',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    password_hash VARCHAR(255),
+    role VARCHAR(20) NOT NULL DEFAULT 'agent' CHECK (role IN ('admin', 'agent', 'viewer')),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create api_tokens table
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    token_hash CHAR(64) NOT NULL UNIQUE,
+    token_prefix VARCHAR(12) NOT NULL,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    expires_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create workflows table (statuses and transitions defined as data)
+CREATE TABLE IF NOT EXISTS workflows (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
     created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
 );
 
+CREATE UNIQUE INDEX IF NOT EXISTS idx_workflows_default ON workflows(is_default) WHERE is_default;
+
+-- Create workflow_states table (category: open, active or done)
+CREATE TABLE IF NOT EXISTS workflow_states (
+    workflow_id UUID NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    key VARCHAR(50) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    category VARCHAR(10) NOT NULL CHECK (category IN ('open', 'active', 'done')),
+    position INTEGER NOT NULL,
+    PRIMARY KEY (workflow_id, key)
+);
+
+-- Create workflow_transitions table
+CREATE TABLE IF NOT EXISTS workflow_transitions (
+    workflow_id UUID NOT NULL,
+    from_state VARCHAR(50) NOT NULL,
+    to_state VARCHAR(50) NOT NULL,
+    PRIMARY KEY (workflow_id, from_state, to_state),
+    FOREIGN KEY (workflow_id, from_state) REFERENCES workflow_states(workflow_id, key) ON DELETE CASCADE,
+    FOREIGN KEY (workflow_id, to_state) REFERENCES workflow_states(workflow_id, key) ON DELETE CASCADE
+);
+
+-- Seed the default workflow (the original four statuses)
+INSERT INTO workflows (id, name, description, is_default)
+VALUES ('00000000-0000-0000-0000-000000000001', 'default', '默认工作流', TRUE)
+ON CONFLICT DO NOTHING;
+
+INSERT INTO workflow_states (workflow_id, key, name, category, position) VALUES
+    ('00000000-0000-0000-0000-000000000001', 'open', '待处理', 'open', 0),
+    ('00000000-0000-0000-0000-000000000001', 'in_progress', '处理中', 'active', 1),
+    ('00000000-0000-0000-0000-000000000001', 'resolved', '已解决', 'done', 2),
+    ('00000000-0000-0000-0000-000000000001', 'closed', '已关闭', 'done', 3)
+ON CONFLICT DO NOTHING;
+
+INSERT INTO workflow_transitions (workflow_id, from_state, to_state) VALUES
+    ('00000000-0000-0000-0000-000000000001', 'open', 'in_progress'),
+    ('00000000-0000-0000-0000-000000000001', 'open', 'resolved'),
+    ('00000000-0000-0000-0000-000000000001', 'open', 'closed'),
+    ('00000000-0000-0000-0000-000000000001', 'in_progress', 'open'),
+    ('00000000-0000-0000-0000-000000000001', 'in_progress', 'resolved'),
+    ('00000000-0000-0000-0000-000000000001', 'in_progress', 'closed'),
+    ('00000000-0000-0000-0000-000000000001', 'resolved', 'open'),
+    ('00000000-0000-0000-0000-000000000001', 'resolved', 'in_progress'),
+    ('00000000-0000-0000-0000-000000000001', 'resolved', 'closed')
+ON CONFLICT DO NOTHING;
+
 -- Create tickets table
 CREATE TABLE IF NOT EXISTS tickets (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     title VARCHAR(255) NOT NULL,
     description TEXT,
-    status VARCHAR(20) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'in_progress', 'resolved', 'closed')),
+    workflow_id UUID NOT NULL REFERENCES workflows(id),
+    status VARCHAR(50) NOT NULL,
     priority VARCHAR(10) NOT NULL DEFAULT 'medium' CHECK (priority IN ('low', 'medium', 'high', 'urgent')),
-    assignee_id UUID,
-    reporter_id UUID,
+    assignee_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    reporter_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    version INTEGER NOT NULL DEFAULT 1, -- optimistic concurrency, exposed as ETag
     created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
-    resolved_at TIMESTAMP WITH TIME ZONE
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    due_date TIMESTAMP WITH TIME ZONE, -- optional deadline; tickets without one sort last
+    deleted_at TIMESTAMP WITH TIME ZONE -- set when moved to trash, purged after the retention period
 );
 
 -- Create ticket_tags table (many-to-many relationship)
@@ -34,17 +133,213 @@ CREATE TABLE IF NOT EXISTS ticket_tags (
 CREATE TABLE IF NOT EXISTS comments (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     ticket_id UUID NOT NULL REFERENCES tickets(id) ON DELETE CASCADE,
-    author_id UUID,
+    author_id UUID REFERENCES users(id) ON DELETE SET NULL,
     content TEXT NOT NULL,
     created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
 );
 
+-- Attachments: file contents live in the storage backend (local directory or
+-- S3-compatible bucket); only metadata is kept here. comment_id is NULL for
+-- files attached directly to the ticket.
+CREATE TABLE IF NOT EXISTS attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    ticket_id UUID NOT NULL REFERENCES tickets(id) ON DELETE CASCADE,
+    comment_id UUID REFERENCES comments(id) ON DELETE CASCADE,
+    filename VARCHAR(255) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    size BIGINT NOT NULL CHECK (size >= 0),
+    sha256 CHAR(64) NOT NULL,
+    storage_key VARCHAR(512) NOT NULL UNIQUE,
+    uploader_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Webhook subscriptions; events holds event names, "*" and "ticket.*" style wildcards
+CREATE TABLE IF NOT EXISTS webhooks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    url TEXT NOT NULL,
+    secret VARCHAR(128) NOT NULL,
+    events TEXT[] NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Webhook delivery queue, filled in the same transaction as the triggering write
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    webhook_id UUID NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event VARCHAR(50) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'succeeded', 'failed')),
+    attempt_count INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE,
+    response_status INTEGER,
+    response_body TEXT,
+    error TEXT,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS webhook_delivery_attempts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    delivery_id UUID NOT NULL REFERENCES webhook_deliveries(id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+    response_status INTEGER,
+    response_body TEXT,
+    error TEXT,
+    duration_ms INTEGER NOT NULL,
+    attempted_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Real-time event stream, written in the same transaction as the change and announced
+-- with NOTIFY stream_events; replayed to SSE clients by Last-Event-ID
+CREATE TABLE IF NOT EXISTS stream_events (
+    id BIGSERIAL PRIMARY KEY,
+    event VARCHAR(50) NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Highest stream event id removed by retention pruning (single row); ids can have gaps,
+-- so replay checks this instead of the oldest retained id
+CREATE TABLE IF NOT EXISTS stream_event_prunes (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    pruned_through BIGINT NOT NULL
+);
+
+-- Users watching a ticket get notified about status changes and new comments
+CREATE TABLE IF NOT EXISTS ticket_watchers (
+    ticket_id UUID NOT NULL REFERENCES tickets(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (ticket_id, user_id)
+);
+
+-- Per-user notification preferences (defaults apply when there is no row);
+-- delivery: immediate, digest (one email per hour) or off
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    language VARCHAR(10) NOT NULL DEFAULT 'zh' CHECK (language IN ('zh', 'en')),
+    delivery VARCHAR(20) NOT NULL DEFAULT 'immediate'
+        CHECK (delivery IN ('immediate', 'digest', 'off')),
+    assigned BOOLEAN NOT NULL DEFAULT TRUE,
+    status_changed BOOLEAN NOT NULL DEFAULT TRUE,
+    comment_added BOOLEAN NOT NULL DEFAULT TRUE,
+    mentioned BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Email notification queue; data holds the ticket/actor snapshot used to render the email
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ticket_id UUID REFERENCES tickets(id) ON DELETE CASCADE,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    kind VARCHAR(30) NOT NULL
+        CHECK (kind IN ('assigned', 'status_changed', 'comment_added', 'mentioned')),
+    data JSONB NOT NULL DEFAULT '{}'::jsonb,
+    digest BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'failed', 'skipped')),
+    attempt_count INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    error TEXT,
+    sent_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Full-text search: weighted tsvector document over title, description and comments.
+-- Kept in its own table so refreshing it does not bump tickets.version / updated_at.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Dedicated text search configuration (copy of simple); alter it to use a Chinese
+-- parser such as zhparser or pg_jieba, then run SELECT refresh_ticket_search(id) FROM tickets;
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = 'ticket_search') THEN
+        CREATE TEXT SEARCH CONFIGURATION ticket_search (COPY = simple);
+    END IF;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS ticket_search (
+    ticket_id UUID PRIMARY KEY REFERENCES tickets(id) ON DELETE CASCADE,
+    document TSVECTOR NOT NULL
+);
+
+-- Create tag_aliases table (old names kept after a tag merge or rename, still resolve to the tag)
+CREATE TABLE IF NOT EXISTS tag_aliases (
+    name VARCHAR(50) PRIMARY KEY,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create saved_views table (named ticket filter sets, private to the owner unless shared)
+CREATE TABLE IF NOT EXISTS saved_views (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    shared BOOLEAN NOT NULL DEFAULT FALSE,
+    filters JSONB NOT NULL DEFAULT '{}', -- status, priority, tag_ids, search, q, sort
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (owner_id, name)
+);
+
+-- Create ticket_events table (audit log, kept after the ticket is deleted)
+CREATE TABLE IF NOT EXISTS ticket_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    ticket_id UUID NOT NULL,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    event_type VARCHAR(30) NOT NULL CHECK (event_type IN (
+        'created', 'field_changed', 'tag_added', 'tag_removed',
+        'comment_added', 'comment_updated', 'comment_deleted', 'deleted',
+        'restored', 'purged', 'attachment_added', 'attachment_deleted'
+    )),
+    field VARCHAR(50),
+    old_value JSONB,
+    new_value JSONB,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
 -- Create indexes for performance
 CREATE INDEX IF NOT EXISTS idx_tickets_status ON tickets(status);
 CREATE INDEX IF NOT EXISTS idx_tickets_priority ON tickets(priority);
 CREATE INDEX IF NOT EXISTS idx_tickets_created_at ON tickets(created_at);
 CREATE INDEX IF NOT EXISTS idx_comments_ticket_id ON comments(ticket_id);
+CREATE INDEX IF NOT EXISTS idx_attachments_ticket_id ON attachments(ticket_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_attachments_comment_id ON attachments(comment_id) WHERE comment_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id ON webhook_deliveries(webhook_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_webhook_delivery_attempts_delivery_id ON webhook_delivery_attempts(delivery_id, attempt);
+CREATE INDEX IF NOT EXISTS idx_stream_events_created_at ON stream_events(created_at);
+CREATE INDEX IF NOT EXISTS idx_ticket_watchers_user_id ON ticket_watchers(user_id);
+CREATE INDEX IF NOT EXISTS idx_notifications_due ON notifications(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_users_is_active ON users(is_active);
+CREATE INDEX IF NOT EXISTS idx_users_role ON users(role);
+CREATE INDEX IF NOT EXISTS idx_api_tokens_user_id ON api_tokens(user_id);
+CREATE INDEX IF NOT EXISTS idx_tickets_reporter_id ON tickets(reporter_id);
+CREATE INDEX IF NOT EXISTS idx_tickets_workflow_id ON tickets(workflow_id);
+CREATE INDEX IF NOT EXISTS idx_tickets_deleted_at ON tickets(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_tags_parent_id ON tags(parent_id);
+CREATE INDEX IF NOT EXISTS idx_tags_group_id ON tags(group_id);
+CREATE INDEX IF NOT EXISTS idx_tag_aliases_tag_id ON tag_aliases(tag_id);
+CREATE INDEX IF NOT EXISTS idx_saved_views_shared ON saved_views(shared) WHERE shared;
+-- Keyset (cursor) pagination over (sort key, id)
+CREATE INDEX IF NOT EXISTS idx_tickets_created_at_id ON tickets(created_at, id) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_tickets_updated_at_id ON tickets(updated_at, id) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_ticket_search_document ON ticket_search USING GIN(document);
+CREATE INDEX IF NOT EXISTS idx_tickets_title_trgm ON tickets USING GIN(title gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_tickets_description_trgm ON tickets USING GIN(description gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_comments_content_trgm ON comments USING GIN(content gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_comments_author_id ON comments(author_id);
+CREATE INDEX IF NOT EXISTS idx_ticket_events_ticket_id ON ticket_events(ticket_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_ticket_events_actor_id ON ticket_events(actor_id);
 
 -- Create function for auto-updating updated_at
 CREATE OR REPLACE FUNCTION update_updated_at_column()
@@ -56,10 +351,34 @@ END;
 $$ language 'plpgsql';
 
 -- Create triggers for auto-updating updated_at
+DROP TRIGGER IF EXISTS update_users_updated_at ON users;
+CREATE TRIGGER update_users_updated_at BEFORE UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
 DROP TRIGGER IF EXISTS update_tags_updated_at ON tags;
 CREATE TRIGGER update_tags_updated_at BEFORE UPDATE ON tags
     FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
 
+DROP TRIGGER IF EXISTS update_tag_groups_updated_at ON tag_groups;
+CREATE TRIGGER update_tag_groups_updated_at BEFORE UPDATE ON tag_groups
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_webhooks_updated_at ON webhooks;
+CREATE TRIGGER update_webhooks_updated_at BEFORE UPDATE ON webhooks
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_webhook_deliveries_updated_at ON webhook_deliveries;
+CREATE TRIGGER update_webhook_deliveries_updated_at BEFORE UPDATE ON webhook_deliveries
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_notification_preferences_updated_at ON notification_preferences;
+CREATE TRIGGER update_notification_preferences_updated_at BEFORE UPDATE ON notification_preferences
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+DROP TRIGGER IF EXISTS update_workflows_updated_at ON workflows;
+CREATE TRIGGER update_workflows_updated_at BEFORE UPDATE ON workflows
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
 DROP TRIGGER IF EXISTS update_tickets_updated_at ON tickets;
 CREATE TRIGGER update_tickets_updated_at BEFORE UPDATE ON tickets
     FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -68,4 +387,70 @@ DROP TRIGGER IF EXISTS update_comments_updated_at ON comments;
 CREATE TRIGGER update_comments_updated_at BEFORE UPDATE ON comments
     FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
 
+DROP TRIGGER IF EXISTS update_saved_views_updated_at ON saved_views;
+CREATE TRIGGER update_saved_views_updated_at BEFORE UPDATE ON saved_views
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Create function for bumping the optimistic concurrency version
+CREATE OR REPLACE FUNCTION increment_version()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.version = OLD.version + 1;
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS increment_tickets_version ON tickets;
+CREATE TRIGGER increment_tickets_version BEFORE UPDATE ON tickets
+    FOR EACH ROW EXECUTE FUNCTION increment_version();
+
+DROP TRIGGER IF EXISTS increment_tags_version ON tags;
+CREATE TRIGGER increment_tags_version BEFORE UPDATE ON tags
+    FOR EACH ROW EXECUTE FUNCTION increment_version();
+
+-- Create functions keeping the search documents up to date
+CREATE OR REPLACE FUNCTION refresh_ticket_search(target UUID)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO ticket_search (ticket_id, document)
+    SELECT t.id,
+        setweight(to_tsvector('ticket_search', t.title), 'A') ||
+        setweight(to_tsvector('ticket_search', COALESCE(t.description, '')), 'B') ||
+        setweight(to_tsvector('ticket_search', COALESCE(
+            (SELECT string_agg(c.content, ' ') FROM comments c WHERE c.ticket_id = t.id), ''
+        )), 'C')
+    FROM tickets t
+    WHERE t.id = target
+    ON CONFLICT (ticket_id) DO UPDATE SET document = EXCLUDED.document;
+END;
+$$ language 'plpgsql';
+
+CREATE OR REPLACE FUNCTION refresh_ticket_search_from_ticket()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_ticket_search(NEW.id);
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+CREATE OR REPLACE FUNCTION refresh_ticket_search_from_comment()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM refresh_ticket_search(OLD.ticket_id);
+    ELSE
+        PERFORM refresh_ticket_search(NEW.ticket_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS refresh_ticket_search ON tickets;
+CREATE TRIGGER refresh_ticket_search AFTER INSERT OR UPDATE OF title, description ON tickets
+    FOR EACH ROW EXECUTE FUNCTION refresh_ticket_search_from_ticket();
+
+DROP TRIGGER IF EXISTS refresh_ticket_search ON comments;
+CREATE TRIGGER refresh_ticket_search AFTER INSERT OR UPDATE OF content OR DELETE ON comments
+    FOR EACH ROW EXECUTE FUNCTION refresh_ticket_search_from_comment();
+
 SELECT 'Database tables created successfully!' as status;
\ No newline at end of file
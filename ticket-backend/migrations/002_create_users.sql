@@ -0,0 +1,34 @@
+-- 创建用户表
+CREATE TABLE users (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    email VARCHAR(255) NOT NULL UNIQUE, -- 统一存储为小写
+    avatar_color VARCHAR(7) NOT NULL DEFAULT '#6B7280', -- 头像背景色（HEX）
+    is_active BOOLEAN NOT NULL DEFAULT TRUE, -- 停用后不可再被指派
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_users_is_active ON users(is_active);
+
+CREATE TRIGGER update_users_updated_at BEFORE UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 清理无法关联到用户的历史数据
+UPDATE tickets SET assignee_id = NULL
+    WHERE assignee_id IS NOT NULL AND assignee_id NOT IN (SELECT id FROM users);
+UPDATE tickets SET reporter_id = NULL
+    WHERE reporter_id IS NOT NULL AND reporter_id NOT IN (SELECT id FROM users);
+UPDATE comments SET author_id = NULL
+    WHERE author_id IS NOT NULL AND author_id NOT IN (SELECT id FROM users);
+
+-- 工单与评论关联用户表
+ALTER TABLE tickets
+    ADD CONSTRAINT fk_tickets_assignee FOREIGN KEY (assignee_id) REFERENCES users(id) ON DELETE SET NULL,
+    ADD CONSTRAINT fk_tickets_reporter FOREIGN KEY (reporter_id) REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE comments
+    ADD CONSTRAINT fk_comments_author FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_tickets_reporter_id ON tickets(reporter_id);
+CREATE INDEX idx_comments_author_id ON comments(author_id);
@@ -0,0 +1,18 @@
+-- 用户登录密码（Argon2 哈希，未设置密码的用户无法登录）
+ALTER TABLE users ADD COLUMN password_hash VARCHAR(255);
+
+-- 创建 API 令牌表
+CREATE TABLE api_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    token_hash CHAR(64) NOT NULL UNIQUE, -- 令牌的 SHA-256 哈希（HEX），明文不落库
+    token_prefix VARCHAR(12) NOT NULL, -- 令牌前缀，便于用户识别
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    expires_at TIMESTAMP WITH TIME ZONE, -- 为空表示永不过期
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_api_tokens_user_id ON api_tokens(user_id);
@@ -0,0 +1,5 @@
+-- 用户角色：admin（管理员）、agent（处理人）、viewer（只读）
+ALTER TABLE users ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'agent'
+    CHECK (role IN ('admin', 'agent', 'viewer'));
+
+CREATE INDEX idx_users_role ON users(role);
@@ -0,0 +1,18 @@
+-- 创建工单事件表（审计日志）
+-- ticket_id 不设外键：工单删除后仍保留其历史记录
+CREATE TABLE ticket_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    ticket_id UUID NOT NULL,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL, -- 操作人，系统操作为空
+    event_type VARCHAR(30) NOT NULL CHECK (event_type IN (
+        'created', 'field_changed', 'tag_added', 'tag_removed',
+        'comment_added', 'comment_updated', 'comment_deleted', 'deleted'
+    )),
+    field VARCHAR(50), -- field_changed 时记录变更的字段
+    old_value JSONB,
+    new_value JSONB,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_ticket_events_ticket_id ON ticket_events(ticket_id, created_at);
+CREATE INDEX idx_ticket_events_actor_id ON ticket_events(actor_id);
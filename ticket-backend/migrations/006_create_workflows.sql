@@ -0,0 +1,68 @@
+-- 创建工作流表：状态与流转规则以数据形式定义
+CREATE TABLE workflows (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE, -- 新建工单未指定工作流时使用
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 最多只有一个默认工作流
+CREATE UNIQUE INDEX idx_workflows_default ON workflows(is_default) WHERE is_default;
+
+CREATE TRIGGER update_workflows_updated_at BEFORE UPDATE ON workflows
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- 工作流状态，category 用于统计与解决时间维护：open（待处理）、active（处理中）、done（已完成）
+CREATE TABLE workflow_states (
+    workflow_id UUID NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    key VARCHAR(50) NOT NULL, -- 存储在 tickets.status 中的状态标识
+    name VARCHAR(100) NOT NULL, -- 展示名称
+    category VARCHAR(10) NOT NULL CHECK (category IN ('open', 'active', 'done')),
+    position INTEGER NOT NULL, -- 状态在工作流中的顺序
+    PRIMARY KEY (workflow_id, key)
+);
+
+-- 工作流允许的状态流转
+CREATE TABLE workflow_transitions (
+    workflow_id UUID NOT NULL,
+    from_state VARCHAR(50) NOT NULL,
+    to_state VARCHAR(50) NOT NULL,
+    PRIMARY KEY (workflow_id, from_state, to_state),
+    FOREIGN KEY (workflow_id, from_state) REFERENCES workflow_states(workflow_id, key) ON DELETE CASCADE,
+    FOREIGN KEY (workflow_id, to_state) REFERENCES workflow_states(workflow_id, key) ON DELETE CASCADE
+);
+
+-- 默认工作流：保留原有的四个状态
+INSERT INTO workflows (id, name, description, is_default)
+VALUES ('00000000-0000-0000-0000-000000000001', 'default', '默认工作流', TRUE);
+
+INSERT INTO workflow_states (workflow_id, key, name, category, position) VALUES
+    ('00000000-0000-0000-0000-000000000001', 'open', '待处理', 'open', 0),
+    ('00000000-0000-0000-0000-000000000001', 'in_progress', '处理中', 'active', 1),
+    ('00000000-0000-0000-0000-000000000001', 'resolved', '已解决', 'done', 2),
+    ('00000000-0000-0000-0000-000000000001', 'closed', '已关闭', 'done', 3);
+
+-- 已关闭的工单只能通过重新打开回到初始状态
+INSERT INTO workflow_transitions (workflow_id, from_state, to_state) VALUES
+    ('00000000-0000-0000-0000-000000000001', 'open', 'in_progress'),
+    ('00000000-0000-0000-0000-000000000001', 'open', 'resolved'),
+    ('00000000-0000-0000-0000-000000000001', 'open', 'closed'),
+    ('00000000-0000-0000-0000-000000000001', 'in_progress', 'open'),
+    ('00000000-0000-0000-0000-000000000001', 'in_progress', 'resolved'),
+    ('00000000-0000-0000-0000-000000000001', 'in_progress', 'closed'),
+    ('00000000-0000-0000-0000-000000000001', 'resolved', 'open'),
+    ('00000000-0000-0000-0000-000000000001', 'resolved', 'in_progress'),
+    ('00000000-0000-0000-0000-000000000001', 'resolved', 'closed');
+
+-- 工单关联工作流，状态改由工作流校验
+ALTER TABLE tickets ADD COLUMN workflow_id UUID REFERENCES workflows(id);
+UPDATE tickets SET workflow_id = '00000000-0000-0000-0000-000000000001';
+ALTER TABLE tickets ALTER COLUMN workflow_id SET NOT NULL;
+
+ALTER TABLE tickets DROP CONSTRAINT IF EXISTS tickets_status_check;
+ALTER TABLE tickets ALTER COLUMN status TYPE VARCHAR(50);
+ALTER TABLE tickets ALTER COLUMN status DROP DEFAULT;
+
+CREATE INDEX idx_tickets_workflow_id ON tickets(workflow_id);
@@ -0,0 +1,17 @@
+-- 乐观并发控制：工单与标签的版本号，每次更新自增，对外以 ETag 形式暴露
+ALTER TABLE tickets ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE tags ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE OR REPLACE FUNCTION increment_version()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.version = OLD.version + 1;
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER increment_tickets_version BEFORE UPDATE ON tickets
+    FOR EACH ROW EXECUTE FUNCTION increment_version();
+
+CREATE TRIGGER increment_tags_version BEFORE UPDATE ON tags
+    FOR EACH ROW EXECUTE FUNCTION increment_version();
@@ -0,0 +1,12 @@
+-- 工单软删除：删除时移入回收站，超过保留期后由后台任务永久删除
+ALTER TABLE tickets ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_tickets_deleted_at ON tickets(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- 新增恢复与永久删除事件
+ALTER TABLE ticket_events DROP CONSTRAINT ticket_events_event_type_check;
+ALTER TABLE ticket_events ADD CONSTRAINT ticket_events_event_type_check CHECK (event_type IN (
+    'created', 'field_changed', 'tag_added', 'tag_removed',
+    'comment_added', 'comment_updated', 'comment_deleted', 'deleted',
+    'restored', 'purged'
+));
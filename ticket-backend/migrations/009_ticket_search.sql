@@ -0,0 +1,66 @@
+-- 工单全文搜索：标题、描述与评论内容合并为加权 tsvector 文档
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- 搜索专用的文本搜索配置，默认复制自 simple（不做词干处理，中文按连续字符切分）
+-- 安装中文分词扩展（如 zhparser、pg_jieba）后可修改该配置，再执行
+-- SELECT refresh_ticket_search(id) FROM tickets; 重建搜索文档
+CREATE TEXT SEARCH CONFIGURATION ticket_search (COPY = simple);
+
+-- 搜索文档单独存放：评论变更时刷新文档不会改动工单的 version 与 updated_at
+CREATE TABLE ticket_search (
+    ticket_id UUID PRIMARY KEY REFERENCES tickets(id) ON DELETE CASCADE,
+    document TSVECTOR NOT NULL
+);
+
+CREATE INDEX idx_ticket_search_document ON ticket_search USING GIN(document);
+
+-- 中文等未分词文本的子串匹配回退（ILIKE）使用三元组索引
+CREATE INDEX idx_tickets_title_trgm ON tickets USING GIN(title gin_trgm_ops);
+CREATE INDEX idx_tickets_description_trgm ON tickets USING GIN(description gin_trgm_ops);
+CREATE INDEX idx_comments_content_trgm ON comments USING GIN(content gin_trgm_ops);
+
+-- 重建单个工单的搜索文档（权重：标题 A、描述 B、评论 C）
+CREATE OR REPLACE FUNCTION refresh_ticket_search(target UUID)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO ticket_search (ticket_id, document)
+    SELECT t.id,
+        setweight(to_tsvector('ticket_search', t.title), 'A') ||
+        setweight(to_tsvector('ticket_search', COALESCE(t.description, '')), 'B') ||
+        setweight(to_tsvector('ticket_search', COALESCE(
+            (SELECT string_agg(c.content, ' ') FROM comments c WHERE c.ticket_id = t.id), ''
+        )), 'C')
+    FROM tickets t
+    WHERE t.id = target
+    ON CONFLICT (ticket_id) DO UPDATE SET document = EXCLUDED.document;
+END;
+$$ language 'plpgsql';
+
+CREATE OR REPLACE FUNCTION refresh_ticket_search_from_ticket()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_ticket_search(NEW.id);
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+CREATE OR REPLACE FUNCTION refresh_ticket_search_from_comment()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM refresh_ticket_search(OLD.ticket_id);
+    ELSE
+        PERFORM refresh_ticket_search(NEW.ticket_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER refresh_ticket_search AFTER INSERT OR UPDATE OF title, description ON tickets
+    FOR EACH ROW EXECUTE FUNCTION refresh_ticket_search_from_ticket();
+
+CREATE TRIGGER refresh_ticket_search AFTER INSERT OR UPDATE OF content OR DELETE ON comments
+    FOR EACH ROW EXECUTE FUNCTION refresh_ticket_search_from_comment();
+
+-- 为已有工单生成搜索文档
+SELECT refresh_ticket_search(id) FROM tickets;
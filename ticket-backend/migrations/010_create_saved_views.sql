@@ -0,0 +1,16 @@
+-- 保存的视图：命名的工单筛选条件，归属于创建者，可共享给所有人
+CREATE TABLE saved_views (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    owner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    shared BOOLEAN NOT NULL DEFAULT FALSE, -- 为 TRUE 时所有用户可见
+    filters JSONB NOT NULL DEFAULT '{}', -- 筛选条件：status、priority、tag_ids、search、q、排序等
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (owner_id, name)
+);
+
+CREATE INDEX idx_saved_views_shared ON saved_views(shared) WHERE shared;
+
+CREATE TRIGGER update_saved_views_updated_at BEFORE UPDATE ON saved_views
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
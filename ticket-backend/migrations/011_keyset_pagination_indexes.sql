@@ -0,0 +1,3 @@
+-- 游标分页按 (排序键, id) 做 keyset 查询，为常用的时间排序建立复合索引
+CREATE INDEX idx_tickets_created_at_id ON tickets(created_at, id) WHERE deleted_at IS NULL;
+CREATE INDEX idx_tickets_updated_at_id ON tickets(updated_at, id) WHERE deleted_at IS NULL;
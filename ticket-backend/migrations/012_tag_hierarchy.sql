@@ -0,0 +1,6 @@
+-- 标签层级：parent_id 指向父标签，为空表示根标签（如 area/backend/db）
+-- 环路检测在 TagRepository 中完成；删除父标签时子标签由应用上移一级，外键兜底置空
+ALTER TABLE tags ADD COLUMN parent_id UUID REFERENCES tags(id) ON DELETE SET NULL;
+ALTER TABLE tags ADD CONSTRAINT tags_parent_not_self CHECK (parent_id <> id);
+
+CREATE INDEX idx_tags_parent_id ON tags(parent_id);
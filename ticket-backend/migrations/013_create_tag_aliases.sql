@@ -0,0 +1,8 @@
+-- 标签别名：合并或改名后保留的旧名称，按名称查找、搜索和筛选时解析到对应标签
+CREATE TABLE tag_aliases (
+    name VARCHAR(50) PRIMARY KEY,
+    tag_id UUID NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_tag_aliases_tag_id ON tag_aliases(tag_id);
@@ -0,0 +1,6 @@
+-- 标签颜色统一为大写 #RRGGBB：历史数据中的小写值转为大写，无效值与空值重置为默认颜色
+UPDATE tags SET color = UPPER(color) WHERE color ~* '^#[0-9a-f]{6}$';
+UPDATE tags SET color = '#6B7280' WHERE color IS NULL OR color !~ '^#[0-9A-F]{6}$';
+
+ALTER TABLE tags ALTER COLUMN color SET NOT NULL;
+ALTER TABLE tags ADD CONSTRAINT tags_color_format CHECK (color ~ '^#[0-9A-F]{6}$');
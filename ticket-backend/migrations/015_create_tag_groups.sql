@@ -0,0 +1,15 @@
+-- 标签分组：exclusive 为 TRUE 时，一个工单最多只能带有该分组中的一个标签（如 severity-*、team-*）
+CREATE TABLE tag_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(50) NOT NULL UNIQUE,
+    exclusive BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE tags ADD COLUMN group_id UUID REFERENCES tag_groups(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_tags_group_id ON tags(group_id);
+
+CREATE TRIGGER update_tag_groups_updated_at BEFORE UPDATE ON tag_groups
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,25 @@
+-- 附件：文件内容保存在存储后端（本地目录或 S3 兼容对象存储），这里只记录元数据
+-- comment_id 为空表示直接附加在工单上；工单或评论删除时附件记录级联删除
+CREATE TABLE attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    ticket_id UUID NOT NULL REFERENCES tickets(id) ON DELETE CASCADE,
+    comment_id UUID REFERENCES comments(id) ON DELETE CASCADE,
+    filename VARCHAR(255) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    size BIGINT NOT NULL CHECK (size >= 0),
+    sha256 CHAR(64) NOT NULL,
+    storage_key VARCHAR(512) NOT NULL UNIQUE,
+    uploader_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_attachments_ticket_id ON attachments(ticket_id, created_at);
+CREATE INDEX idx_attachments_comment_id ON attachments(comment_id) WHERE comment_id IS NOT NULL;
+
+-- 新增附件上传与删除事件
+ALTER TABLE ticket_events DROP CONSTRAINT ticket_events_event_type_check;
+ALTER TABLE ticket_events ADD CONSTRAINT ticket_events_event_type_check CHECK (event_type IN (
+    'created', 'field_changed', 'tag_added', 'tag_removed',
+    'comment_added', 'comment_updated', 'comment_deleted', 'deleted',
+    'restored', 'purged', 'attachment_added', 'attachment_deleted'
+));
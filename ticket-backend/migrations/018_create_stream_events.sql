@@ -0,0 +1,10 @@
+-- 实时事件流：与触发事件的写操作在同一事务中写入，并通过 NOTIFY stream_events 通知各后端实例
+-- 客户端断线重连时按 Last-Event-ID 从这里补发，超过保留期的记录由后台任务清理
+CREATE TABLE stream_events (
+    id BIGSERIAL PRIMARY KEY,
+    event VARCHAR(50) NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_stream_events_created_at ON stream_events(created_at);
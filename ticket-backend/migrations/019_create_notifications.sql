@@ -0,0 +1,55 @@
+-- 工单关注者：工单状态变更、新评论时收到通知
+CREATE TABLE ticket_watchers (
+    ticket_id UUID NOT NULL REFERENCES tickets(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (ticket_id, user_id)
+);
+
+CREATE INDEX idx_ticket_watchers_user_id ON ticket_watchers(user_id);
+
+-- 已有工单的报告人和指派人默认关注
+INSERT INTO ticket_watchers (ticket_id, user_id)
+SELECT id, reporter_id FROM tickets WHERE reporter_id IS NOT NULL
+UNION
+SELECT id, assignee_id FROM tickets WHERE assignee_id IS NOT NULL;
+
+-- 用户的通知偏好，没有记录时使用默认值
+-- delivery: immediate 立即发送，digest 每小时汇总发送一封，off 不发送邮件
+CREATE TABLE notification_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    language VARCHAR(10) NOT NULL DEFAULT 'zh' CHECK (language IN ('zh', 'en')),
+    delivery VARCHAR(20) NOT NULL DEFAULT 'immediate'
+        CHECK (delivery IN ('immediate', 'digest', 'off')),
+    assigned BOOLEAN NOT NULL DEFAULT TRUE,
+    status_changed BOOLEAN NOT NULL DEFAULT TRUE,
+    comment_added BOOLEAN NOT NULL DEFAULT TRUE,
+    mentioned BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 通知（邮件发送队列），data 为生成时的工单、操作者等快照，用于渲染邮件；
+-- 用户关闭邮件通知后，尚未发送的通知标记为 skipped
+CREATE TABLE notifications (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ticket_id UUID REFERENCES tickets(id) ON DELETE CASCADE,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    kind VARCHAR(30) NOT NULL
+        CHECK (kind IN ('assigned', 'status_changed', 'comment_added', 'mentioned')),
+    data JSONB NOT NULL DEFAULT '{}'::jsonb,
+    digest BOOLEAN NOT NULL DEFAULT FALSE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'sent', 'failed', 'skipped')),
+    attempt_count INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    error TEXT,
+    sent_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_notifications_due ON notifications(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX idx_notifications_user_id ON notifications(user_id, created_at DESC);
+
+CREATE TRIGGER update_notification_preferences_updated_at BEFORE UPDATE ON notification_preferences
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,2 @@
+-- 工单截止时间：可为空，按截止时间排序时未设置的工单排在最后
+ALTER TABLE tickets ADD COLUMN due_date TIMESTAMP WITH TIME ZONE;
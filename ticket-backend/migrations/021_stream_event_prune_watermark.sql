@@ -0,0 +1,10 @@
+-- 实时事件清理水位：记录已清理事件的最大序号（单行表）
+-- 序号来自序列，回滚的事务会留下空洞，补发时不能凭最早保留的序号判断客户端之后的事件是否已被清理
+CREATE TABLE stream_event_prunes (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    pruned_through BIGINT NOT NULL
+);
+
+-- 已有事件之前的记录可能已被清理，保守地以最早保留的序号为起点
+INSERT INTO stream_event_prunes (pruned_through)
+SELECT MIN(id) - 1 FROM stream_events HAVING COUNT(*) > 0;
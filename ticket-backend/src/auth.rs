@@ -0,0 +1,311 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query, Request, State},
+    http::{
+        header::{ACCEPT, AUTHORIZATION, UPGRADE},
+        request::Parts,
+        HeaderMap, Method,
+    },
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    config::AuthConfig,
+    database::DbPool,
+    error::AppError,
+    models::{CreateUserRequest, UpdateUserRequest, User, UserRole},
+    repositories::{ApiTokenRepository, UserRepository},
+    state::AppState,
+};
+
+// API 令牌前缀，用于与会话令牌（JWT）区分
+pub const API_TOKEN_PREFIX: &str = "tk_";
+
+// API 令牌权限范围
+pub const SCOPE_READ: &str = "read";
+pub const SCOPE_WRITE: &str = "write";
+pub const SCOPES: [&str; 2] = [SCOPE_READ, SCOPE_WRITE];
+
+// 当前请求的调用者
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub user: User,
+    // 通过 API 令牌认证时的权限范围；会话令牌为 None，拥有全部权限
+    pub scopes: Option<Vec<String>>,
+}
+
+impl CurrentUser {
+    pub fn id(&self) -> Uuid {
+        self.user.id
+    }
+
+    pub fn role(&self) -> UserRole {
+        self.user.role
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        match &self.scopes {
+            Some(scopes) => scopes.iter().any(|s| s == scope),
+            None => true,
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CurrentUser>()
+            .cloned()
+            .ok_or_else(|| AppError::unauthorized("请先登录"))
+    }
+}
+
+// 会话令牌声明
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: Uuid,
+    iat: i64,
+    exp: i64,
+}
+
+// 认证中间件：校验 Bearer 令牌并将调用者写入请求扩展
+pub async fn require_auth(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let token = request_token(&request).ok_or_else(|| AppError::unauthorized("缺少认证令牌"))?;
+    let current_user = authenticate(&state, &token).await?;
+
+    let read_only = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    let required_scope = if read_only { SCOPE_READ } else { SCOPE_WRITE };
+    if !current_user.has_scope(required_scope) {
+        return Err(AppError::forbidden(format!(
+            "令牌缺少 {} 权限",
+            required_scope
+        )));
+    }
+
+    request.extensions_mut().insert(current_user);
+    Ok(next.run(request).await)
+}
+
+// 管理员中间件：须挂在 require_auth 之后
+pub async fn require_admin(request: Request, next: Next) -> Result<Response, AppError> {
+    let is_admin = request
+        .extensions()
+        .get::<CurrentUser>()
+        .is_some_and(|current_user| current_user.role().is_admin());
+
+    if !is_admin {
+        return Err(AppError::forbidden("需要管理员权限"));
+    }
+
+    Ok(next.run(request).await)
+}
+
+// 写权限中间件：须挂在 require_auth 之后；只读角色不能修改工单、标签、评论等共享数据，
+// 通知偏好、关注、个人视图、API 令牌等属于用户自己的资源不受限制
+pub async fn require_writer(request: Request, next: Next) -> Result<Response, AppError> {
+    let can_write = request
+        .extensions()
+        .get::<CurrentUser>()
+        .is_some_and(|current_user| current_user.role().can_write());
+
+    if !can_write {
+        return Err(AppError::forbidden("只读用户无权执行写操作"));
+    }
+
+    Ok(next.run(request).await)
+}
+
+// 从请求中取出令牌：优先使用 Authorization 头；
+// 浏览器的 EventSource 和 WebSocket 无法设置请求头，这两类流式请求也接受 access_token 查询参数
+fn request_token(request: &Request) -> Option<String> {
+    let headers = request.headers();
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty());
+    if let Some(token) = bearer {
+        return Some(token.to_string());
+    }
+
+    if !is_streaming_request(headers) {
+        return None;
+    }
+
+    Query::<AccessTokenQuery>::try_from_uri(request.uri())
+        .ok()?
+        .0
+        .access_token
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+#[derive(Deserialize)]
+struct AccessTokenQuery {
+    access_token: Option<String>,
+}
+
+fn is_streaming_request(headers: &HeaderMap) -> bool {
+    let header_contains = |name, needle: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.to_ascii_lowercase().contains(needle))
+    };
+
+    header_contains(ACCEPT, "text/event-stream") || header_contains(UPGRADE, "websocket")
+}
+
+// 根据令牌（API 令牌或会话令牌）识别调用者
+pub async fn authenticate(state: &AppState, token: &str) -> Result<CurrentUser, AppError> {
+    let users = UserRepository::new(state.pool.clone());
+
+    let (user_id, scopes) = if token.starts_with(API_TOKEN_PREFIX) {
+        let api_token = ApiTokenRepository::new(state.pool.clone())
+            .authenticate(&hash_api_token(token))
+            .await?
+            .ok_or_else(|| AppError::unauthorized("令牌无效、已过期或已撤销"))?;
+        (api_token.user_id, Some(api_token.scopes))
+    } else {
+        (decode_session_token(&state.config.auth, token)?, None)
+    };
+
+    let user = users
+        .get_by_id(user_id)
+        .await
+        .map_err(|_| AppError::unauthorized("用户不存在"))?;
+
+    if !user.is_active {
+        return Err(AppError::unauthorized("用户已停用"));
+    }
+
+    Ok(CurrentUser { user, scopes })
+}
+
+// 签发会话令牌
+pub fn issue_session_token(
+    config: &AuthConfig,
+    user_id: Uuid,
+) -> Result<(String, DateTime<Utc>), AppError> {
+    let now = Utc::now();
+    let expires_at = now + config.jwt_expires_in;
+    let claims = Claims {
+        sub: user_id,
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+
+    let token = jsonwebtoken::encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )
+    .map_err(|e| AppError::internal(format!("签发令牌失败: {}", e)))?;
+
+    Ok((token, expires_at))
+}
+
+fn decode_session_token(config: &AuthConfig, token: &str) -> Result<Uuid, AppError> {
+    let data = jsonwebtoken::decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|_| AppError::unauthorized("令牌无效或已过期"))?;
+
+    Ok(data.claims.sub)
+}
+
+// 生成新的 API 令牌明文
+pub fn generate_api_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", API_TOKEN_PREFIX, hex::encode(bytes))
+}
+
+// API 令牌仅以 SHA-256 哈希形式存储
+pub fn hash_api_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn hash_password(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::internal(format!("密码哈希失败: {}", e)))
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+// 确保配置中的管理员账号存在且密码与配置一致
+pub async fn ensure_bootstrap_admin(pool: &DbPool, config: &AuthConfig) -> anyhow::Result<()> {
+    let (Some(email), Some(password)) = (&config.admin_email, &config.admin_password) else {
+        return Ok(());
+    };
+
+    let users = UserRepository::new(pool.clone());
+    match users.find_by_email(email).await? {
+        Some(user) => {
+            users
+                .update(
+                    user.id,
+                    UpdateUserRequest {
+                        password: Some(password.clone()),
+                        role: Some(UserRole::Admin),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+        }
+        None => {
+            users
+                .create(CreateUserRequest {
+                    name: "Administrator".to_string(),
+                    email: email.clone(),
+                    avatar_color: None,
+                    password: Some(password.clone()),
+                    role: Some(UserRole::Admin),
+                })
+                .await?;
+            info!("已创建管理员账号: {}", email);
+        }
+    }
+
+    Ok(())
+}
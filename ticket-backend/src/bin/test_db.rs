@@ -1,6 +1,5 @@
 // 数据库连接测试程序
 use dotenv::dotenv;
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -57,7 +56,7 @@ async fn main() -> anyhow::Result<()> {
 
 // 测试数据模型
 async fn test_data_models(pool: &ticket_backend::database::DbPool) -> anyhow::Result<()> {
-    use ticket_backend::models::{Priority, TicketStatus};
+    use ticket_backend::models::Priority;
     use uuid::Uuid;
 
     // 测试插入标签
@@ -74,11 +73,12 @@ async fn test_data_models(pool: &ticket_backend::database::DbPool) -> anyhow::Re
 
     // 测试查询标签
     println!("   - 测试查询标签...");
-    let tag: Option<ticket_backend::models::Tag> =
-        sqlx::query_as("SELECT id, name, color, created_at, updated_at FROM tags WHERE id = $1")
-            .bind(tag_id)
-            .fetch_optional(pool)
-            .await?;
+    let tag: Option<ticket_backend::models::Tag> = sqlx::query_as(
+        "SELECT id, name, color, parent_id, group_id, version, created_at, updated_at FROM tags WHERE id = $1",
+    )
+    .bind(tag_id)
+    .fetch_optional(pool)
+    .await?;
 
     if tag.is_some() {
         println!("     ✅ 标签查询成功！");
@@ -90,11 +90,11 @@ async fn test_data_models(pool: &ticket_backend::database::DbPool) -> anyhow::Re
     println!("   - 测试插入工单...");
     let ticket_id = Uuid::new_v4();
     sqlx::query!(
-        "INSERT INTO tickets (id, title, description, status, priority) VALUES ($1, $2, $3, $4, $5)",
+        "INSERT INTO tickets (id, title, description, workflow_id, status, priority) VALUES ($1, $2, $3, (SELECT id FROM workflows WHERE is_default), $4, $5)",
         ticket_id,
         "Test Ticket",
         "This is a test ticket",
-        TicketStatus::Open as TicketStatus,
+        "open",
         Priority::Medium as Priority
     )
     .execute(pool)
@@ -103,7 +103,7 @@ async fn test_data_models(pool: &ticket_backend::database::DbPool) -> anyhow::Re
     // 测试查询工单
     println!("   - 测试查询工单...");
     let ticket: Option<ticket_backend::models::Ticket> = sqlx::query_as(
-        "SELECT id, title, description, status, priority, assignee_id, reporter_id, created_at, updated_at, resolved_at FROM tickets WHERE id = $1"
+        "SELECT id, title, description, workflow_id, status, priority, assignee_id, reporter_id, version, created_at, updated_at, resolved_at, due_date, deleted_at FROM tickets WHERE id = $1"
     )
     .bind(ticket_id)
     .fetch_optional(pool)
@@ -1,16 +1,430 @@
-use std::env;
+use std::{env, path::PathBuf};
 
+#[derive(Debug, Clone)]
 pub struct Config {
     pub port: u16,
+    pub auth: AuthConfig,
+    pub trash: TrashConfig,
+    pub attachments: AttachmentConfig,
+    pub webhooks: WebhookConfig,
+    pub event_stream: EventStreamConfig,
+    pub collaboration: CollaborationConfig,
+    pub notifications: NotificationConfig,
+}
+
+// 认证配置
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub jwt_secret: String,
+    pub jwt_expires_in: chrono::Duration,
+    // 启动时确保存在的管理员账号（可选）
+    pub admin_email: Option<String>,
+    pub admin_password: Option<String>,
 }
 
 impl Config {
-    pub fn from_env() -> Self {
-        Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        Ok(Config {
             port: env::var("PORT")
                 .unwrap_or_else(|_| "3000".to_string())
                 .parse()
                 .unwrap_or(3000),
+            auth: AuthConfig::from_env()?,
+            trash: TrashConfig::from_env()?,
+            attachments: AttachmentConfig::from_env()?,
+            webhooks: WebhookConfig::from_env()?,
+            event_stream: EventStreamConfig::from_env()?,
+            collaboration: CollaborationConfig::from_env()?,
+            notifications: NotificationConfig::from_env()?,
+        })
+    }
+}
+
+impl AuthConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let jwt_secret =
+            env::var("JWT_SECRET").map_err(|_| anyhow::anyhow!("JWT_SECRET 环境变量未设置"))?;
+
+        let jwt_expires_in = env::var("JWT_EXPIRES_IN").unwrap_or_else(|_| "24h".to_string());
+        let jwt_expires_in = parse_duration(&jwt_expires_in)
+            .ok_or_else(|| anyhow::anyhow!("JWT_EXPIRES_IN 格式无效，示例: 30m、24h、7d"))?;
+
+        Ok(Self {
+            jwt_secret,
+            jwt_expires_in,
+            admin_email: env::var("ADMIN_EMAIL").ok().filter(|v| !v.is_empty()),
+            admin_password: env::var("ADMIN_PASSWORD").ok().filter(|v| !v.is_empty()),
+        })
+    }
+}
+
+// 回收站配置
+#[derive(Debug, Clone)]
+pub struct TrashConfig {
+    // 工单在回收站中保留的时长，超过后永久删除
+    pub retention: chrono::Duration,
+    // 清理任务的执行间隔
+    pub purge_interval: chrono::Duration,
+}
+
+impl TrashConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let retention = env::var("TRASH_RETENTION").unwrap_or_else(|_| "30d".to_string());
+        let retention = parse_duration(&retention)
+            .ok_or_else(|| anyhow::anyhow!("TRASH_RETENTION 格式无效，示例: 12h、30d"))?;
+
+        let purge_interval = env::var("TRASH_PURGE_INTERVAL").unwrap_or_else(|_| "1h".to_string());
+        let purge_interval = parse_duration(&purge_interval)
+            .filter(|interval| *interval > chrono::Duration::zero())
+            .ok_or_else(|| anyhow::anyhow!("TRASH_PURGE_INTERVAL 格式无效，示例: 30m、1h"))?;
+
+        Ok(Self {
+            retention,
+            purge_interval,
+        })
+    }
+}
+
+// 附件配置
+#[derive(Debug, Clone)]
+pub struct AttachmentConfig {
+    // 单个文件的大小上限（字节）
+    pub max_size: u64,
+    // 允许上传的 MIME 类型，支持 "image/*" 形式的通配
+    pub allowed_types: Vec<String>,
+    pub storage: StorageConfig,
+}
+
+// 附件存储后端
+#[derive(Debug, Clone)]
+pub enum StorageConfig {
+    Local { root: PathBuf },
+    S3(S3Config),
+}
+
+// S3 兼容对象存储（AWS S3、MinIO 等），使用路径风格的 URL
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+const DEFAULT_ALLOWED_TYPES: &str =
+    "image/*,text/plain,text/csv,application/pdf,application/json,application/zip,application/gzip";
+
+impl AttachmentConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let max_size = env::var("ATTACHMENT_MAX_SIZE").unwrap_or_else(|_| "10MB".to_string());
+        let max_size = parse_size(&max_size)
+            .filter(|size| *size > 0)
+            .ok_or_else(|| anyhow::anyhow!("ATTACHMENT_MAX_SIZE 格式无效，示例: 512KB、10MB"))?;
+
+        let allowed_types = env::var("ATTACHMENT_ALLOWED_TYPES")
+            .unwrap_or_else(|_| DEFAULT_ALLOWED_TYPES.to_string())
+            .split(',')
+            .map(|value| value.trim().to_ascii_lowercase())
+            .filter(|value| !value.is_empty())
+            .collect();
+
+        let storage = match env::var("ATTACHMENT_STORAGE")
+            .unwrap_or_else(|_| "local".to_string())
+            .as_str()
+        {
+            "local" => StorageConfig::Local {
+                root: env::var("ATTACHMENT_DIR")
+                    .unwrap_or_else(|_| "data/attachments".to_string())
+                    .into(),
+            },
+            "s3" => StorageConfig::S3(S3Config::from_env()?),
+            other => anyhow::bail!("ATTACHMENT_STORAGE 取值无效: {}，可选 local、s3", other),
+        };
+
+        Ok(Self {
+            max_size,
+            allowed_types,
+            storage,
+        })
+    }
+
+    // 内容类型是否在允许列表中
+    pub fn is_allowed_type(&self, content_type: &str) -> bool {
+        let content_type = content_type.to_ascii_lowercase();
+        self.allowed_types.iter().any(|allowed| {
+            if allowed == "*/*" {
+                return true;
+            }
+            match allowed.strip_suffix("/*") {
+                Some(prefix) => content_type
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/')),
+                None => *allowed == content_type,
+            }
+        })
+    }
+}
+
+impl S3Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let required = |name: &str| {
+            env::var(name)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("使用 S3 存储时必须设置 {} 环境变量", name))
+        };
+
+        Ok(Self {
+            endpoint: required("S3_ENDPOINT")?.trim_end_matches('/').to_string(),
+            bucket: required("S3_BUCKET")?,
+            region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            access_key_id: required("S3_ACCESS_KEY_ID")?,
+            secret_access_key: required("S3_SECRET_ACCESS_KEY")?,
+        })
+    }
+}
+
+// Webhook 投递配置
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    // 投递任务检查待发送队列的间隔
+    pub poll_interval: chrono::Duration,
+    // 单次请求的超时时间
+    pub timeout: chrono::Duration,
+    // 最多尝试次数，全部失败后标记为 failed
+    pub max_attempts: i32,
+    // 首次重试的等待时间，之后每次翻倍
+    pub retry_base: chrono::Duration,
+}
+
+impl WebhookConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let duration = |name: &str, default: &str| {
+            let value = env::var(name).unwrap_or_else(|_| default.to_string());
+            parse_duration(&value)
+                .filter(|duration| *duration > chrono::Duration::zero())
+                .ok_or_else(|| anyhow::anyhow!("{} 格式无效，示例: 30s、5m", name))
+        };
+
+        let max_attempts = env::var("WEBHOOK_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse()
+            .ok()
+            .filter(|attempts| *attempts > 0)
+            .ok_or_else(|| anyhow::anyhow!("WEBHOOK_MAX_ATTEMPTS 必须是正整数"))?;
+
+        Ok(Self {
+            poll_interval: duration("WEBHOOK_POLL_INTERVAL", "1s")?,
+            timeout: duration("WEBHOOK_TIMEOUT", "10s")?,
+            max_attempts,
+            retry_base: duration("WEBHOOK_RETRY_BASE", "30s")?,
+        })
+    }
+}
+
+// 实时事件流配置
+#[derive(Debug, Clone)]
+pub struct EventStreamConfig {
+    // 事件保留时长，断线重连时只能补发保留期内的事件
+    pub retention: chrono::Duration,
+}
+
+impl EventStreamConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let retention = env::var("EVENT_STREAM_RETENTION").unwrap_or_else(|_| "24h".to_string());
+        let retention = parse_duration(&retention)
+            .filter(|retention| *retention > chrono::Duration::zero())
+            .ok_or_else(|| anyhow::anyhow!("EVENT_STREAM_RETENTION 格式无效，示例: 12h、1d"))?;
+
+        Ok(Self { retention })
+    }
+}
+
+// 工单协作通道（WebSocket）配置
+#[derive(Debug, Clone)]
+pub struct CollaborationConfig {
+    // 服务端发送 Ping 的间隔
+    pub heartbeat_interval: chrono::Duration,
+    // 超过该时长未收到客户端任何消息（含 Pong）即视为连接已断开
+    pub heartbeat_timeout: chrono::Duration,
+}
+
+impl CollaborationConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let duration = |name: &str, default: &str| {
+            let value = env::var(name).unwrap_or_else(|_| default.to_string());
+            parse_duration(&value)
+                .filter(|duration| *duration > chrono::Duration::zero())
+                .ok_or_else(|| anyhow::anyhow!("{} 格式无效，示例: 30s、2m", name))
+        };
+
+        let heartbeat_interval = duration("WS_HEARTBEAT_INTERVAL", "30s")?;
+        let heartbeat_timeout = duration("WS_HEARTBEAT_TIMEOUT", "90s")?;
+        if heartbeat_timeout <= heartbeat_interval {
+            anyhow::bail!("WS_HEARTBEAT_TIMEOUT 必须大于 WS_HEARTBEAT_INTERVAL");
         }
+
+        Ok(Self {
+            heartbeat_interval,
+            heartbeat_timeout,
+        })
+    }
+}
+
+// 邮件通知配置
+#[derive(Debug, Clone)]
+pub struct NotificationConfig {
+    // 未设置 SMTP_HOST 时只记录通知，不发送邮件
+    pub smtp: Option<SmtpConfig>,
+    // 发件人，如 "工单系统 <noreply@example.com>"
+    pub from: String,
+    // 邮件中的工单链接，{id} 替换为工单 ID，如 "https://tickets.example.com/tickets/{id}"
+    pub ticket_url: Option<String>,
+    // 发送任务检查队列的间隔
+    pub poll_interval: chrono::Duration,
+    // 汇总模式下，最早一条未发送的通知等待多久后发送汇总邮件
+    pub digest_interval: chrono::Duration,
+    // 最多尝试次数，全部失败后标记为 failed
+    pub max_attempts: i32,
+    // 首次重试的等待时间，之后每次翻倍
+    pub retry_base: chrono::Duration,
+}
+
+// SMTP 连接配置
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub timeout: chrono::Duration,
+}
+
+// SMTP 加密方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    // 明文连接，仅用于本地测试（如 MailHog、Mailpit）
+    None,
+    StartTls,
+    Tls,
+}
+
+impl NotificationConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let duration = |name: &str, default: &str| {
+            let value = env::var(name).unwrap_or_else(|_| default.to_string());
+            parse_duration(&value)
+                .filter(|duration| *duration > chrono::Duration::zero())
+                .ok_or_else(|| anyhow::anyhow!("{} 格式无效，示例: 30s、1h", name))
+        };
+
+        let from = env::var("NOTIFICATION_FROM")
+            .unwrap_or_else(|_| "工单系统 <noreply@localhost>".to_string());
+        if from.parse::<lettre::message::Mailbox>().is_err() {
+            anyhow::bail!("NOTIFICATION_FROM 格式无效，示例: 工单系统 <noreply@example.com>");
+        }
+
+        let max_attempts = env::var("NOTIFICATION_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .ok()
+            .filter(|attempts| *attempts > 0)
+            .ok_or_else(|| anyhow::anyhow!("NOTIFICATION_MAX_ATTEMPTS 必须是正整数"))?;
+
+        Ok(Self {
+            smtp: SmtpConfig::from_env()?,
+            from,
+            ticket_url: env::var("NOTIFICATION_TICKET_URL")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            poll_interval: duration("NOTIFICATION_POLL_INTERVAL", "5s")?,
+            digest_interval: duration("NOTIFICATION_DIGEST_INTERVAL", "1h")?,
+            max_attempts,
+            retry_base: duration("NOTIFICATION_RETRY_BASE", "1m")?,
+        })
+    }
+}
+
+impl SmtpConfig {
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let Some(host) = env::var("SMTP_HOST").ok().filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+
+        let security = match env::var("SMTP_SECURITY")
+            .unwrap_or_else(|_| "starttls".to_string())
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "none" => SmtpSecurity::None,
+            "starttls" => SmtpSecurity::StartTls,
+            "tls" => SmtpSecurity::Tls,
+            other => anyhow::bail!("SMTP_SECURITY 无效: {}，可选 none、starttls、tls", other),
+        };
+
+        let default_port = match security {
+            SmtpSecurity::None => 25,
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+        };
+        let port = match env::var("SMTP_PORT") {
+            Ok(port) => port
+                .parse()
+                .map_err(|_| anyhow::anyhow!("SMTP_PORT 必须是端口号"))?,
+            Err(_) => default_port,
+        };
+
+        let timeout = env::var("SMTP_TIMEOUT").unwrap_or_else(|_| "10s".to_string());
+        let timeout = parse_duration(&timeout)
+            .filter(|timeout| *timeout > chrono::Duration::zero())
+            .ok_or_else(|| anyhow::anyhow!("SMTP_TIMEOUT 格式无效，示例: 10s"))?;
+
+        Ok(Some(Self {
+            host,
+            port,
+            security,
+            username: env::var("SMTP_USERNAME").ok().filter(|v| !v.is_empty()),
+            password: env::var("SMTP_PASSWORD").ok().filter(|v| !v.is_empty()),
+            timeout,
+        }))
+    }
+}
+
+// 解析形如 "512KB"、"10MB"、"1GB" 的大小（1024 进制，不区分大小写），纯数字按字节计算
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "b"),
+    };
+    let number: u64 = number.parse().ok()?;
+
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "b" => 1,
+        "k" | "kb" => 1024,
+        "m" | "mb" => 1024 * 1024,
+        "g" | "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.checked_mul(multiplier)
+}
+
+// 解析形如 "30s"、"15m"、"24h"、"7d" 的时长，纯数字按秒计算
+pub fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+    let number: i64 = number.parse().ok()?;
+
+    match unit {
+        "s" => Some(chrono::Duration::seconds(number)),
+        "m" => Some(chrono::Duration::minutes(number)),
+        "h" => Some(chrono::Duration::hours(number)),
+        "d" => Some(chrono::Duration::days(number)),
+        _ => None,
     }
 }
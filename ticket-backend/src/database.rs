@@ -79,19 +79,23 @@ pub async fn health_check(pool: &DbPool) -> bool {
     }
 }
 
-// 获取数据库统计信息
+// 获取数据库统计信息（不含回收站中的工单及其评论）
 pub async fn get_database_stats(pool: &DbPool) -> anyhow::Result<DatabaseStats> {
-    let tickets_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tickets")
-        .fetch_one(pool)
-        .await?;
+    let tickets_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM tickets WHERE deleted_at IS NULL")
+            .fetch_one(pool)
+            .await?;
 
     let tags_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tags")
         .fetch_one(pool)
         .await?;
 
-    let comments_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM comments")
-        .fetch_one(pool)
-        .await?;
+    let comments_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM comments c \
+         INNER JOIN tickets t ON t.id = c.ticket_id AND t.deleted_at IS NULL",
+    )
+    .fetch_one(pool)
+    .await?;
 
     Ok(DatabaseStats {
         tickets_count,
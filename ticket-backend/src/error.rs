@@ -1,12 +1,16 @@
 use axum::{
-    http::StatusCode,
+    extract::multipart::MultipartError,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
+use serde_json::{json, Value};
 use thiserror::Error;
 use validator::ValidationErrors;
 
+use crate::precondition::etag;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("数据库错误: {0}")]
@@ -24,12 +28,27 @@ pub enum AppError {
     #[error("无效请求: {0}")]
     BadRequest(String),
 
+    #[error("未认证: {0}")]
+    Unauthorized(String),
+
+    #[error("无权限: {0}")]
+    Forbidden(String),
+
+    #[error("请求内容过大: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("前置条件不满足: 当前版本为 {version}")]
+    PreconditionFailed { version: i32, current: Value },
+
     #[error("内部服务器错误: {0}")]
     Internal(String),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        // 版本冲突时附带服务端当前副本及其 ETag，便于客户端合并后重试
+        let mut current_copy = None;
+
         let (status, error_message) = match self {
             AppError::Database(err) => {
                 tracing::error!("数据库错误: {:?}", err);
@@ -45,6 +64,16 @@ impl IntoResponse for AppError {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.to_string()),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, msg.to_string()),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.to_string()),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.to_string()),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg.to_string()),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg.to_string()),
+            AppError::PreconditionFailed { version, current } => {
+                current_copy = Some((version, current));
+                (
+                    StatusCode::PRECONDITION_FAILED,
+                    "资源已被他人修改，请基于最新版本重试".to_string(),
+                )
+            }
             AppError::Internal(msg) => {
                 tracing::error!("内部错误: {}", msg);
                 (
@@ -54,14 +83,30 @@ impl IntoResponse for AppError {
             }
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": {
                 "message": error_message,
                 "status": status.as_u16()
             }
-        }));
+        });
 
-        (status, body).into_response()
+        if let Some((version, current)) = current_copy {
+            body["error"]["current"] = current;
+            return (status, [(header::ETAG, etag(version))], Json(body)).into_response();
+        }
+
+        (status, Json(body)).into_response()
+    }
+}
+
+// multipart 解析失败：超过请求体大小限制时返回 413，其余视为无效请求
+impl From<MultipartError> for AppError {
+    fn from(err: MultipartError) -> Self {
+        if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            AppError::payload_too_large("上传内容超过大小限制")
+        } else {
+            AppError::bad_request(format!("无效的上传请求: {}", err.body_text()))
+        }
     }
 }
 
@@ -79,6 +124,25 @@ impl AppError {
         Self::BadRequest(message.into())
     }
 
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::Forbidden(message.into())
+    }
+
+    pub fn precondition_failed<T: Serialize>(version: i32, current: &T) -> Self {
+        Self::PreconditionFailed {
+            version,
+            current: serde_json::to_value(current).unwrap_or(Value::Null),
+        }
+    }
+
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        Self::PayloadTooLarge(message.into())
+    }
+
     pub fn internal(message: impl Into<String>) -> Self {
         Self::Internal(message.into())
     }
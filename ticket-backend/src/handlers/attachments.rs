@@ -0,0 +1,232 @@
+use crate::{
+    auth::CurrentUser,
+    config::AttachmentConfig,
+    error::AppError,
+    models::{Attachment, NewAttachment},
+    repositories::attachments::AttachmentRepository,
+    state::AppState,
+    storage::{self, SpoolWriter, SpooledFile},
+    utils::filename,
+};
+use axum::{
+    body::Body,
+    extract::{multipart::Field, Multipart, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use uuid::Uuid as UuidType;
+
+// 单次请求最多上传的文件数
+pub const MAX_FILES_PER_REQUEST: usize = 10;
+
+// 上传路由的请求体上限：单文件上限 × 文件数，外加 multipart 分隔与表单字段的余量
+pub fn upload_body_limit(config: &AttachmentConfig) -> usize {
+    let limit = config
+        .max_size
+        .saturating_mul(MAX_FILES_PER_REQUEST as u64)
+        .saturating_add(1024 * 1024);
+    usize::try_from(limit).unwrap_or(usize::MAX)
+}
+
+// 获取工单的全部附件（含评论附件）
+pub async fn list_ticket_attachments(
+    State(state): State<AppState>,
+    Path(ticket_id): Path<UuidType>,
+) -> Result<Json<Vec<Attachment>>, AppError> {
+    let repository = AttachmentRepository::new(state.pool);
+    let attachments = repository.list(ticket_id, None).await?;
+    Ok(Json(attachments))
+}
+
+// 获取评论的附件
+pub async fn list_comment_attachments(
+    State(state): State<AppState>,
+    Path((ticket_id, comment_id)): Path<(UuidType, UuidType)>,
+) -> Result<Json<Vec<Attachment>>, AppError> {
+    let repository = AttachmentRepository::new(state.pool);
+    let attachments = repository.list(ticket_id, Some(comment_id)).await?;
+    Ok(Json(attachments))
+}
+
+// 上传工单附件（multipart/form-data，每个带文件名的字段为一个文件）
+pub async fn upload_ticket_attachments(
+    State(state): State<AppState>,
+    Path(ticket_id): Path<UuidType>,
+    current_user: CurrentUser,
+    multipart: Multipart,
+) -> Result<(StatusCode, Json<Vec<Attachment>>), AppError> {
+    upload(state, ticket_id, None, current_user, multipart).await
+}
+
+// 上传评论附件
+pub async fn upload_comment_attachments(
+    State(state): State<AppState>,
+    Path((ticket_id, comment_id)): Path<(UuidType, UuidType)>,
+    current_user: CurrentUser,
+    multipart: Multipart,
+) -> Result<(StatusCode, Json<Vec<Attachment>>), AppError> {
+    upload(state, ticket_id, Some(comment_id), current_user, multipart).await
+}
+
+// 下载附件，内容从存储后端流式返回
+pub async fn download_attachment(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Response, AppError> {
+    let repository = AttachmentRepository::new(state.pool);
+    let attachment = repository.get(id).await?;
+    let stream = state.storage.get(&attachment.storage_key).await?;
+
+    let headers = [
+        (header::CONTENT_TYPE, attachment.content_type),
+        (header::CONTENT_LENGTH, attachment.size.to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            filename::content_disposition(&attachment.filename),
+        ),
+        (header::ETAG, format!("\"{}\"", attachment.sha256)),
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+    ];
+
+    Ok((headers, Body::from_stream(stream)).into_response())
+}
+
+// 删除附件（仅上传者或管理员）
+pub async fn delete_attachment(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+) -> Result<StatusCode, AppError> {
+    let repository = AttachmentRepository::new(state.pool);
+    let attachment = repository.get(id).await?;
+
+    if attachment.uploader_id != Some(current_user.id()) && !current_user.role().is_admin() {
+        return Err(AppError::forbidden("只有上传者或管理员可以删除附件"));
+    }
+
+    let attachment = repository.delete(id, Some(current_user.id())).await?;
+    storage::delete_all(state.storage.as_ref(), &[attachment.storage_key]).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn upload(
+    state: AppState,
+    ticket_id: UuidType,
+    comment_id: Option<UuidType>,
+    current_user: CurrentUser,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Vec<Attachment>>), AppError> {
+    let repository = AttachmentRepository::new(state.pool.clone());
+    // 先检查目标，避免为不存在的工单读取整个请求体
+    repository
+        .ensure_target_exists(ticket_id, comment_id)
+        .await?;
+
+    let config = &state.config.attachments;
+    let mut files = Vec::new();
+    while let Some(field) = multipart.next_field().await? {
+        // 忽略不带文件名的普通表单字段
+        if field.file_name().is_none() {
+            continue;
+        }
+        if files.len() == MAX_FILES_PER_REQUEST {
+            return Err(AppError::bad_request(format!(
+                "单次最多上传 {} 个文件",
+                MAX_FILES_PER_REQUEST
+            )));
+        }
+        files.push(read_file(field, ticket_id, config).await?);
+    }
+
+    if files.is_empty() {
+        return Err(AppError::bad_request("请至少上传一个文件"));
+    }
+
+    // 先写入存储后端，再写入数据库；任何一步失败都清理已写入的对象
+    let mut stored = Vec::with_capacity(files.len());
+    let mut result = Ok(());
+    for (file, spooled) in &files {
+        result = state
+            .storage
+            .put(&file.storage_key, &file.content_type, spooled)
+            .await;
+        if result.is_err() {
+            break;
+        }
+        stored.push(file.storage_key.clone());
+    }
+
+    let files: Vec<NewAttachment> = files.into_iter().map(|(file, _)| file).collect();
+    let result = match result {
+        Ok(()) => {
+            repository
+                .create_many(ticket_id, comment_id, current_user.id(), &files)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(attachments) => Ok((StatusCode::CREATED, Json(attachments))),
+        Err(e) => {
+            storage::delete_all(state.storage.as_ref(), &stored).await;
+            Err(e)
+        }
+    }
+}
+
+// 读取单个文件字段：校验类型与大小，内容逐块写入临时文件并计算 SHA-256
+async fn read_file(
+    mut field: Field<'_>,
+    ticket_id: UuidType,
+    config: &AttachmentConfig,
+) -> Result<(NewAttachment, SpooledFile), AppError> {
+    let filename = filename::sanitize(field.file_name().unwrap_or_default());
+    let content_type = resolve_content_type(field.content_type(), &filename);
+    if !config.is_allowed_type(&content_type) {
+        return Err(AppError::bad_request(format!(
+            "不支持的文件类型: {}",
+            content_type
+        )));
+    }
+
+    let mut writer = SpoolWriter::new()?;
+    while let Some(chunk) = field.chunk().await? {
+        if writer.size() + chunk.len() as u64 > config.max_size {
+            return Err(AppError::payload_too_large(format!(
+                "文件 {} 超过大小限制（最大 {} 字节）",
+                filename, config.max_size
+            )));
+        }
+        writer.write(&chunk).await?;
+    }
+    let spooled = writer.finish().await?;
+
+    let id = UuidType::new_v4();
+    let attachment = NewAttachment {
+        id,
+        storage_key: format!("tickets/{}/{}", ticket_id, id),
+        filename,
+        content_type,
+        size: spooled.size() as i64,
+        sha256: spooled.sha256().to_string(),
+    };
+
+    Ok((attachment, spooled))
+}
+
+// 使用客户端声明的类型（去掉参数部分）；未声明或为通用二进制类型时按扩展名推断
+fn resolve_content_type(declared: Option<&str>, filename: &str) -> String {
+    let declared = declared
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty() && value != "application/octet-stream");
+
+    declared.unwrap_or_else(|| {
+        mime_guess::from_path(filename)
+            .first_raw()
+            .unwrap_or("application/octet-stream")
+            .to_string()
+    })
+}
@@ -0,0 +1,84 @@
+use crate::{
+    auth::{issue_session_token, verify_password, CurrentUser},
+    error::AppError,
+    models::{ApiToken, CreateApiTokenRequest, CreatedApiToken, LoginRequest, LoginResponse, User},
+    repositories::{api_tokens::ApiTokenRepository, users::UserRepository},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use uuid::Uuid as UuidType;
+use validator::Validate;
+
+// 邮箱密码登录，签发会话令牌
+pub async fn login(
+    State(state): State<AppState>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = UserRepository::new(state.pool);
+    let credentials = repository.find_credentials(&request.email).await?;
+
+    // 不区分“用户不存在”与“密码错误”，避免泄露账号信息
+    let user = match credentials {
+        Some((user, Some(password_hash)))
+            if user.is_active && verify_password(&request.password, &password_hash) =>
+        {
+            user
+        }
+        _ => return Err(AppError::unauthorized("邮箱或密码错误")),
+    };
+
+    let (token, expires_at) = issue_session_token(&state.config.auth, user.id)?;
+
+    Ok(Json(LoginResponse {
+        token,
+        token_type: "Bearer".to_string(),
+        expires_at,
+        user,
+    }))
+}
+
+// 获取当前登录用户
+pub async fn me(current_user: CurrentUser) -> Json<User> {
+    Json(current_user.user)
+}
+
+// 列出当前用户的 API 令牌
+pub async fn list_tokens(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> Result<Json<Vec<ApiToken>>, AppError> {
+    let repository = ApiTokenRepository::new(state.pool);
+    let tokens = repository.list_for_user(current_user.id()).await?;
+    Ok(Json(tokens))
+}
+
+// 创建 API 令牌，明文仅返回一次
+pub async fn create_token(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(request): Json<CreateApiTokenRequest>,
+) -> Result<Json<CreatedApiToken>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = ApiTokenRepository::new(state.pool);
+    let token = repository.create(current_user.id(), request).await?;
+    Ok(Json(token))
+}
+
+// 撤销 API 令牌
+pub async fn revoke_token(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(id): Path<UuidType>,
+) -> Result<Json<ApiToken>, AppError> {
+    let repository = ApiTokenRepository::new(state.pool);
+    let token = repository.revoke(current_user.id(), id).await?;
+    Ok(Json(token))
+}
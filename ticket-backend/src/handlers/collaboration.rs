@@ -0,0 +1,27 @@
+use crate::{
+    auth::CurrentUser, error::AppError, repositories::tickets::TicketRepository,
+    services::collaboration, state::AppState,
+};
+use axum::{
+    extract::{ws::WebSocketUpgrade, Path, State},
+    response::Response,
+};
+use uuid::Uuid as UuidType;
+
+// 工单协作通道（WebSocket）
+// 连接后收到 presence（在线参与者），之后推送 join/leave/typing 以及已保存的 field_changed；
+// 客户端可发送 {"type":"typing","typing":true}、{"type":"presence"} 与 {"type":"leave"}
+pub async fn ticket_socket(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    // 工单不存在或已在回收站中时不建立连接
+    TicketRepository::new(state.pool.clone())
+        .get_by_id(id)
+        .await?;
+
+    let user = current_user.user;
+    Ok(ws.on_upgrade(move |socket| collaboration::run_session(state, id, user, socket)))
+}
@@ -0,0 +1,108 @@
+use crate::{
+    auth::CurrentUser,
+    error::AppError,
+    models::{
+        Comment, CommentQuery, CreateCommentRequest, PaginatedResponse, UpdateCommentRequest,
+    },
+    repositories::comments::CommentRepository,
+    state::AppState,
+    storage,
+    utils::resolve_pagination,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid as UuidType;
+use validator::Validate;
+
+// 分页获取工单评论
+pub async fn list_comments(
+    State(state): State<AppState>,
+    Path(ticket_id): Path<UuidType>,
+    Query(query): Query<CommentQuery>,
+) -> Result<Json<PaginatedResponse<Comment>>, AppError> {
+    let (limit, offset) = resolve_pagination(query.page, query.limit, query.offset);
+
+    let repository = CommentRepository::new(state.pool);
+    let comments = repository.list(ticket_id, limit, offset).await?;
+    Ok(Json(comments))
+}
+
+// 获取单条评论
+pub async fn get_comment(
+    State(state): State<AppState>,
+    Path((ticket_id, comment_id)): Path<(UuidType, UuidType)>,
+) -> Result<Json<Comment>, AppError> {
+    let repository = CommentRepository::new(state.pool);
+    let comment = repository.get(ticket_id, comment_id).await?;
+    Ok(Json(comment))
+}
+
+// 添加评论到工单
+pub async fn create_comment(
+    State(state): State<AppState>,
+    Path(ticket_id): Path<UuidType>,
+    current_user: CurrentUser,
+    Json(request): Json<CreateCommentRequest>,
+) -> Result<Json<Comment>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = CommentRepository::new(state.pool);
+    let comment = repository
+        .create(ticket_id, request, current_user.id())
+        .await?;
+    Ok(Json(comment))
+}
+
+// 编辑评论
+pub async fn update_comment(
+    State(state): State<AppState>,
+    Path((ticket_id, comment_id)): Path<(UuidType, UuidType)>,
+    current_user: CurrentUser,
+    Json(request): Json<UpdateCommentRequest>,
+) -> Result<Json<Comment>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = CommentRepository::new(state.pool);
+    let comment = repository.get(ticket_id, comment_id).await?;
+    ensure_author_or_admin(&comment, &current_user, "只有作者或管理员可以编辑评论")?;
+
+    let comment = repository
+        .update(ticket_id, comment_id, request, Some(current_user.id()))
+        .await?;
+    Ok(Json(comment))
+}
+
+// 删除评论
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    Path((ticket_id, comment_id)): Path<(UuidType, UuidType)>,
+    current_user: CurrentUser,
+) -> Result<StatusCode, AppError> {
+    let repository = CommentRepository::new(state.pool);
+    let comment = repository.get(ticket_id, comment_id).await?;
+    ensure_author_or_admin(&comment, &current_user, "只有作者或管理员可以删除评论")?;
+
+    let storage_keys = repository
+        .delete(ticket_id, comment_id, Some(current_user.id()))
+        .await?;
+    storage::delete_all(state.storage.as_ref(), &storage_keys).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 评论只能由作者本人或管理员修改
+fn ensure_author_or_admin(
+    comment: &Comment,
+    current_user: &CurrentUser,
+    message: &str,
+) -> Result<(), AppError> {
+    if comment.author_id != Some(current_user.id()) && !current_user.role().is_admin() {
+        return Err(AppError::forbidden(message));
+    }
+
+    Ok(())
+}
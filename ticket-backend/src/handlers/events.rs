@@ -0,0 +1,102 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    convert::Infallible,
+};
+
+use crate::{
+    error::AppError,
+    models::{EventStreamQuery, StreamEvent},
+    repositories::StreamEventRepository,
+    state::AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{stream, Stream};
+use serde_json::json;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+// 断线重连时最多补发的事件数，超过后改为发送 reset
+const MAX_REPLAY: i64 = 1000;
+
+// 实时事件流（SSE）
+// 每条消息的 id 为事件序号，event 为事件名，data 与 Webhook 请求体相同；
+// 重连时通过 Last-Event-ID 请求头（或 last_event_id 参数）补发遗漏的事件，
+// 无法补发（超过保留期或数量过多）以及客户端处理过慢时发送 reset，客户端应重新加载数据
+pub async fn stream_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<EventStreamQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .or(query.last_event_id);
+
+    // 先订阅再查询补发的事件，避免两者之间写入的事件丢失
+    let receiver = state.events.subscribe();
+
+    let mut pending = VecDeque::new();
+    let mut replayed = HashSet::new();
+    if let Some(last_event_id) = last_event_id {
+        let repository = StreamEventRepository::new(state.pool.clone());
+        let events = repository.list_after(last_event_id, MAX_REPLAY + 1).await?;
+        let expired = !repository.can_replay_after(last_event_id).await?;
+
+        if expired || events.len() as i64 > MAX_REPLAY {
+            pending.push_back(reset_event(if expired { "expired" } else { "too_many" }));
+        } else {
+            for event in events {
+                replayed.insert(event.id);
+                pending.push_back(to_sse_event(&event));
+            }
+        }
+    }
+
+    let stream = stream::unfold(
+        EventStreamState {
+            pending,
+            replayed,
+            receiver,
+        },
+        |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((Ok(event), state));
+                }
+
+                match state.receiver.recv().await {
+                    // 已在补发中发送过
+                    Ok(event) if state.replayed.remove(&event.id) => continue,
+                    Ok(event) => return Some((Ok(to_sse_event(&event)), state)),
+                    Err(RecvError::Lagged(_)) => return Some((Ok(reset_event("lagged")), state)),
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+struct EventStreamState {
+    pending: VecDeque<Event>,
+    replayed: HashSet<i64>,
+    receiver: broadcast::Receiver<StreamEvent>,
+}
+
+fn to_sse_event(event: &StreamEvent) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .event(&event.event)
+        .data(event.payload.to_string())
+}
+
+fn reset_event(reason: &str) -> Event {
+    Event::default()
+        .event("reset")
+        .data(json!({ "reason": reason }).to_string())
+}
@@ -0,0 +1,86 @@
+use crate::{
+    auth::CurrentUser,
+    error::AppError,
+    models::{
+        Notification, NotificationPreferences, NotificationQuery, PaginatedResponse,
+        UpdateNotificationPreferencesRequest, UserSummary,
+    },
+    repositories::notifications::NotificationRepository,
+    state::AppState,
+    utils::resolve_pagination,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid as UuidType;
+
+// 获取当前用户收到的通知
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Query(query): Query<NotificationQuery>,
+) -> Result<Json<PaginatedResponse<Notification>>, AppError> {
+    let (limit, offset) = resolve_pagination(query.page, query.limit, query.offset);
+    let repository = NotificationRepository::new(state.pool);
+    let notifications = repository
+        .list(current_user.id(), &query, limit, offset)
+        .await?;
+    Ok(Json(notifications))
+}
+
+// 获取当前用户的通知偏好
+pub async fn get_notification_preferences(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> Result<Json<NotificationPreferences>, AppError> {
+    let repository = NotificationRepository::new(state.pool);
+    let preferences = repository.get_preferences(current_user.id()).await?;
+    Ok(Json(preferences))
+}
+
+// 更新当前用户的通知偏好
+pub async fn update_notification_preferences(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(request): Json<UpdateNotificationPreferencesRequest>,
+) -> Result<Json<NotificationPreferences>, AppError> {
+    let repository = NotificationRepository::new(state.pool);
+    let preferences = repository
+        .update_preferences(current_user.id(), request)
+        .await?;
+    Ok(Json(preferences))
+}
+
+// 获取工单的关注者
+pub async fn list_ticket_watchers(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Json<Vec<UserSummary>>, AppError> {
+    let repository = NotificationRepository::new(state.pool);
+    let watchers = repository.watchers(id).await?;
+    Ok(Json(watchers))
+}
+
+// 关注工单
+pub async fn watch_ticket(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+) -> Result<StatusCode, AppError> {
+    let repository = NotificationRepository::new(state.pool);
+    repository.watch(id, current_user.id()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 取消关注工单
+pub async fn unwatch_ticket(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+) -> Result<StatusCode, AppError> {
+    let repository = NotificationRepository::new(state.pool);
+    repository.unwatch(id, current_user.id()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,75 @@
+use crate::{
+    auth::CurrentUser,
+    error::AppError,
+    models::{CreateSavedViewRequest, SavedView, UpdateSavedViewRequest},
+    repositories::saved_views::SavedViewRepository,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid as UuidType;
+use validator::Validate;
+
+// 获取当前用户可见的视图（自己的与共享的）
+pub async fn list_views(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+) -> Result<Json<Vec<SavedView>>, AppError> {
+    let repository = SavedViewRepository::new(state.pool);
+    let views = repository.list_visible(current_user.id()).await?;
+    Ok(Json(views))
+}
+
+// 获取单个视图
+pub async fn get_view(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(id): Path<UuidType>,
+) -> Result<Json<SavedView>, AppError> {
+    let repository = SavedViewRepository::new(state.pool);
+    let view = repository.get_visible(id, current_user.id()).await?;
+    Ok(Json(view))
+}
+
+// 创建视图
+pub async fn create_view(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(request): Json<CreateSavedViewRequest>,
+) -> Result<Json<SavedView>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = SavedViewRepository::new(state.pool);
+    let view = repository.create(current_user.id(), request).await?;
+    Ok(Json(view))
+}
+
+// 更新视图
+pub async fn update_view(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(id): Path<UuidType>,
+    Json(request): Json<UpdateSavedViewRequest>,
+) -> Result<Json<SavedView>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = SavedViewRepository::new(state.pool);
+    let view = repository.update(id, current_user.id(), request).await?;
+    Ok(Json(view))
+}
+
+// 删除视图
+pub async fn delete_view(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Path(id): Path<UuidType>,
+) -> Result<StatusCode, AppError> {
+    let repository = SavedViewRepository::new(state.pool);
+    repository.delete(id, current_user.id()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
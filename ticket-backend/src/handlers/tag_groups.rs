@@ -0,0 +1,69 @@
+use crate::{
+    error::AppError,
+    models::{CreateTagGroupRequest, TagGroup, TagGroupWithTags, UpdateTagGroupRequest},
+    repositories::tag_groups::TagGroupRepository,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid as UuidType;
+use validator::Validate;
+
+// 获取所有标签分组
+pub async fn list_tag_groups(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TagGroupWithTags>>, AppError> {
+    let repository = TagGroupRepository::new(state.pool);
+    let groups = repository.list().await?;
+    Ok(Json(groups))
+}
+
+// 获取单个标签分组
+pub async fn get_tag_group(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Json<TagGroupWithTags>, AppError> {
+    let repository = TagGroupRepository::new(state.pool);
+    let group = repository.get(id).await?;
+    Ok(Json(group))
+}
+
+// 创建标签分组
+pub async fn create_tag_group(
+    State(state): State<AppState>,
+    Json(request): Json<CreateTagGroupRequest>,
+) -> Result<Json<TagGroup>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = TagGroupRepository::new(state.pool);
+    let group = repository.create(request).await?;
+    Ok(Json(group))
+}
+
+// 更新标签分组
+pub async fn update_tag_group(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    Json(request): Json<UpdateTagGroupRequest>,
+) -> Result<Json<TagGroup>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = TagGroupRepository::new(state.pool);
+    let group = repository.update(id, request).await?;
+    Ok(Json(group))
+}
+
+// 删除标签分组
+pub async fn delete_tag_group(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<StatusCode, AppError> {
+    let repository = TagGroupRepository::new(state.pool);
+    repository.delete(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
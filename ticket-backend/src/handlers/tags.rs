@@ -1,7 +1,14 @@
 use crate::{
+    auth::CurrentUser,
     error::AppError,
-    models::{CreateTagRequest, Tag, TagWithCount, UpdateTagRequest},
+    models::{
+        CreateTagRequest, MergeTagsRequest, MoveTagRequest, SetTagGroupRequest, Tag, TagAlias,
+        TagMergeResult, TagNode, TagWithCount, UpdateTagRequest,
+    },
+    precondition::{IfMatch, WithETag},
     repositories::tags::TagRepository,
+    state::AppState,
+    utils::color,
 };
 use axum::{
     extract::{Path, Query, State},
@@ -9,24 +16,28 @@ use axum::{
     response::Json,
 };
 use serde::Deserialize;
-use sqlx::PgPool;
 use uuid::Uuid as UuidType;
 use validator::Validate;
 
 // 获取所有标签
 pub async fn list_tags(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Query(query): Query<ListTagsQuery>,
 ) -> Result<Json<ListTagsResponse>, AppError> {
-    let repository = TagRepository::new(pool);
+    let repository = TagRepository::new(state.pool);
 
-    if query.with_counts.unwrap_or(false) {
-        let tags = repository.list_with_counts().await?;
-        Ok(Json(ListTagsResponse::WithCounts(tags)))
+    let data = if query.with_counts.unwrap_or(false) {
+        TagList::WithCounts(repository.list_with_counts().await?)
     } else {
-        let tags = repository.list().await?;
-        Ok(Json(ListTagsResponse::Simple(tags)))
-    }
+        TagList::Simple(repository.list().await?)
+    };
+
+    let total = match &data {
+        TagList::Simple(tags) => tags.len(),
+        TagList::WithCounts(tags) => tags.len(),
+    };
+
+    Ok(Json(ListTagsResponse { data, total }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,73 +45,154 @@ pub struct ListTagsQuery {
     pub with_counts: Option<bool>,
 }
 
+#[derive(Debug, serde::Serialize)]
+pub struct ListTagsResponse {
+    pub data: TagList,
+    pub total: usize,
+}
+
 #[derive(Debug, serde::Serialize)]
 #[serde(untagged)]
-pub enum ListTagsResponse {
+pub enum TagList {
     Simple(Vec<Tag>),
     WithCounts(Vec<TagWithCount>),
 }
 
 // 获取单个标签
 pub async fn get_tag(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(id): Path<UuidType>,
-) -> Result<Json<Tag>, AppError> {
-    let repository = TagRepository::new(pool);
+) -> Result<WithETag<Tag>, AppError> {
+    let repository = TagRepository::new(state.pool);
     let tag = repository.get_by_id(id).await?;
-    Ok(Json(tag))
+    Ok(WithETag::new(tag.version, tag))
 }
 
 // 创建标签
 pub async fn create_tag(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Json(request): Json<CreateTagRequest>,
-) -> Result<Json<Tag>, AppError> {
+) -> Result<WithETag<Tag>, AppError> {
     // 验证请求数据
-    request.validate()
-        .map_err(|e| AppError::Validation(e))?;
+    request.validate()?;
 
     // 检查名称是否已存在
-    let repository = TagRepository::new(pool.clone());
+    let repository = TagRepository::new(state.pool);
     if repository.name_exists(&request.name, None).await? {
         return Err(AppError::conflict("标签名称"));
     }
 
     let tag = repository.create(request).await?;
-    Ok(Json(tag))
+    Ok(WithETag::new(tag.version, tag))
 }
 
-// 更新标签
+// 更新标签（携带 If-Match 时校验版本）
 pub async fn update_tag(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(id): Path<UuidType>,
+    if_match: Option<IfMatch>,
     Json(request): Json<UpdateTagRequest>,
-) -> Result<Json<Tag>, AppError> {
+) -> Result<WithETag<Tag>, AppError> {
     // 验证请求数据
-    request.validate()
-        .map_err(|e| AppError::Validation(e))?;
+    request.validate()?;
+
+    let repository = TagRepository::new(state.pool);
+    let tag = repository.update(id, request, if_match.as_ref()).await?;
+    Ok(WithETag::new(tag.version, tag))
+}
+
+// 移动标签（携带 If-Match 时校验版本）
+pub async fn move_tag(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    if_match: Option<IfMatch>,
+    Json(request): Json<MoveTagRequest>,
+) -> Result<WithETag<Tag>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    let tag = repository
+        .move_to(id, request.parent_id, if_match.as_ref())
+        .await?;
+    Ok(WithETag::new(tag.version, tag))
+}
+
+// 设置标签所属分组（携带 If-Match 时校验版本）
+pub async fn set_tag_group(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    if_match: Option<IfMatch>,
+    Json(request): Json<SetTagGroupRequest>,
+) -> Result<WithETag<Tag>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    let tag = repository
+        .set_group(id, request.group_id, if_match.as_ref())
+        .await?;
+    Ok(WithETag::new(tag.version, tag))
+}
+
+// 将其他标签合并到指定标签，源标签名称保留为别名
+pub async fn merge_tags(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+    Json(request): Json<MergeTagsRequest>,
+) -> Result<Json<TagMergeResult>, AppError> {
+    request.validate()?;
+
+    let repository = TagRepository::new(state.pool);
+    let result = repository
+        .merge(id, &request.source_ids, Some(current_user.id()))
+        .await?;
+    Ok(Json(result))
+}
+
+// 获取标签的别名
+pub async fn get_tag_aliases(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Json<Vec<TagAlias>>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    repository.get_by_id(id).await?;
+    let aliases = repository.aliases(id).await?;
+    Ok(Json(aliases))
+}
+
+// 获取完整的标签树
+pub async fn get_tag_tree(State(state): State<AppState>) -> Result<Json<Vec<TagNode>>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    let nodes = repository.tree(None).await?;
+    Ok(Json(nodes))
+}
 
-    let repository = TagRepository::new(pool);
-    let tag = repository.update(id, request).await?;
-    Ok(Json(tag))
+// 获取以指定标签为根的子树
+pub async fn get_tag_subtree(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Json<Vec<TagNode>>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    let nodes = repository.tree(Some(id)).await?;
+    Ok(Json(nodes))
 }
 
-// 删除标签
+// 删除标签（携带 If-Match 时校验版本）
 pub async fn delete_tag(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+    if_match: Option<IfMatch>,
 ) -> Result<StatusCode, AppError> {
-    let repository = TagRepository::new(pool);
-    repository.delete(id).await?;
+    let repository = TagRepository::new(state.pool);
+    repository
+        .delete(id, Some(current_user.id()), if_match.as_ref())
+        .await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
 // 搜索标签
 pub async fn search_tags(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
 ) -> Result<Json<Vec<Tag>>, AppError> {
-    let repository = TagRepository::new(pool);
+    let repository = TagRepository::new(state.pool);
     let tags = repository.search(&query.q).await?;
     Ok(Json(tags))
 }
@@ -112,10 +204,10 @@ pub struct SearchQuery {
 
 // 获取热门标签
 pub async fn get_popular_tags(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Query(query): Query<PopularTagsQuery>,
 ) -> Result<Json<Vec<TagWithCount>>, AppError> {
-    let repository = TagRepository::new(pool);
+    let repository = TagRepository::new(state.pool);
     let tags = repository.get_popular(query.limit).await?;
     Ok(Json(tags))
 }
@@ -127,57 +219,32 @@ pub struct PopularTagsQuery {
 
 // 获取工单的标签
 pub async fn get_ticket_tags(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(ticket_id): Path<UuidType>,
 ) -> Result<Json<Vec<Tag>>, AppError> {
-    let repository = TagRepository::new(pool);
+    let repository = TagRepository::new(state.pool);
     let tags = repository.get_ticket_tags(ticket_id).await?;
     Ok(Json(tags))
 }
 
 // 为工单设置标签
 pub async fn set_ticket_tags(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(ticket_id): Path<UuidType>,
+    current_user: CurrentUser,
+    if_match: Option<IfMatch>,
     Json(request): Json<SetTicketTagsRequest>,
-) -> Result<Json<Vec<Tag>>, AppError> {
-    // 验证所有标签ID都存在
-    let tag_repository = TagRepository::new(pool.clone());
-    for tag_id in &request.tag_ids {
-        if tag_repository.get_by_id(*tag_id).await.is_err() {
-            return Err(AppError::not_found("标签"));
-        }
-    }
-
-    // 先删除现有关联
-    sqlx::query!("DELETE FROM ticket_tags WHERE ticket_id = $1", ticket_id)
-        .execute(&pool)
+) -> Result<WithETag<Vec<Tag>>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    let (version, tags) = repository
+        .set_ticket_tags(
+            ticket_id,
+            &request.tag_ids,
+            Some(current_user.id()),
+            if_match.as_ref(),
+        )
         .await?;
-
-    // 添加新关联
-    if !request.tag_ids.is_empty() {
-        let mut query_builder = sqlx::QueryBuilder::new("INSERT INTO ticket_tags (ticket_id, tag_id) VALUES ");
-
-        let mut first = true;
-        for tag_id in &request.tag_ids {
-            if !first {
-                query_builder.push(", ");
-            }
-            query_builder.push("(");
-            query_builder.push_bind(ticket_id);
-            query_builder.push(", ");
-            query_builder.push_bind(*tag_id);
-            query_builder.push(")");
-            first = false;
-        }
-
-        query_builder.push(" ON CONFLICT DO NOTHING");
-        query_builder.build().execute(&pool).await?;
-    }
-
-    // 返回更新后的标签列表
-    let updated_tags = tag_repository.get_ticket_tags(ticket_id).await?;
-    Ok(Json(updated_tags))
+    Ok(WithETag::new(version, tags))
 }
 
 #[derive(Debug, Deserialize)]
@@ -187,85 +254,66 @@ pub struct SetTicketTagsRequest {
 
 // 为工单添加单个标签
 pub async fn add_ticket_tag(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path((ticket_id, tag_id)): Path<(UuidType, UuidType)>,
-) -> Result<Json<Vec<Tag>>, AppError> {
-    // 验证标签存在
-    let tag_repository = TagRepository::new(pool.clone());
-    tag_repository.get_by_id(tag_id).await?;
-
-    // 添加关联
-    sqlx::query!(
-        "INSERT INTO ticket_tags (ticket_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
-        ticket_id,
-        tag_id
-    )
-    .execute(&pool)
-    .await?;
-
-    // 返回更新后的标签列表
-    let updated_tags = tag_repository.get_ticket_tags(ticket_id).await?;
-    Ok(Json(updated_tags))
+    current_user: CurrentUser,
+    if_match: Option<IfMatch>,
+) -> Result<WithETag<Vec<Tag>>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    let (version, tags) = repository
+        .add_ticket_tag(
+            ticket_id,
+            tag_id,
+            Some(current_user.id()),
+            if_match.as_ref(),
+        )
+        .await?;
+    Ok(WithETag::new(version, tags))
 }
 
 // 从工单中移除标签
 pub async fn remove_ticket_tag(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path((ticket_id, tag_id)): Path<(UuidType, UuidType)>,
-) -> Result<Json<Vec<Tag>>, AppError> {
-    // 删除关联
-    sqlx::query!(
-        "DELETE FROM ticket_tags WHERE ticket_id = $1 AND tag_id = $2",
-        ticket_id,
-        tag_id
+    current_user: CurrentUser,
+    if_match: Option<IfMatch>,
+) -> Result<WithETag<Vec<Tag>>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    let (version, tags) = repository
+        .remove_ticket_tag(
+            ticket_id,
+            tag_id,
+            Some(current_user.id()),
+            if_match.as_ref(),
+        )
+        .await?;
+    Ok(WithETag::new(version, tags))
+}
+
+// 获取自动分配颜色使用的调色板
+pub async fn get_tag_palette() -> Json<Vec<TagColor>> {
+    Json(
+        color::PALETTE
+            .iter()
+            .map(|color| TagColor {
+                color,
+                text_color: color::text_color(color),
+            })
+            .collect(),
     )
-    .execute(&pool)
-    .await?;
+}
 
-    // 返回更新后的标签列表
-    let tag_repository = TagRepository::new(pool);
-    let updated_tags = tag_repository.get_ticket_tags(ticket_id).await?;
-    Ok(Json(updated_tags))
+#[derive(Debug, serde::Serialize)]
+pub struct TagColor {
+    pub color: &'static str,
+    pub text_color: &'static str,
 }
 
 // 获取标签统计信息
-pub async fn get_tag_stats(
-    State(pool): State<PgPool>,
-) -> Result<Json<TagStats>, AppError> {
-    let total_tags: i64 = sqlx::query_scalar!("SELECT COUNT(*) FROM tags")
-        .fetch_one(&pool)
-        .await?;
-
-    let total_usage: i64 = sqlx::query_scalar!("SELECT COUNT(*) FROM ticket_tags")
-        .fetch_one(&pool)
-        .await?;
-
-    let unused_tags: i64 = sqlx::query_scalar!(
-        r#"
-        SELECT COUNT(*)
-        FROM tags t
-        LEFT JOIN ticket_tags tt ON t.id = tt.tag_id
-        WHERE tt.tag_id IS NULL
-        "#
-    )
-    .fetch_one(&pool)
-    .await?;
-
-    let most_used_tags = sqlx::query_as!(
-        TagWithCount,
-        r#"
-        SELECT
-            t.id, t.name, t.color, t.created_at, t.updated_at,
-            COUNT(tt.tag_id) as "ticket_count!"
-        FROM tags t
-        LEFT JOIN ticket_tags tt ON t.id = tt.tag_id
-        GROUP BY t.id, t.name, t.color, t.created_at, t.updated_at
-        ORDER BY COUNT(tt.tag_id) DESC
-        LIMIT 5
-        "#
-    )
-    .fetch_all(&pool)
-    .await?;
+pub async fn get_tag_stats(State(state): State<AppState>) -> Result<Json<TagStats>, AppError> {
+    let repository = TagRepository::new(state.pool);
+    let (total_tags, total_usage, unused_tags) = repository.usage_counts().await?;
+    let most_used_tags = repository.get_popular(Some(5)).await?;
 
     Ok(Json(TagStats {
         total_tags,
@@ -281,4 +329,4 @@ pub struct TagStats {
     pub total_usage: i64,
     pub unused_tags: i64,
     pub most_used_tags: Vec<TagWithCount>,
-}
\ No newline at end of file
+}
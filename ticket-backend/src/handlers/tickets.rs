@@ -1,179 +1,356 @@
 use crate::{
+    auth::CurrentUser,
     error::AppError,
-    models::{CreateTicketRequest, Ticket, TicketQuery, TicketWithDetails, UpdateTicketRequest},
-    repositories::tickets::TicketRepository,
+    models::{
+        CreateTicketRequest, PaginatedResponse, Priority, StatusCategory, TagRef,
+        TicketEventWithActor, TicketHistoryQuery, TicketQuery, TicketSearchHit, TicketStatus,
+        TicketWithDetails, TicketWithTags, TrashQuery, UpdateTicketRequest, ViewFilters,
+    },
+    precondition::{IfMatch, WithETag},
+    repositories::{
+        comments::CommentRepository, saved_views::SavedViewRepository,
+        ticket_events::TicketEventRepository, tickets::TicketRepository,
+    },
+    state::AppState,
+    utils::{query_language::TicketFilter, resolve_pagination, sort::parse_sort, MAX_PAGE_SIZE},
 };
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
 };
 use serde::Deserialize;
-use sqlx::PgPool;
+use std::collections::BTreeMap;
 use uuid::Uuid as UuidType;
 use validator::Validate;
 
+// 搜索默认返回的结果数
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+
 // 获取工单列表查询参数
 #[derive(Debug, Deserialize)]
 pub struct ListTicketsQuery {
-    pub status: Option<crate::models::TicketStatus>,
-    pub priority: Option<crate::models::Priority>,
+    pub status: Option<TicketStatus>,
+    pub status_category: Option<StatusCategory>,
+    pub workflow_id: Option<UuidType>,
+    pub priority: Option<Priority>,
     pub assignee_id: Option<UuidType>,
     pub reporter_id: Option<UuidType>,
     pub tag_id: Option<UuidType>,
+    pub tag_ids: Option<String>,   // 逗号分隔的标签ID，等同于 tags_any
+    pub tags_all: Option<String>,  // 逗号分隔的标签ID或名称，须包含全部标签
+    pub tags_any: Option<String>,  // 包含任一标签
+    pub tags_none: Option<String>, // 不包含其中任何标签
+    pub include_descendant_tags: Option<bool>, // 标签条件是否包含子标签
     pub search: Option<String>,
+    pub q: Option<String>, // 查询语言，如 `status:open priority>=high tag:backend`
+    pub view: Option<UuidType>, // 保存的视图ID
+    pub cursor: Option<String>, // 游标分页，首页传空字符串
+    pub count: Option<bool>, // 游标分页时是否返回总数，默认返回
+    pub page: Option<i64>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    pub sort: Option<String>, // 多字段排序，如 `-priority,created_at`
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
 }
 
 // 获取工单列表
 pub async fn list_tickets(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
+    current_user: CurrentUser,
     Query(query): Query<ListTicketsQuery>,
-) -> Result<Json<crate::models::PaginatedResponse<Ticket>>, AppError> {
-    let repository = TicketRepository::new(pool);
+) -> Result<Response, AppError> {
+    // page 优先于 offset，兼容前端的分页参数
+    let (limit, offset) = resolve_pagination(query.page, query.limit, query.offset);
+
+    // 应用保存的视图，显式传入的参数优先于视图中的条件
+    let view = match query.view {
+        Some(view_id) => {
+            SavedViewRepository::new(state.pool.clone())
+                .get_visible(view_id, current_user.id())
+                .await?
+                .filters
+                .0
+        }
+        None => ViewFilters::default(),
+    };
+
+    // 标签条件：tag_id 并入 tags_all，tag_ids 并入 tags_any
+    let tag_refs = |explicit: Option<String>, saved: Option<String>| {
+        explicit
+            .or(saved)
+            .map(|raw| TagRef::parse_list(&raw))
+            .unwrap_or_default()
+    };
+    let mut tags_all = tag_refs(query.tags_all, view.tags_all);
+    tags_all.extend(query.tag_id.map(TagRef::Id));
+    let mut tags_any = tag_refs(query.tags_any, view.tags_any);
+    let tag_ids = match query.tag_ids.as_deref() {
+        Some(raw) => Some(parse_uuid_list(raw)?),
+        None => view.tag_ids,
+    };
+    tags_any.extend(tag_ids.unwrap_or_default().into_iter().map(TagRef::Id));
+    let tags_none = tag_refs(query.tags_none, view.tags_none);
+
+    // 请求中的排序参数优先于视图中的排序
+    let sort = match parse_sort(
+        query.sort.as_deref(),
+        query.sort_by.as_deref(),
+        query.sort_order.as_deref(),
+    )? {
+        Some(keys) => keys,
+        None => parse_sort(
+            view.sort.as_deref(),
+            view.sort_by.as_deref(),
+            view.sort_order.as_deref(),
+        )?
+        .unwrap_or_default(),
+    };
+
+    // 视图与请求中的查询语言条件同时生效
+    let mut filter: Option<TicketFilter> = None;
+    for q in [view.q.as_deref(), query.q.as_deref()]
+        .into_iter()
+        .flatten()
+    {
+        let parsed = TicketFilter::parse(q, current_user.id())?;
+        filter
+            .get_or_insert_with(TicketFilter::default)
+            .clauses
+            .extend(parsed.clauses);
+    }
+
     let ticket_query = TicketQuery {
-        status: query.status,
-        priority: query.priority,
-        assignee_id: query.assignee_id,
-        reporter_id: query.reporter_id,
-        tag_id: query.tag_id,
-        search: query.search,
-        limit: query.limit,
-        offset: query.offset,
-        sort_by: query.sort_by,
-        sort_order: query.sort_order,
+        status: query.status.or(view.status),
+        status_category: query.status_category.or(view.status_category),
+        workflow_id: query.workflow_id.or(view.workflow_id),
+        priority: query.priority.or(view.priority),
+        assignee_id: query.assignee_id.or(view.assignee_id),
+        reporter_id: query.reporter_id.or(view.reporter_id),
+        tags_all,
+        tags_any,
+        tags_none,
+        include_descendant_tags: query
+            .include_descendant_tags
+            .or(view.include_descendant_tags)
+            .unwrap_or(false),
+        search: query.search.or(view.search),
+        filter,
+        limit: Some(limit),
+        offset: Some(offset),
+        sort,
     };
 
-    let result = repository.list(ticket_query).await?;
-    Ok(Json(result))
+    let repository = TicketRepository::new(state.pool);
+
+    // 携带 cursor 时使用游标分页，否则保持原有的 page/offset 分页
+    match query.cursor {
+        Some(cursor) => {
+            let page = repository
+                .list_after(ticket_query, &cursor, query.count.unwrap_or(true))
+                .await?;
+            Ok(Json(page).into_response())
+        }
+        None => {
+            let result = repository.list(ticket_query).await?;
+            Ok(Json(result).into_response())
+        }
+    }
 }
 
 // 获取单个工单详情（带标签和评论）
 pub async fn get_ticket(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(id): Path<UuidType>,
-) -> Result<Json<TicketWithDetails>, AppError> {
-    let ticket_repository = TicketRepository::new(pool.clone());
+) -> Result<WithETag<TicketWithDetails>, AppError> {
+    let ticket_repository = TicketRepository::new(state.pool.clone());
 
     // 获取带标签的工单
     let ticket_with_tags = ticket_repository.get_with_tags(id).await?;
 
     // 获取评论
-    let comments = sqlx::query_as!(
-        crate::models::Comment,
-        r#"
-        SELECT id, ticket_id, author_id, content, created_at, updated_at
-        FROM comments
-        WHERE ticket_id = $1
-        ORDER BY created_at ASC
-        "#,
-        id
-    )
-    .fetch_all(&pool)
-    .await?;
-
-    Ok(Json(TicketWithDetails {
-        ticket: ticket_with_tags.ticket,
-        tags: ticket_with_tags.tags,
-        comments,
-    }))
+    let comments = CommentRepository::new(state.pool)
+        .list_all_for_ticket(id)
+        .await?;
+
+    Ok(WithETag::new(
+        ticket_with_tags.ticket.version,
+        TicketWithDetails {
+            ticket: ticket_with_tags.ticket,
+            tags: ticket_with_tags.tags,
+            assignee: ticket_with_tags.assignee,
+            reporter: ticket_with_tags.reporter,
+            comments,
+        },
+    ))
 }
 
 // 创建工单
 pub async fn create_ticket(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
+    current_user: CurrentUser,
     Json(request): Json<CreateTicketRequest>,
-) -> Result<Json<Ticket>, AppError> {
+) -> Result<WithETag<TicketWithTags>, AppError> {
     // 验证请求数据
-    request.validate()
-        .map_err(|e| AppError::Validation(e))?;
+    request.validate()?;
 
-    let repository = TicketRepository::new(pool);
-    let ticket = repository.create(request).await?;
+    let repository = TicketRepository::new(state.pool);
+    let ticket = repository.create(request, Some(current_user.id())).await?;
 
-    Ok(Json(ticket))
+    Ok(WithETag::new(ticket.ticket.version, ticket))
 }
 
-// 更新工单
+// 更新工单（携带 If-Match 时校验版本）
 pub async fn update_ticket(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+    if_match: Option<IfMatch>,
     Json(request): Json<UpdateTicketRequest>,
-) -> Result<Json<Ticket>, AppError> {
+) -> Result<WithETag<TicketWithTags>, AppError> {
     // 验证请求数据
-    request.validate()
-        .map_err(|e| AppError::Validation(e))?;
+    request.validate()?;
 
-    let repository = TicketRepository::new(pool);
-    let ticket = repository.update(id, request).await?;
+    let repository = TicketRepository::new(state.pool);
+    let ticket = repository
+        .update(id, request, Some(current_user.id()), if_match.as_ref())
+        .await?;
 
-    Ok(Json(ticket))
+    Ok(WithETag::new(ticket.ticket.version, ticket))
 }
 
-// 删除工单
+// 删除工单（携带 If-Match 时校验版本）
 pub async fn delete_ticket(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+    if_match: Option<IfMatch>,
 ) -> Result<StatusCode, AppError> {
-    let repository = TicketRepository::new(pool);
-    repository.delete(id).await?;
+    let repository = TicketRepository::new(state.pool);
+    repository
+        .delete(id, Some(current_user.id()), if_match.as_ref())
+        .await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
-// 搜索工单
+// 重新打开已解决或已关闭的工单
+pub async fn reopen_ticket(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+) -> Result<WithETag<TicketWithTags>, AppError> {
+    let repository = TicketRepository::new(state.pool);
+    let ticket = repository.reopen(id, Some(current_user.id())).await?;
+    Ok(WithETag::new(ticket.ticket.version, ticket))
+}
+
+// 从回收站恢复工单
+pub async fn restore_ticket(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    current_user: CurrentUser,
+) -> Result<WithETag<TicketWithTags>, AppError> {
+    let repository = TicketRepository::new(state.pool);
+    let ticket = repository.restore(id, Some(current_user.id())).await?;
+    Ok(WithETag::new(ticket.ticket.version, ticket))
+}
+
+// 分页获取回收站中的工单
+pub async fn list_trash(
+    State(state): State<AppState>,
+    Query(query): Query<TrashQuery>,
+) -> Result<Json<PaginatedResponse<TicketWithTags>>, AppError> {
+    let (limit, offset) = resolve_pagination(query.page, query.limit, query.offset);
+
+    let repository = TicketRepository::new(state.pool);
+    let trash = repository.list_deleted(limit, offset).await?;
+    Ok(Json(trash))
+}
+
+// 分页获取工单变更历史
+pub async fn get_ticket_history(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    Query(query): Query<TicketHistoryQuery>,
+) -> Result<Json<PaginatedResponse<TicketEventWithActor>>, AppError> {
+    let (limit, offset) = resolve_pagination(query.page, query.limit, query.offset);
+
+    let repository = TicketEventRepository::new(state.pool);
+    let history = repository.list(id, limit, offset).await?;
+    Ok(Json(history))
+}
+
+// 全文搜索工单（按相关度排序，附带高亮片段）
 pub async fn search_tickets(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
-) -> Result<Json<Vec<Ticket>>, AppError> {
-    let repository = TicketRepository::new(pool);
-    let tickets = repository.search(&query.q).await?;
-    Ok(Json(tickets))
+) -> Result<Json<Vec<TicketSearchHit>>, AppError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let repository = TicketRepository::new(state.pool);
+    let hits = repository.search(&query.q, limit).await?;
+    Ok(Json(hits))
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SearchQuery {
     pub q: String,
+    pub limit: Option<i64>,
 }
 
 // 批量更新工单状态
 pub async fn bulk_update_status(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
+    current_user: CurrentUser,
     Json(request): Json<BulkUpdateStatusRequest>,
 ) -> Result<Json<BulkUpdateResult>, AppError> {
-    let repository = TicketRepository::new(pool);
-    let mut updated_count = 0;
-    let mut errors = Vec::new();
-
-    for ticket_id in request.ticket_ids {
-        let update_request = UpdateTicketRequest {
-            title: None,
-            description: None,
-            status: Some(request.status.clone()),
-            priority: None,
-            assignee_id: None,
-            tag_ids: None,
-        };
-
-        match repository.update(ticket_id, update_request).await {
-            Ok(_) => updated_count += 1,
-            Err(e) => errors.push(format!("{}: {}", ticket_id, e)),
-        }
+    if request.ticket_ids.is_empty() {
+        return Err(AppError::bad_request("ticket_ids 不能为空"));
     }
 
+    let repository = TicketRepository::new(state.pool);
+    let outcome = repository
+        .bulk_update_status(
+            &request.ticket_ids,
+            request.status.clone(),
+            Some(current_user.id()),
+        )
+        .await?;
+
+    let errors: Vec<String> = request
+        .ticket_ids
+        .iter()
+        .filter(|id| !outcome.updated.contains(id))
+        .map(
+            |id| match outcome.rejected.iter().find(|(rejected, _)| rejected == id) {
+                Some((_, current)) => format!(
+                    "{}: 不允许将状态从 {} 变更为 {}",
+                    id, current, request.status
+                ),
+                None => format!("{}: 工单未找到", id),
+            },
+        )
+        .collect();
+
     Ok(Json(BulkUpdateResult {
-        updated_count,
+        updated_count: outcome.updated.len(),
         total_count: request.ticket_ids.len(),
-        errors: if errors.is_empty() { None } else { Some(errors) },
+        errors: if errors.is_empty() {
+            None
+        } else {
+            Some(errors)
+        },
     }))
 }
 
 #[derive(Debug, Deserialize)]
 pub struct BulkUpdateStatusRequest {
     pub ticket_ids: Vec<UuidType>,
-    pub status: crate::models::TicketStatus,
+    pub status: TicketStatus,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -183,148 +360,45 @@ pub struct BulkUpdateResult {
     pub errors: Option<Vec<String>>,
 }
 
-// 添加评论到工单
-pub async fn add_comment(
-    State(pool): State<PgPool>,
-    Path(ticket_id): Path<UuidType>,
-    Json(request): Json<crate::models::CreateCommentRequest>,
-) -> Result<Json<crate::models::Comment>, AppError> {
-    // 验证请求数据
-    request.validate()
-        .map_err(|e| AppError::Validation(e))?;
-
-    // 验证工单是否存在
-    let ticket_repository = TicketRepository::new(pool.clone());
-    if ticket_repository.get_by_id(ticket_id).await.is_err() {
-        return Err(AppError::not_found("工单"));
-    }
-
-    let comment_id = UuidType::new_v4();
-    let now = chrono::Utc::now();
-
-    let comment = sqlx::query_as!(
-        crate::models::Comment,
-        r#"
-        INSERT INTO comments (id, ticket_id, author_id, content, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $5)
-        RETURNING id, ticket_id, author_id, content, created_at, updated_at
-        "#,
-        comment_id,
-        ticket_id,
-        request.author_id,
-        request.content,
-        now
-    )
-    .fetch_one(&pool)
-    .await?;
-
-    Ok(Json(comment))
-}
-
 // 获取工单统计信息
 pub async fn get_ticket_stats(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Query(query): Query<TicketStatsQuery>,
 ) -> Result<Json<TicketStats>, AppError> {
-    let mut where_conditions = Vec::new();
-
-    if let Some(assignee_id) = query.assignee_id {
-        where_conditions.push(format!("assignee_id = '{}'", assignee_id));
+    let repository = TicketRepository::new(state.pool);
+
+    // 获取各状态的数量（状态由工作流定义，按状态标识汇总）
+    let total_by_status = repository
+        .count_by_status(query.assignee_id)
+        .await?
+        .into_iter()
+        .collect();
+
+    // 获取各状态分类的数量
+    let mut total_by_category = CategoryStats::default();
+    for (category, count) in repository.count_by_category(query.assignee_id).await? {
+        match category {
+            StatusCategory::Open => total_by_category.open = count,
+            StatusCategory::Active => total_by_category.active = count,
+            StatusCategory::Done => total_by_category.done = count,
+        }
     }
 
-    let where_clause = if where_conditions.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", where_conditions.join(" AND "))
-    };
-
-    // 获取各状态的数量
-    let status_counts = sqlx::query!(
-        r#"
-        SELECT status, COUNT(*) as count
-        FROM tickets
-        {}
-        GROUP BY status
-        "#,
-        where_clause
-    )
-    .fetch_all(&pool)
-    .await?;
-
     // 获取各优先级的数量
-    let priority_counts = sqlx::query!(
-        r#"
-        SELECT priority, COUNT(*) as count
-        FROM tickets
-        {}
-        GROUP BY priority
-        "#,
-        where_clause
-    )
-    .fetch_all(&pool)
-    .await?;
-
-    let total_open = status_counts
-        .iter()
-        .filter(|r| matches!(r.status, crate::models::TicketStatus::Open))
-        .map(|r| r.count.unwrap_or(0))
-        .sum::<i64>();
-
-    let total_in_progress = status_counts
-        .iter()
-        .filter(|r| matches!(r.status, crate::models::TicketStatus::InProgress))
-        .map(|r| r.count.unwrap_or(0))
-        .sum::<i64>();
-
-    let total_resolved = status_counts
-        .iter()
-        .filter(|r| matches!(r.status, crate::models::TicketStatus::Resolved))
-        .map(|r| r.count.unwrap_or(0))
-        .sum::<i64>();
-
-    let total_closed = status_counts
-        .iter()
-        .filter(|r| matches!(r.status, crate::models::TicketStatus::Closed))
-        .map(|r| r.count.unwrap_or(0))
-        .sum::<i64>();
-
-    let total_urgent = priority_counts
-        .iter()
-        .filter(|r| matches!(r.priority, crate::models::Priority::Urgent))
-        .map(|r| r.count.unwrap_or(0))
-        .sum::<i64>();
-
-    let total_high = priority_counts
-        .iter()
-        .filter(|r| matches!(r.priority, crate::models::Priority::High))
-        .map(|r| r.count.unwrap_or(0))
-        .sum::<i64>();
-
-    let total_medium = priority_counts
-        .iter()
-        .filter(|r| matches!(r.priority, crate::models::Priority::Medium))
-        .map(|r| r.count.unwrap_or(0))
-        .sum::<i64>();
-
-    let total_low = priority_counts
-        .iter()
-        .filter(|r| matches!(r.priority, crate::models::Priority::Low))
-        .map(|r| r.count.unwrap_or(0))
-        .sum::<i64>();
+    let mut total_by_priority = PriorityStats::default();
+    for (priority, count) in repository.count_by_priority(query.assignee_id).await? {
+        match priority {
+            Priority::Urgent => total_by_priority.urgent = count,
+            Priority::High => total_by_priority.high = count,
+            Priority::Medium => total_by_priority.medium = count,
+            Priority::Low => total_by_priority.low = count,
+        }
+    }
 
     Ok(Json(TicketStats {
-        total_by_status: StatusStats {
-            open: total_open,
-            in_progress: total_in_progress,
-            resolved: total_resolved,
-            closed: total_closed,
-        },
-        total_by_priority: PriorityStats {
-            urgent: total_urgent,
-            high: total_high,
-            medium: total_medium,
-            low: total_low,
-        },
+        total_by_status,
+        total_by_category,
+        total_by_priority,
     }))
 }
 
@@ -335,22 +409,34 @@ pub struct TicketStatsQuery {
 
 #[derive(Debug, serde::Serialize)]
 pub struct TicketStats {
-    pub total_by_status: StatusStats,
+    pub total_by_status: BTreeMap<TicketStatus, i64>,
+    pub total_by_category: CategoryStats,
     pub total_by_priority: PriorityStats,
 }
 
-#[derive(Debug, serde::Serialize)]
-pub struct StatusStats {
+#[derive(Debug, Default, serde::Serialize)]
+pub struct CategoryStats {
     pub open: i64,
-    pub in_progress: i64,
-    pub resolved: i64,
-    pub closed: i64,
+    pub active: i64,
+    pub done: i64,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Default, serde::Serialize)]
 pub struct PriorityStats {
     pub urgent: i64,
     pub high: i64,
     pub medium: i64,
     pub low: i64,
-}
\ No newline at end of file
+}
+
+// 解析逗号分隔的UUID列表
+fn parse_uuid_list(raw: &str) -> Result<Vec<UuidType>, AppError> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<UuidType>()
+                .map_err(|_| AppError::bad_request(format!("无效的标签ID: {}", s)))
+        })
+        .collect()
+}
@@ -0,0 +1,69 @@
+use crate::{
+    error::AppError,
+    models::{CreateUserRequest, PaginatedResponse, UpdateUserRequest, User, UserQuery},
+    repositories::users::UserRepository,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use uuid::Uuid as UuidType;
+use validator::Validate;
+
+// 获取用户列表
+pub async fn list_users(
+    State(state): State<AppState>,
+    Query(query): Query<UserQuery>,
+) -> Result<Json<PaginatedResponse<User>>, AppError> {
+    let repository = UserRepository::new(state.pool);
+    let users = repository.list(query).await?;
+    Ok(Json(users))
+}
+
+// 获取单个用户
+pub async fn get_user(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Json<User>, AppError> {
+    let repository = UserRepository::new(state.pool);
+    let user = repository.get_by_id(id).await?;
+    Ok(Json(user))
+}
+
+// 创建用户
+pub async fn create_user(
+    State(state): State<AppState>,
+    Json(request): Json<CreateUserRequest>,
+) -> Result<Json<User>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = UserRepository::new(state.pool);
+    let user = repository.create(request).await?;
+    Ok(Json(user))
+}
+
+// 更新用户
+pub async fn update_user(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    Json(request): Json<UpdateUserRequest>,
+) -> Result<Json<User>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = UserRepository::new(state.pool);
+    let user = repository.update(id, request).await?;
+    Ok(Json(user))
+}
+
+// 停用用户
+pub async fn deactivate_user(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Json<User>, AppError> {
+    let repository = UserRepository::new(state.pool);
+    let user = repository.deactivate(id).await?;
+    Ok(Json(user))
+}
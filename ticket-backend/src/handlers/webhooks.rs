@@ -0,0 +1,113 @@
+use crate::{
+    auth::CurrentUser,
+    error::AppError,
+    models::{
+        CreateWebhookRequest, CreatedWebhook, PaginatedResponse, UpdateWebhookRequest, Webhook,
+        WebhookDelivery, WebhookDeliveryDetail, WebhookDeliveryQuery,
+    },
+    repositories::webhooks::WebhookRepository,
+    services::webhooks::WEBHOOK_EVENTS,
+    state::AppState,
+    utils::resolve_pagination,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid as UuidType;
+use validator::Validate;
+
+// 获取可订阅的事件
+pub async fn list_webhook_events() -> Json<&'static [&'static str]> {
+    Json(WEBHOOK_EVENTS)
+}
+
+// 获取所有 Webhook
+pub async fn list_webhooks(State(state): State<AppState>) -> Result<Json<Vec<Webhook>>, AppError> {
+    let repository = WebhookRepository::new(state.pool);
+    let webhooks = repository.list().await?;
+    Ok(Json(webhooks))
+}
+
+// 获取单个 Webhook
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Json<Webhook>, AppError> {
+    let repository = WebhookRepository::new(state.pool);
+    let webhook = repository.get(id).await?;
+    Ok(Json(webhook))
+}
+
+// 创建 Webhook（签名密钥仅在此时返回）
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    current_user: CurrentUser,
+    Json(request): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<CreatedWebhook>), AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = WebhookRepository::new(state.pool);
+    let webhook = repository.create(request, current_user.id()).await?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+// 更新 Webhook
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    Json(request): Json<UpdateWebhookRequest>,
+) -> Result<Json<Webhook>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = WebhookRepository::new(state.pool);
+    let webhook = repository.update(id, request).await?;
+    Ok(Json(webhook))
+}
+
+// 删除 Webhook
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<StatusCode, AppError> {
+    let repository = WebhookRepository::new(state.pool);
+    repository.delete(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 分页获取投递记录，可按状态与事件筛选
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    Query(query): Query<WebhookDeliveryQuery>,
+) -> Result<Json<PaginatedResponse<WebhookDelivery>>, AppError> {
+    let (limit, offset) = resolve_pagination(query.page, query.limit, query.offset);
+    let repository = WebhookRepository::new(state.pool);
+    let deliveries = repository
+        .list_deliveries(id, &query, limit, offset)
+        .await?;
+    Ok(Json(deliveries))
+}
+
+// 获取投递详情（含每次尝试的状态码与响应体）
+pub async fn get_webhook_delivery(
+    State(state): State<AppState>,
+    Path((id, delivery_id)): Path<(UuidType, UuidType)>,
+) -> Result<Json<WebhookDeliveryDetail>, AppError> {
+    let repository = WebhookRepository::new(state.pool);
+    let delivery = repository.get_delivery(id, delivery_id).await?;
+    Ok(Json(delivery))
+}
+
+// 立即重新投递
+pub async fn retry_webhook_delivery(
+    State(state): State<AppState>,
+    Path((id, delivery_id)): Path<(UuidType, UuidType)>,
+) -> Result<Json<WebhookDelivery>, AppError> {
+    let repository = WebhookRepository::new(state.pool);
+    let delivery = repository.retry_delivery(id, delivery_id).await?;
+    Ok(Json(delivery))
+}
@@ -0,0 +1,69 @@
+use crate::{
+    error::AppError,
+    models::{CreateWorkflowRequest, UpdateWorkflowRequest, WorkflowWithStates},
+    repositories::workflows::WorkflowRepository,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid as UuidType;
+use validator::Validate;
+
+// 获取所有工作流
+pub async fn list_workflows(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<WorkflowWithStates>>, AppError> {
+    let repository = WorkflowRepository::new(state.pool);
+    let workflows = repository.list().await?;
+    Ok(Json(workflows))
+}
+
+// 获取单个工作流
+pub async fn get_workflow(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<Json<WorkflowWithStates>, AppError> {
+    let repository = WorkflowRepository::new(state.pool);
+    let workflow = repository.get(id).await?;
+    Ok(Json(workflow))
+}
+
+// 创建工作流
+pub async fn create_workflow(
+    State(state): State<AppState>,
+    Json(request): Json<CreateWorkflowRequest>,
+) -> Result<Json<WorkflowWithStates>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = WorkflowRepository::new(state.pool);
+    let workflow = repository.create(request).await?;
+    Ok(Json(workflow))
+}
+
+// 更新工作流
+pub async fn update_workflow(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+    Json(request): Json<UpdateWorkflowRequest>,
+) -> Result<Json<WorkflowWithStates>, AppError> {
+    // 验证请求数据
+    request.validate()?;
+
+    let repository = WorkflowRepository::new(state.pool);
+    let workflow = repository.update(id, request).await?;
+    Ok(Json(workflow))
+}
+
+// 删除工作流
+pub async fn delete_workflow(
+    State(state): State<AppState>,
+    Path(id): Path<UuidType>,
+) -> Result<StatusCode, AppError> {
+    let repository = WorkflowRepository::new(state.pool);
+    repository.delete(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
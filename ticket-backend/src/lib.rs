@@ -1,9 +1,16 @@
+pub mod auth;
 pub mod config;
 pub mod database;
 pub mod error;
 pub mod handlers;
 pub mod models;
+pub mod precondition;
+pub mod repositories;
 pub mod routes;
+pub mod services;
+pub mod state;
+pub mod storage;
+pub mod utils;
 
 pub use config::*;
 pub use database::*;
@@ -11,3 +18,4 @@ pub use error::*;
 pub use handlers::*;
 pub use models::*;
 pub use routes::*;
+pub use state::*;
@@ -1,6 +1,16 @@
 use std::net::SocketAddr;
+use ticket_backend::{
+    auth::ensure_bootstrap_admin,
+    config::Config,
+    database::init_database,
+    routes::create_app,
+    services::{
+        event_stream::spawn_event_listener, notifications::spawn_notification_task,
+        trash::spawn_purge_task, webhooks::spawn_delivery_task,
+    },
+    state::AppState,
+};
 use tracing::info;
-use ticket_backend::{config::Config, database::init_database, routes::create_app};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -11,13 +21,39 @@ async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
 
     // 加载配置
-    let config = Config::from_env();
+    let config = Config::from_env()?;
 
     // 初始化数据库连接池
     let pool = init_database().await?;
 
+    // 确保管理员账号存在
+    ensure_bootstrap_admin(&pool, &config.auth).await?;
+
+    // 创建共享状态（含附件存储后端）
+    let state = AppState::new(pool, config.clone());
+
+    // 启动回收站清理任务
+    spawn_purge_task(
+        state.pool.clone(),
+        state.storage.clone(),
+        config.trash.clone(),
+    );
+
+    // 启动 Webhook 投递任务
+    spawn_delivery_task(state.pool.clone(), config.webhooks.clone());
+
+    // 启动邮件通知发送任务
+    spawn_notification_task(state.pool.clone(), config.notifications.clone());
+
+    // 启动实时事件监听任务
+    spawn_event_listener(
+        state.pool.clone(),
+        state.events.clone(),
+        config.event_stream.clone(),
+    );
+
     // 创建应用路由
-    let app = create_app(pool);
+    let app = create_app(state);
 
     // 绑定地址
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
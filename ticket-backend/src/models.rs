@@ -1,58 +1,215 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 use validator::Validate;
 
-// 工单状态枚举
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "varchar")]
-pub enum TicketStatus {
+use crate::utils::{color, query_language::TicketFilter, sort::TicketSortKey};
+
+// 工单状态：所属工作流中的状态标识（如 "open"、"in_progress"），由工作流校验
+pub type TicketStatus = String;
+
+// 工作流状态分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum StatusCategory {
     Open,
-    InProgress,
-    Resolved,
-    Closed,
+    Active,
+    Done,
 }
 
-impl Default for TicketStatus {
-    fn default() -> Self {
-        Self::Open
+impl StatusCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Active => "active",
+            Self::Done => "done",
+        }
     }
 }
 
-// 优先级枚举
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "varchar")]
+// 工作流模型
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Workflow {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 工作流状态（按 position 排序）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, Validate)]
+pub struct WorkflowState {
+    #[validate(length(min = 1, max = 50, message = "状态标识长度必须在1-50个字符之间"))]
+    pub key: String,
+    #[validate(length(min = 1, max = 100, message = "状态名称长度必须在1-100个字符之间"))]
+    pub name: String,
+    pub category: StatusCategory,
+}
+
+// 工作流状态流转
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct WorkflowTransition {
+    #[sqlx(rename = "from_state")]
+    pub from: String,
+    #[sqlx(rename = "to_state")]
+    pub to: String,
+}
+
+// 带状态与流转规则的工作流
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowWithStates {
+    #[serde(flatten)]
+    pub workflow: Workflow,
+    pub states: Vec<WorkflowState>,
+    pub transitions: Vec<WorkflowTransition>,
+}
+
+impl WorkflowWithStates {
+    pub fn state(&self, key: &str) -> Option<&WorkflowState> {
+        self.states.iter().find(|state| state.key == key)
+    }
+
+    // 新建或重新打开的工单进入第一个 open 分类的状态
+    pub fn initial_state(&self) -> Option<&WorkflowState> {
+        self.states
+            .iter()
+            .find(|state| state.category == StatusCategory::Open)
+    }
+
+    // 从指定状态出发允许进入的状态
+    pub fn next_states(&self, from: &str) -> Vec<&str> {
+        self.transitions
+            .iter()
+            .filter(|transition| transition.from == from)
+            .map(|transition| transition.to.as_str())
+            .collect()
+    }
+
+    // 保持当前状态视为合法
+    pub fn can_transition(&self, from: &str, to: &str) -> bool {
+        from == to
+            || self
+                .transitions
+                .iter()
+                .any(|transition| transition.from == from && transition.to == to)
+    }
+
+    pub fn is_done(&self, key: &str) -> bool {
+        self.state(key)
+            .is_some_and(|state| state.category == StatusCategory::Done)
+    }
+}
+
+// 创建工作流请求
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateWorkflowRequest {
+    #[validate(length(min = 1, max = 100, message = "工作流名称长度必须在1-100个字符之间"))]
+    pub name: String,
+    pub description: Option<String>,
+    #[validate(length(min = 1, message = "至少需要一个状态"), nested)]
+    pub states: Vec<WorkflowState>,
+    #[serde(default)]
+    pub transitions: Vec<WorkflowTransition>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+// 更新工作流请求（states 与 transitions 为整体替换）
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct UpdateWorkflowRequest {
+    #[validate(length(min = 1, max = 100, message = "工作流名称长度必须在1-100个字符之间"))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[validate(length(min = 1, message = "至少需要一个状态"), nested)]
+    pub states: Option<Vec<WorkflowState>>,
+    pub transitions: Option<Vec<WorkflowTransition>>,
+    pub is_default: Option<bool>,
+}
+
+// 优先级枚举（按严重程度从低到高声明，比较顺序即严重程度）
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
 pub enum Priority {
     Low,
+    #[default]
     Medium,
     High,
     Urgent,
 }
 
-impl Default for Priority {
-    fn default() -> Self {
-        Self::Medium
+impl Priority {
+    pub const ALL: [Priority; 4] = [Self::Low, Self::Medium, Self::High, Self::Urgent];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Urgent => "urgent",
+        }
     }
 }
 
-// 标签模型
+// 标签模型（序列化时附带根据背景色计算的 text_color）
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(into = "TagResponse")]
 pub struct Tag {
     pub id: Uuid,
     pub name: String,
     pub color: String,
+    pub parent_id: Option<Uuid>, // 父标签，为空表示根标签
+    pub group_id: Option<Uuid>,  // 所属标签分组
+    pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+// 标签的响应格式
+#[derive(Debug, Serialize)]
+pub struct TagResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub color: String,
+    pub text_color: &'static str, // 徽标文字颜色（#000000 或 #FFFFFF）
+    pub parent_id: Option<Uuid>,
+    pub group_id: Option<Uuid>,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Tag> for TagResponse {
+    fn from(tag: Tag) -> Self {
+        Self {
+            text_color: color::text_color(&tag.color),
+            id: tag.id,
+            name: tag.name,
+            color: tag.color,
+            parent_id: tag.parent_id,
+            group_id: tag.group_id,
+            version: tag.version,
+            created_at: tag.created_at,
+            updated_at: tag.updated_at,
+        }
+    }
+}
+
 // 创建标签请求
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct CreateTagRequest {
     #[validate(length(min = 1, max = 50, message = "标签名称长度必须在1-50个字符之间"))]
     pub name: String,
-    #[validate(length(min = 7, max = 7, message = "颜色必须是7位HEX值"))]
-    pub color: Option<String>,
+    #[validate(custom(function = "color::validate_hex_color"))]
+    pub color: Option<String>, // #RRGGBB，未指定时从调色板自动分配
+    pub parent_id: Option<Uuid>,
+    pub group_id: Option<Uuid>,
 }
 
 // 更新标签请求
@@ -60,8 +217,173 @@ pub struct CreateTagRequest {
 pub struct UpdateTagRequest {
     #[validate(length(min = 1, max = 50, message = "标签名称长度必须在1-50个字符之间"))]
     pub name: Option<String>,
-    #[validate(length(min = 7, max = 7, message = "颜色必须是7位HEX值"))]
+    #[validate(custom(function = "color::validate_hex_color"))]
     pub color: Option<String>,
+    #[serde(default)]
+    pub keep_alias: bool, // 改名时将旧名称保留为别名
+}
+
+// 移动标签请求，parent_id 为空表示移动为根标签
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoveTagRequest {
+    pub parent_id: Option<Uuid>,
+}
+
+// 设置标签所属分组请求，group_id 为空表示移出分组
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetTagGroupRequest {
+    pub group_id: Option<Uuid>,
+}
+
+// 标签分组：exclusive 为 true 时一个工单最多带有该分组中的一个标签
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TagGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub exclusive: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 带标签列表的分组
+#[derive(Debug, Clone, Serialize)]
+pub struct TagGroupWithTags {
+    #[serde(flatten)]
+    pub group: TagGroup,
+    pub tags: Vec<Tag>,
+}
+
+// 创建标签分组请求
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateTagGroupRequest {
+    #[validate(length(min = 1, max = 50, message = "分组名称长度必须在1-50个字符之间"))]
+    pub name: String,
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+// 更新标签分组请求
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct UpdateTagGroupRequest {
+    #[validate(length(min = 1, max = 50, message = "分组名称长度必须在1-50个字符之间"))]
+    pub name: Option<String>,
+    pub exclusive: Option<bool>,
+}
+
+// 合并标签请求：将 source_ids 中的标签合并到路径中的目标标签
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct MergeTagsRequest {
+    #[validate(length(min = 1, message = "请指定要合并的标签"))]
+    pub source_ids: Vec<Uuid>,
+}
+
+// 标签别名
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TagAlias {
+    pub name: String,
+    pub tag_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+// 合并结果：目标标签、合并后的全部别名以及受影响的工单数
+#[derive(Debug, Clone, Serialize)]
+pub struct TagMergeResult {
+    pub tag: Tag,
+    pub aliases: Vec<TagAlias>,
+    pub retagged_tickets: i64,
+}
+
+// 标签树节点：depth 为距根标签的层级，path 为从根标签开始以 `/` 连接的名称
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TagNode {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub tag: Tag,
+    pub depth: i32,
+    pub path: String,
+}
+
+// 用户角色枚举
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum UserRole {
+    Admin,
+    #[default]
+    Agent,
+    Viewer,
+}
+
+impl UserRole {
+    // 只读角色不能执行任何写操作
+    pub fn can_write(self) -> bool {
+        self != UserRole::Viewer
+    }
+
+    pub fn is_admin(self) -> bool {
+        self == UserRole::Admin
+    }
+}
+
+// 用户模型
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct User {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub avatar_color: String,
+    pub role: UserRole,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 用户摘要（嵌入工单等响应中）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub avatar_color: String,
+}
+
+// 创建用户请求
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreateUserRequest {
+    #[validate(length(min = 1, max = 100, message = "用户名长度必须在1-100个字符之间"))]
+    pub name: String,
+    #[validate(email(message = "邮箱格式不正确"), length(max = 255))]
+    pub email: String,
+    #[validate(length(min = 7, max = 7, message = "颜色必须是7位HEX值"))]
+    pub avatar_color: Option<String>,
+    #[validate(length(min = 8, max = 128, message = "密码长度必须在8-128个字符之间"))]
+    pub password: Option<String>,
+    pub role: Option<UserRole>,
+}
+
+// 更新用户请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateUserRequest {
+    #[validate(length(min = 1, max = 100, message = "用户名长度必须在1-100个字符之间"))]
+    pub name: Option<String>,
+    #[validate(email(message = "邮箱格式不正确"), length(max = 255))]
+    pub email: Option<String>,
+    #[validate(length(min = 7, max = 7, message = "颜色必须是7位HEX值"))]
+    pub avatar_color: Option<String>,
+    #[validate(length(min = 8, max = 128, message = "密码长度必须在8-128个字符之间"))]
+    pub password: Option<String>,
+    pub role: Option<UserRole>,
+}
+
+// 用户查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserQuery {
+    pub search: Option<String>, // 按名称或邮箱搜索
+    pub include_inactive: Option<bool>,
+    pub role: Option<UserRole>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
 // 工单模型
@@ -70,13 +392,19 @@ pub struct Ticket {
     pub id: Uuid,
     pub title: String,
     pub description: Option<String>,
+    pub workflow_id: Uuid,
     pub status: TicketStatus,
     pub priority: Priority,
     pub assignee_id: Option<Uuid>,
     pub reporter_id: Option<Uuid>,
+    pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub resolved_at: Option<DateTime<Utc>>,
+    // 截止时间，未设置为空
+    pub due_date: Option<DateTime<Utc>>,
+    // 移入回收站的时间，未删除为空
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 // 创建工单请求
@@ -87,8 +415,9 @@ pub struct CreateTicketRequest {
     pub description: Option<String>,
     pub priority: Option<Priority>,
     pub assignee_id: Option<Uuid>,
-    pub reporter_id: Option<Uuid>,
-    pub tag_ids: Option<Vec<Uuid>>, // 标签ID列表
+    pub due_date: Option<DateTime<Utc>>,
+    pub workflow_id: Option<Uuid>,  // 未指定时使用默认工作流
+    pub tag_ids: Option<Vec<Uuid>>, // 标签ID列表（报告人取当前登录用户）
 }
 
 // 更新工单请求
@@ -99,23 +428,68 @@ pub struct UpdateTicketRequest {
     pub description: Option<String>,
     pub status: Option<TicketStatus>,
     pub priority: Option<Priority>,
-    pub assignee_id: Option<Uuid>,
+    // 省略字段表示不修改，显式传 null 表示清空
+    #[serde(default, deserialize_with = "nullable")]
+    pub assignee_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub due_date: Option<Option<DateTime<Utc>>>,
     pub tag_ids: Option<Vec<Uuid>>, // 标签ID列表
 }
 
+// 区分缺省字段与 null：字段存在时总是解析为 Some，缺省时由 serde(default) 得到 None
+fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// 按ID或名称引用标签，名称匹配不区分大小写
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagRef {
+    Id(Uuid),
+    Name(String),
+}
+
+impl TagRef {
+    // 解析逗号分隔的标签列表，能解析为 UUID 的视为ID，否则视为名称
+    pub fn parse_list(raw: &str) -> Vec<TagRef> {
+        raw.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| match s.parse::<Uuid>() {
+                Ok(id) => TagRef::Id(id),
+                Err(_) => TagRef::Name(s.to_string()),
+            })
+            .collect()
+    }
+}
+
 // 工单查询参数
 #[derive(Debug, Clone, Deserialize)]
 pub struct TicketQuery {
     pub status: Option<TicketStatus>,
+    pub status_category: Option<StatusCategory>,
+    pub workflow_id: Option<Uuid>,
     pub priority: Option<Priority>,
     pub assignee_id: Option<Uuid>,
     pub reporter_id: Option<Uuid>,
-    pub tag_id: Option<Uuid>,
+    #[serde(skip)]
+    pub tags_all: Vec<TagRef>, // 同时包含全部标签
+    #[serde(skip)]
+    pub tags_any: Vec<TagRef>, // 包含任一标签
+    #[serde(skip)]
+    pub tags_none: Vec<TagRef>, // 不包含其中任何标签
+    #[serde(default)]
+    pub include_descendant_tags: bool, // 标签条件同时匹配其全部子标签
     pub search: Option<String>, // 搜索关键词
+    #[serde(skip)]
+    pub filter: Option<TicketFilter>, // 查询语言解析结果
     pub limit: Option<i64>,
     pub offset: Option<i64>,
-    pub sort_by: Option<String>,    // 排序字段
-    pub sort_order: Option<String>, // 排序方向 asc/desc
+    #[serde(skip)]
+    pub sort: Vec<TicketSortKey>, // 排序键，为空时按创建时间倒序
 }
 
 // 评论模型
@@ -132,29 +506,139 @@ pub struct Comment {
 // 创建评论请求
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct CreateCommentRequest {
-    #[validate(length(min = 1, message = "评论内容不能为空"))]
+    #[validate(length(min = 1, max = 10000, message = "评论内容长度必须在1-10000个字符之间"))]
     pub content: String,
 }
 
 // 更新评论请求
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct UpdateCommentRequest {
-    #[validate(length(min = 1, message = "评论内容不能为空"))]
+    #[validate(length(min = 1, max = 10000, message = "评论内容长度必须在1-10000个字符之间"))]
     pub content: String,
 }
 
+// 评论查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommentQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// 附件模型（文件内容在存储后端，storage_key 不对外暴露）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub ticket_id: Uuid,
+    pub comment_id: Option<Uuid>, // 为空表示直接附加在工单上
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    pub sha256: String,
+    #[serde(skip)]
+    pub storage_key: String,
+    pub uploader_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 待保存的附件：上传内容已写入存储后端，尚未写入数据库
+#[derive(Debug, Clone)]
+pub struct NewAttachment {
+    pub id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    pub sha256: String,
+    pub storage_key: String,
+}
+
+// 工单事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum TicketEventType {
+    Created,
+    FieldChanged,
+    TagAdded,
+    TagRemoved,
+    CommentAdded,
+    CommentUpdated,
+    CommentDeleted,
+    Deleted,
+    Restored,
+    Purged,
+    AttachmentAdded,
+    AttachmentDeleted,
+}
+
+// 工单事件（审计日志）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TicketEvent {
+    pub id: Uuid,
+    pub ticket_id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub event_type: TicketEventType,
+    pub field: Option<String>,
+    pub old_value: Option<serde_json::Value>,
+    pub new_value: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 带操作人摘要的工单事件
+#[derive(Debug, Clone, Serialize)]
+pub struct TicketEventWithActor {
+    #[serde(flatten)]
+    pub event: TicketEvent,
+    pub actor: Option<UserSummary>,
+}
+
+// 工单历史查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct TicketHistoryQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// 回收站查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrashQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
 // 带标签的工单模型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TicketWithTags {
     #[serde(flatten)]
     pub ticket: Ticket,
     pub tags: Vec<Tag>,
+    pub assignee: Option<UserSummary>,
+    pub reporter: Option<UserSummary>,
+}
+
+// 全文搜索结果：工单、相关度与高亮片段
+#[derive(Debug, Clone, Serialize)]
+pub struct TicketSearchHit {
+    #[serde(flatten)]
+    pub ticket: TicketWithTags,
+    pub rank: f32,
+    pub highlights: SearchHighlights,
+}
+
+// 高亮片段为 HTML，原文已转义，匹配处以 <mark> 标记
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHighlights {
+    pub title: String,
+    pub description: Option<String>,
 }
 
 // 带计数的标签模型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct TagWithCount {
     #[serde(flatten)]
+    #[sqlx(flatten)]
     pub tag: Tag,
     pub ticket_count: i64,
 }
@@ -165,6 +649,8 @@ pub struct TicketWithDetails {
     #[serde(flatten)]
     pub ticket: Ticket,
     pub tags: Vec<Tag>,
+    pub assignee: Option<UserSummary>,
+    pub reporter: Option<UserSummary>,
     pub comments: Vec<Comment>,
 }
 
@@ -173,17 +659,426 @@ pub struct TicketWithDetails {
 pub struct PaginatedResponse<T> {
     pub data: Vec<T>,
     pub total: i64,
+    pub page: i64,
     pub limit: i64,
     pub offset: i64,
 }
 
 impl<T> PaginatedResponse<T> {
     pub fn new(data: Vec<T>, total: i64, limit: i64, offset: i64) -> Self {
+        let page = if limit > 0 {
+            (offset / limit).saturating_add(1)
+        } else {
+            1
+        };
         Self {
             data,
             total,
+            page,
             limit,
             offset,
         }
     }
 }
+
+// 游标分页响应：next_cursor 为空表示没有更多数据，total 仅在请求计数时返回
+#[derive(Debug, Clone, Serialize)]
+pub struct CursorPage<T> {
+    pub data: Vec<T>,
+    pub limit: i64,
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
+
+// 登录请求
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct LoginRequest {
+    #[validate(email(message = "邮箱格式不正确"))]
+    pub email: String,
+    #[validate(length(min = 1, message = "密码不能为空"))]
+    pub password: String,
+}
+
+// 登录响应
+#[derive(Debug, Clone, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub token_type: String,
+    pub expires_at: DateTime<Utc>,
+    pub user: User,
+}
+
+// API 令牌（不含哈希）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 创建 API 令牌请求
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateApiTokenRequest {
+    #[validate(length(min = 1, max = 100, message = "令牌名称长度必须在1-100个字符之间"))]
+    pub name: String,
+    #[validate(length(min = 1, message = "至少需要一个权限范围"))]
+    pub scopes: Vec<String>,
+    #[validate(range(min = 1, max = 3650, message = "有效期必须在1-3650天之间"))]
+    pub expires_in_days: Option<i64>,
+}
+
+// 新建的 API 令牌，明文令牌仅在创建时返回一次
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+// Webhook 订阅（secret 仅在创建时返回）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<String>,
+    pub active: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 创建 Webhook 请求，未提供 secret 时自动生成
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateWebhookRequest {
+    #[validate(
+        url(message = "URL格式无效"),
+        length(max = 2000, message = "URL不能超过2000个字符")
+    )]
+    pub url: String,
+    #[validate(length(min = 16, max = 128, message = "签名密钥长度必须在16-128个字符之间"))]
+    pub secret: Option<String>,
+    #[validate(length(min = 1, message = "至少需要订阅一个事件"))]
+    pub events: Vec<String>,
+    pub active: Option<bool>, // 默认启用
+}
+
+// 更新 Webhook 请求
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct UpdateWebhookRequest {
+    #[validate(
+        url(message = "URL格式无效"),
+        length(max = 2000, message = "URL不能超过2000个字符")
+    )]
+    pub url: Option<String>,
+    #[validate(length(min = 16, max = 128, message = "签名密钥长度必须在16-128个字符之间"))]
+    pub secret: Option<String>,
+    #[validate(length(min = 1, message = "至少需要订阅一个事件"))]
+    pub events: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+// 新建的 Webhook，签名密钥仅在创建时返回一次
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+// Webhook 投递状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+// Webhook 投递记录（含最近一次尝试的结果）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempt_count: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 单次投递尝试
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDeliveryAttempt {
+    pub id: Uuid,
+    pub attempt: i32,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub attempted_at: DateTime<Utc>,
+}
+
+// 投递详情：投递记录及全部尝试
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookDeliveryDetail {
+    #[serde(flatten)]
+    pub delivery: WebhookDelivery,
+    pub attempts: Vec<WebhookDeliveryAttempt>,
+}
+
+// 投递记录查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookDeliveryQuery {
+    pub status: Option<WebhookDeliveryStatus>,
+    pub event: Option<String>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// 实时事件流中的事件，payload 与 Webhook 请求体相同
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StreamEvent {
+    pub id: i64,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+// 事件流查询参数（Last-Event-ID 请求头优先）
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventStreamQuery {
+    pub last_event_id: Option<i64>,
+}
+
+// 工单协作通道中的参与者（每个连接一条）
+#[derive(Debug, Clone, Serialize)]
+pub struct CollaborationParticipant {
+    pub connection_id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub avatar_color: String,
+    pub typing: bool,
+    pub joined_at: DateTime<Utc>,
+}
+
+// 客户端发送的协作消息
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CollaborationClientMessage {
+    // 请求当前参与者列表
+    Presence,
+    // 开始/停止输入（如正在写评论）
+    Typing { typing: bool },
+    // 主动离开，服务端随后关闭连接
+    Leave,
+}
+
+// 服务端推送的协作消息
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CollaborationServerMessage {
+    // 加入后发送给本连接：自己的连接 ID 与当前所有参与者
+    Presence {
+        connection_id: Uuid,
+        participants: Vec<CollaborationParticipant>,
+    },
+    Join {
+        participant: CollaborationParticipant,
+    },
+    Leave {
+        participant: CollaborationParticipant,
+    },
+    Typing {
+        participant: CollaborationParticipant,
+    },
+    // 工单字段已保存（来自实时事件流，多实例间一致）
+    FieldChanged {
+        field: String,
+        old_value: serde_json::Value,
+        new_value: serde_json::Value,
+        actor_id: Option<Uuid>,
+        occurred_at: DateTime<Utc>,
+        // 保存后的工单快照
+        ticket: serde_json::Value,
+    },
+    Error {
+        message: String,
+    },
+}
+
+// 通知类型，同时对应通知偏好中的开关
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum NotificationKind {
+    Assigned,
+    StatusChanged,
+    CommentAdded,
+    Mentioned,
+}
+
+// 通知邮件语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum NotificationLanguage {
+    #[default]
+    Zh,
+    En,
+}
+
+// 邮件发送方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum NotificationDelivery {
+    // 立即发送
+    #[default]
+    Immediate,
+    // 每小时汇总为一封邮件
+    Digest,
+    // 不发送邮件
+    Off,
+}
+
+// 通知状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum NotificationStatus {
+    Pending,
+    Sent,
+    Failed,
+    Skipped,
+}
+
+// 用户的通知偏好
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NotificationPreferences {
+    pub language: NotificationLanguage,
+    pub delivery: NotificationDelivery,
+    pub assigned: bool,
+    pub status_changed: bool,
+    pub comment_added: bool,
+    pub mentioned: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            language: NotificationLanguage::default(),
+            delivery: NotificationDelivery::default(),
+            assigned: true,
+            status_changed: true,
+            comment_added: true,
+            mentioned: true,
+        }
+    }
+}
+
+// 更新通知偏好请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub language: Option<NotificationLanguage>,
+    pub delivery: Option<NotificationDelivery>,
+    pub assigned: Option<bool>,
+    pub status_changed: Option<bool>,
+    pub comment_added: Option<bool>,
+    pub mentioned: Option<bool>,
+}
+
+// 通知记录
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Notification {
+    pub id: Uuid,
+    pub ticket_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub kind: NotificationKind,
+    pub data: serde_json::Value,
+    pub digest: bool,
+    pub status: NotificationStatus,
+    pub attempt_count: i32,
+    pub error: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 通知列表查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationQuery {
+    pub status: Option<NotificationStatus>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// 保存的视图
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SavedView {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub name: String,
+    pub shared: bool,
+    pub filters: sqlx::types::Json<ViewFilters>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// 视图保存的筛选条件，字段含义与工单列表查询参数相同
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewFilters {
+    pub status: Option<TicketStatus>,
+    pub status_category: Option<StatusCategory>,
+    pub workflow_id: Option<Uuid>,
+    pub priority: Option<Priority>,
+    pub assignee_id: Option<Uuid>,
+    pub reporter_id: Option<Uuid>,
+    pub tag_ids: Option<Vec<Uuid>>,
+    pub tags_all: Option<String>, // 逗号分隔的标签ID或名称
+    pub tags_any: Option<String>,
+    pub tags_none: Option<String>,
+    pub include_descendant_tags: Option<bool>,
+    pub search: Option<String>,
+    pub q: Option<String>,    // 查询语言，`me` 按查看者解析
+    pub sort: Option<String>, // 多字段排序，如 "-priority,created_at"
+    pub sort_by: Option<String>,
+    pub sort_order: Option<String>,
+}
+
+// 创建视图请求
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateSavedViewRequest {
+    #[validate(length(min = 1, max = 100, message = "视图名称长度必须在1-100个字符之间"))]
+    pub name: String,
+    #[serde(default)]
+    pub shared: bool,
+    #[serde(default)]
+    pub filters: ViewFilters,
+}
+
+// 更新视图请求（filters 为整体替换）
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct UpdateSavedViewRequest {
+    #[validate(length(min = 1, max = 100, message = "视图名称长度必须在1-100个字符之间"))]
+    pub name: Option<String>,
+    pub shared: Option<bool>,
+    pub filters: Option<ViewFilters>,
+}
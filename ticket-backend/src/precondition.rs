@@ -0,0 +1,95 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::error::AppError;
+
+// 由版本号生成强 ETag
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+// If-Match 前置条件：`*` 匹配任意版本，否则为逗号分隔的 ETag 列表
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfMatch {
+    Any,
+    Versions(Vec<i32>),
+}
+
+impl IfMatch {
+    pub fn parse(raw: &str) -> Self {
+        if raw.trim() == "*" {
+            return Self::Any;
+        }
+
+        // If-Match 要求强比较，弱 ETag 与无法识别的值都视为不匹配
+        let versions = raw
+            .split(',')
+            .filter_map(|tag| {
+                tag.trim()
+                    .strip_prefix('"')?
+                    .strip_suffix('"')?
+                    .parse()
+                    .ok()
+            })
+            .collect();
+
+        Self::Versions(versions)
+    }
+
+    pub fn matches(&self, version: i32) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Versions(versions) => versions.contains(&version),
+        }
+    }
+}
+
+// 以 Option<IfMatch> 提取：未携带 If-Match 时不做版本检查
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = parts
+            .headers
+            .get(header::IF_MATCH)
+            .ok_or_else(|| AppError::bad_request("缺少 If-Match 请求头"))?;
+        let raw = value
+            .to_str()
+            .map_err(|_| AppError::bad_request("无效的 If-Match 请求头"))?;
+
+        Ok(Self::parse(raw))
+    }
+}
+
+// 未携带 If-Match 时视为满足
+pub fn is_satisfied(if_match: Option<&IfMatch>, version: i32) -> bool {
+    if_match.is_none_or(|if_match| if_match.matches(version))
+}
+
+// 带 ETag 响应头的 JSON 响应
+pub struct WithETag<T> {
+    version: i32,
+    body: T,
+}
+
+impl<T> WithETag<T> {
+    pub fn new(version: i32, body: T) -> Self {
+        Self { version, body }
+    }
+}
+
+impl<T: Serialize> IntoResponse for WithETag<T> {
+    fn into_response(self) -> Response {
+        ([(header::ETAG, etag(self.version))], Json(self.body)).into_response()
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    auth::{generate_api_token, hash_api_token, SCOPES},
+    database::DbPool,
+    error::AppError,
+    models::{ApiToken, CreateApiTokenRequest, CreatedApiToken},
+};
+use sqlx::query_as;
+use uuid::Uuid as UuidType;
+
+const API_TOKEN_COLUMNS: &str = "id, user_id, name, token_prefix, scopes, expires_at, revoked_at, \
+                                 last_used_at, created_at";
+
+// 列表中展示的令牌前缀长度（含 "tk_"）
+const TOKEN_PREFIX_LEN: usize = 10;
+
+pub struct ApiTokenRepository {
+    pool: DbPool,
+}
+
+impl ApiTokenRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 为用户创建 API 令牌
+    pub async fn create(
+        &self,
+        user_id: UuidType,
+        request: CreateApiTokenRequest,
+    ) -> Result<CreatedApiToken, AppError> {
+        let mut scopes = Vec::with_capacity(request.scopes.len());
+        for scope in request.scopes {
+            let scope = scope.trim().to_lowercase();
+            if !SCOPES.contains(&scope.as_str()) {
+                return Err(AppError::bad_request(format!(
+                    "未知的权限范围: {}，可选值: {}",
+                    scope,
+                    SCOPES.join(", ")
+                )));
+            }
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+
+        let secret = generate_api_token();
+        let now = chrono::Utc::now();
+        let expires_at = request
+            .expires_in_days
+            .map(|days| now + chrono::Duration::days(days));
+
+        let token = query_as::<_, ApiToken>(&format!(
+            r#"
+            INSERT INTO api_tokens (
+                id, user_id, name, token_hash, token_prefix, scopes, expires_at, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING {API_TOKEN_COLUMNS}
+            "#
+        ))
+        .bind(UuidType::new_v4())
+        .bind(user_id)
+        .bind(request.name.trim())
+        .bind(hash_api_token(&secret))
+        .bind(&secret[..TOKEN_PREFIX_LEN])
+        .bind(scopes)
+        .bind(expires_at)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(CreatedApiToken { token, secret })
+    }
+
+    // 列出用户的 API 令牌
+    pub async fn list_for_user(&self, user_id: UuidType) -> Result<Vec<ApiToken>, AppError> {
+        let tokens = query_as::<_, ApiToken>(&format!(
+            "SELECT {API_TOKEN_COLUMNS} FROM api_tokens WHERE user_id = $1 ORDER BY created_at DESC"
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    // 撤销令牌（仅限令牌所有者）
+    pub async fn revoke(&self, user_id: UuidType, id: UuidType) -> Result<ApiToken, AppError> {
+        query_as::<_, ApiToken>(&format!(
+            r#"
+            UPDATE api_tokens SET revoked_at = COALESCE(revoked_at, $1)
+            WHERE id = $2 AND user_id = $3
+            RETURNING {API_TOKEN_COLUMNS}
+            "#
+        ))
+        .bind(chrono::Utc::now())
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("API令牌"))
+    }
+
+    // 根据令牌哈希查找有效令牌，并记录最近使用时间
+    pub async fn authenticate(&self, token_hash: &str) -> Result<Option<ApiToken>, AppError> {
+        let token = query_as::<_, ApiToken>(&format!(
+            r#"
+            UPDATE api_tokens SET last_used_at = NOW()
+            WHERE token_hash = $1
+              AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+            RETURNING {API_TOKEN_COLUMNS}
+            "#
+        ))
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(token)
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    database::DbPool,
+    error::AppError,
+    models::{Attachment, NewAttachment, TicketEventType},
+    repositories::ticket_events::NewTicketEvent,
+};
+use serde_json::json;
+use sqlx::{query_as, query_scalar, PgConnection};
+use uuid::Uuid as UuidType;
+
+const ATTACHMENT_COLUMNS: &str = "id, ticket_id, comment_id, filename, content_type, size, \
+                                  sha256, storage_key, uploader_id, created_at";
+
+pub struct AttachmentRepository {
+    pool: DbPool,
+}
+
+impl AttachmentRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 检查附件的目标是否存在：工单未删除，评论（如有）属于该工单
+    pub async fn ensure_target_exists(
+        &self,
+        ticket_id: UuidType,
+        comment_id: Option<UuidType>,
+    ) -> Result<(), AppError> {
+        let mut conn = self.pool.acquire().await?;
+        ensure_target(&mut conn, ticket_id, comment_id, false).await
+    }
+
+    // 保存已写入存储后端的附件，并记录上传事件
+    pub async fn create_many(
+        &self,
+        ticket_id: UuidType,
+        comment_id: Option<UuidType>,
+        uploader_id: UuidType,
+        files: &[NewAttachment],
+    ) -> Result<Vec<Attachment>, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        // 锁定目标，避免上传期间工单或评论被删除
+        ensure_target(&mut tx, ticket_id, comment_id, true).await?;
+
+        let now = chrono::Utc::now();
+        let mut attachments = Vec::with_capacity(files.len());
+        for file in files {
+            let attachment = query_as::<_, Attachment>(&format!(
+                r#"
+                INSERT INTO attachments (
+                    id, ticket_id, comment_id, filename, content_type, size,
+                    sha256, storage_key, uploader_id, created_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                RETURNING {ATTACHMENT_COLUMNS}
+                "#
+            ))
+            .bind(file.id)
+            .bind(ticket_id)
+            .bind(comment_id)
+            .bind(&file.filename)
+            .bind(&file.content_type)
+            .bind(file.size)
+            .bind(&file.sha256)
+            .bind(&file.storage_key)
+            .bind(uploader_id)
+            .bind(now)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            NewTicketEvent::new(
+                ticket_id,
+                Some(uploader_id),
+                TicketEventType::AttachmentAdded,
+            )
+            .new_value(&summary(&attachment))
+            .record(&mut tx)
+            .await?;
+
+            attachments.push(attachment);
+        }
+
+        tx.commit().await?;
+
+        Ok(attachments)
+    }
+
+    // 列出工单的附件；指定 comment_id 时只列出该评论的附件
+    pub async fn list(
+        &self,
+        ticket_id: UuidType,
+        comment_id: Option<UuidType>,
+    ) -> Result<Vec<Attachment>, AppError> {
+        self.ensure_target_exists(ticket_id, comment_id).await?;
+
+        let attachments = query_as::<_, Attachment>(&format!(
+            r#"
+            SELECT {ATTACHMENT_COLUMNS}
+            FROM attachments
+            WHERE ticket_id = $1 AND ($2::uuid IS NULL OR comment_id = $2)
+            ORDER BY created_at ASC, id
+            "#
+        ))
+        .bind(ticket_id)
+        .bind(comment_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(attachments)
+    }
+
+    // 获取单个附件；所属工单在回收站中时视为不存在
+    pub async fn get(&self, id: UuidType) -> Result<Attachment, AppError> {
+        query_as::<_, Attachment>(&format!(
+            r#"
+            SELECT {ATTACHMENT_COLUMNS}
+            FROM attachments
+            WHERE id = $1
+              AND EXISTS (SELECT 1 FROM tickets t WHERE t.id = ticket_id AND t.deleted_at IS NULL)
+            "#
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("附件"))
+    }
+
+    // 删除附件记录并记录事件，返回被删除的附件（调用方负责删除存储对象）
+    pub async fn delete(
+        &self,
+        id: UuidType,
+        actor_id: Option<UuidType>,
+    ) -> Result<Attachment, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let attachment = query_as::<_, Attachment>(&format!(
+            "DELETE FROM attachments WHERE id = $1 RETURNING {ATTACHMENT_COLUMNS}"
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("附件"))?;
+
+        NewTicketEvent::new(
+            attachment.ticket_id,
+            actor_id,
+            TicketEventType::AttachmentDeleted,
+        )
+        .old_value(&summary(&attachment))
+        .record(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(attachment)
+    }
+}
+
+async fn ensure_target(
+    conn: &mut PgConnection,
+    ticket_id: UuidType,
+    comment_id: Option<UuidType>,
+    lock: bool,
+) -> Result<(), AppError> {
+    let lock_clause = if lock { " FOR SHARE" } else { "" };
+
+    let ticket_exists: bool = query_scalar(&format!(
+        "SELECT EXISTS(SELECT 1 FROM tickets WHERE id = $1 AND deleted_at IS NULL{lock_clause})"
+    ))
+    .bind(ticket_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    if !ticket_exists {
+        return Err(AppError::not_found("工单"));
+    }
+
+    if let Some(comment_id) = comment_id {
+        let comment_exists: bool = query_scalar(&format!(
+            "SELECT EXISTS(SELECT 1 FROM comments WHERE id = $1 AND ticket_id = $2{lock_clause})"
+        ))
+        .bind(comment_id)
+        .bind(ticket_id)
+        .fetch_one(&mut *conn)
+        .await?;
+        if !comment_exists {
+            return Err(AppError::not_found("评论"));
+        }
+    }
+
+    Ok(())
+}
+
+// 历史记录中的附件摘要
+fn summary(attachment: &Attachment) -> serde_json::Value {
+    json!({
+        "id": attachment.id,
+        "comment_id": attachment.comment_id,
+        "filename": attachment.filename,
+        "content_type": attachment.content_type,
+        "size": attachment.size,
+    })
+}
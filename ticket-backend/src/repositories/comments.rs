@@ -0,0 +1,226 @@
+use crate::{
+    database::DbPool,
+    error::AppError,
+    models::{
+        Comment, CreateCommentRequest, PaginatedResponse, TicketEventType, UpdateCommentRequest,
+    },
+    repositories::ticket_events::NewTicketEvent,
+};
+use serde_json::json;
+use sqlx::{query_as, query_scalar};
+use uuid::Uuid as UuidType;
+
+const COMMENT_COLUMNS: &str = "id, ticket_id, author_id, content, created_at, updated_at";
+
+pub struct CommentRepository {
+    pool: DbPool,
+}
+
+impl CommentRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 创建评论
+    pub async fn create(
+        &self,
+        ticket_id: UuidType,
+        request: CreateCommentRequest,
+        author_id: UuidType,
+    ) -> Result<Comment, AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        let id = UuidType::new_v4();
+        let now = chrono::Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        let comment = query_as::<_, Comment>(&format!(
+            r#"
+            INSERT INTO comments (id, ticket_id, author_id, content, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $5)
+            RETURNING {COMMENT_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .bind(ticket_id)
+        .bind(author_id)
+        .bind(request.content)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        NewTicketEvent::new(ticket_id, Some(author_id), TicketEventType::CommentAdded)
+            .new_value(&json!({ "id": comment.id, "content": comment.content }))
+            .record(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(comment)
+    }
+
+    // 获取工单下的单条评论
+    pub async fn get(&self, ticket_id: UuidType, id: UuidType) -> Result<Comment, AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        query_as::<_, Comment>(&format!(
+            "SELECT {COMMENT_COLUMNS} FROM comments WHERE id = $1 AND ticket_id = $2"
+        ))
+        .bind(id)
+        .bind(ticket_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("评论"))
+    }
+
+    // 分页列出工单评论（按时间正序）
+    pub async fn list(
+        &self,
+        ticket_id: UuidType,
+        limit: i64,
+        offset: i64,
+    ) -> Result<PaginatedResponse<Comment>, AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        let total: i64 = query_scalar("SELECT COUNT(*) FROM comments WHERE ticket_id = $1")
+            .bind(ticket_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let comments = query_as::<_, Comment>(&format!(
+            r#"
+            SELECT {COMMENT_COLUMNS}
+            FROM comments
+            WHERE ticket_id = $1
+            ORDER BY created_at ASC, id
+            LIMIT $2 OFFSET $3
+            "#
+        ))
+        .bind(ticket_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse::new(comments, total, limit, offset))
+    }
+
+    // 获取工单全部评论（用于工单详情）
+    pub async fn list_all_for_ticket(&self, ticket_id: UuidType) -> Result<Vec<Comment>, AppError> {
+        let comments = query_as::<_, Comment>(&format!(
+            r#"
+            SELECT {COMMENT_COLUMNS}
+            FROM comments
+            WHERE ticket_id = $1
+            ORDER BY created_at ASC, id
+            "#
+        ))
+        .bind(ticket_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(comments)
+    }
+
+    // 更新评论内容
+    pub async fn update(
+        &self,
+        ticket_id: UuidType,
+        id: UuidType,
+        request: UpdateCommentRequest,
+        actor_id: Option<UuidType>,
+    ) -> Result<Comment, AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let previous: String = query_scalar(
+            "SELECT content FROM comments WHERE id = $1 AND ticket_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(ticket_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("评论"))?;
+
+        let comment = query_as::<_, Comment>(&format!(
+            r#"
+            UPDATE comments
+            SET content = $1, updated_at = $2
+            WHERE id = $3
+            RETURNING {COMMENT_COLUMNS}
+            "#
+        ))
+        .bind(request.content)
+        .bind(chrono::Utc::now())
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if previous != comment.content {
+            NewTicketEvent::new(ticket_id, actor_id, TicketEventType::CommentUpdated)
+                .old_value(&json!({ "id": id, "content": previous }))
+                .new_value(&json!({ "id": id, "content": comment.content }))
+                .record(&mut tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(comment)
+    }
+
+    // 删除评论及其附件记录，返回附件的存储键（由调用方删除存储对象）
+    pub async fn delete(
+        &self,
+        ticket_id: UuidType,
+        id: UuidType,
+        actor_id: Option<UuidType>,
+    ) -> Result<Vec<String>, AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let storage_keys: Vec<String> = query_scalar(
+            "DELETE FROM attachments WHERE comment_id = $1 AND ticket_id = $2 RETURNING storage_key",
+        )
+        .bind(id)
+        .bind(ticket_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let comment = query_as::<_, Comment>(&format!(
+            "DELETE FROM comments WHERE id = $1 AND ticket_id = $2 RETURNING {COMMENT_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(ticket_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::not_found("评论"))?;
+
+        NewTicketEvent::new(ticket_id, actor_id, TicketEventType::CommentDeleted)
+            .old_value(&json!({ "id": id, "content": comment.content }))
+            .record(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(storage_keys)
+    }
+
+    // 回收站中的工单视为不存在，其评论不能读取或修改
+    async fn ensure_ticket_exists(&self, ticket_id: UuidType) -> Result<(), AppError> {
+        let exists: bool = query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM tickets WHERE id = $1 AND deleted_at IS NULL)",
+        )
+        .bind(ticket_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if !exists {
+            return Err(AppError::not_found("工单"));
+        }
+
+        Ok(())
+    }
+}
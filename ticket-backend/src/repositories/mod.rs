@@ -1,5 +1,27 @@
-pub mod tickets;
+pub mod api_tokens;
+pub mod attachments;
+pub mod comments;
+pub mod notifications;
+pub mod saved_views;
+pub mod stream_events;
+pub mod tag_groups;
 pub mod tags;
+pub mod ticket_events;
+pub mod tickets;
+pub mod users;
+pub mod webhooks;
+pub mod workflows;
 
+pub use api_tokens::ApiTokenRepository;
+pub use attachments::AttachmentRepository;
+pub use comments::CommentRepository;
+pub use notifications::NotificationRepository;
+pub use saved_views::SavedViewRepository;
+pub use stream_events::StreamEventRepository;
+pub use tag_groups::TagGroupRepository;
+pub use tags::TagRepository;
+pub use ticket_events::TicketEventRepository;
 pub use tickets::TicketRepository;
-pub use tags::TagRepository;
\ No newline at end of file
+pub use users::UserRepository;
+pub use webhooks::WebhookRepository;
+pub use workflows::WorkflowRepository;
@@ -0,0 +1,572 @@
+use crate::{
+    database::DbPool,
+    error::AppError,
+    models::{
+        Notification, NotificationDelivery, NotificationKind, NotificationLanguage,
+        NotificationPreferences, NotificationQuery, NotificationStatus, PaginatedResponse,
+        UpdateNotificationPreferencesRequest, UserSummary,
+    },
+    utils::{email_templates::excerpt, mentions::extract_mentions},
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::{query, query_as, query_scalar, FromRow, PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid as UuidType;
+
+const NOTIFICATION_COLUMNS: &str = "id, ticket_id, actor_id, kind, data, digest, status, \
+     attempt_count, error, sent_at, created_at";
+
+const PREFERENCE_COLUMNS: &str =
+    "language, delivery, assigned, status_changed, comment_added, mentioned";
+
+// 已取出待发送的通知（含收件人信息）
+#[derive(Debug, Clone, FromRow)]
+pub struct DueNotification {
+    pub id: UuidType,
+    pub user_id: UuidType,
+    pub kind: NotificationKind,
+    pub data: Value,
+    pub attempt_count: i32,
+    pub email: String,
+    pub name: String,
+    pub language: NotificationLanguage,
+}
+
+// 触发通知的工单事件
+pub(crate) enum NotificationTrigger {
+    Created {
+        assignee_id: Option<UuidType>,
+    },
+    Assigned {
+        assignee_id: UuidType,
+    },
+    StatusChanged {
+        old_status: Value,
+        new_status: Value,
+    },
+    CommentAdded {
+        comment_id: Value,
+        content: String,
+    },
+}
+
+pub struct NotificationRepository {
+    pool: DbPool,
+}
+
+impl NotificationRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 获取通知偏好，未设置过时返回默认值
+    pub async fn get_preferences(
+        &self,
+        user_id: UuidType,
+    ) -> Result<NotificationPreferences, AppError> {
+        let preferences = query_as::<_, NotificationPreferences>(&format!(
+            "SELECT {PREFERENCE_COLUMNS} FROM notification_preferences WHERE user_id = $1"
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(preferences.unwrap_or_default())
+    }
+
+    // 更新通知偏好；修改发送方式时，尚未发送的通知随之调整
+    pub async fn update_preferences(
+        &self,
+        user_id: UuidType,
+        request: UpdateNotificationPreferencesRequest,
+    ) -> Result<NotificationPreferences, AppError> {
+        let current = self.get_preferences(user_id).await?;
+        let preferences = NotificationPreferences {
+            language: request.language.unwrap_or(current.language),
+            delivery: request.delivery.unwrap_or(current.delivery),
+            assigned: request.assigned.unwrap_or(current.assigned),
+            status_changed: request.status_changed.unwrap_or(current.status_changed),
+            comment_added: request.comment_added.unwrap_or(current.comment_added),
+            mentioned: request.mentioned.unwrap_or(current.mentioned),
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        let preferences = query_as::<_, NotificationPreferences>(&format!(
+            r#"
+            INSERT INTO notification_preferences (
+                user_id, language, delivery, assigned, status_changed, comment_added, mentioned
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (user_id) DO UPDATE SET
+                language = EXCLUDED.language,
+                delivery = EXCLUDED.delivery,
+                assigned = EXCLUDED.assigned,
+                status_changed = EXCLUDED.status_changed,
+                comment_added = EXCLUDED.comment_added,
+                mentioned = EXCLUDED.mentioned
+            RETURNING {PREFERENCE_COLUMNS}
+            "#
+        ))
+        .bind(user_id)
+        .bind(preferences.language)
+        .bind(preferences.delivery)
+        .bind(preferences.assigned)
+        .bind(preferences.status_changed)
+        .bind(preferences.comment_added)
+        .bind(preferences.mentioned)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if preferences.delivery != current.delivery {
+            let statement = match preferences.delivery {
+                NotificationDelivery::Off => {
+                    "UPDATE notifications SET status = 'skipped', error = '用户已关闭邮件通知' \
+                     WHERE user_id = $1 AND status = 'pending'"
+                }
+                NotificationDelivery::Digest => {
+                    "UPDATE notifications SET digest = TRUE \
+                     WHERE user_id = $1 AND status = 'pending'"
+                }
+                NotificationDelivery::Immediate => {
+                    "UPDATE notifications SET digest = FALSE \
+                     WHERE user_id = $1 AND status = 'pending'"
+                }
+            };
+            query(statement).bind(user_id).execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(preferences)
+    }
+
+    // 获取用户收到的通知（最新的在前）
+    pub async fn list(
+        &self,
+        user_id: UuidType,
+        query_params: &NotificationQuery,
+        limit: i64,
+        offset: i64,
+    ) -> Result<PaginatedResponse<Notification>, AppError> {
+        let push_filters = |builder: &mut QueryBuilder<'_, Postgres>| {
+            builder.push(" WHERE user_id = ").push_bind(user_id);
+            if let Some(status) = query_params.status {
+                builder.push(" AND status = ").push_bind(status);
+            }
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM notifications");
+        push_filters(&mut builder);
+        let total: i64 = builder.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {NOTIFICATION_COLUMNS} FROM notifications"
+        ));
+        push_filters(&mut builder);
+        builder.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+        builder.push_bind(limit);
+        builder.push(" OFFSET ").push_bind(offset);
+        let notifications = builder
+            .build_query_as::<Notification>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(PaginatedResponse::new(notifications, total, limit, offset))
+    }
+
+    // 获取工单的关注者
+    pub async fn watchers(&self, ticket_id: UuidType) -> Result<Vec<UserSummary>, AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        let watchers = query_as::<_, UserSummary>(
+            r#"
+            SELECT u.id, u.name, u.email, u.avatar_color
+            FROM ticket_watchers w
+            INNER JOIN users u ON u.id = w.user_id
+            WHERE w.ticket_id = $1
+            ORDER BY w.created_at, u.name
+            "#,
+        )
+        .bind(ticket_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(watchers)
+    }
+
+    pub async fn watch(&self, ticket_id: UuidType, user_id: UuidType) -> Result<(), AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        let mut conn = self.pool.acquire().await?;
+        add_watchers(&mut conn, ticket_id, &[user_id]).await
+    }
+
+    pub async fn unwatch(&self, ticket_id: UuidType, user_id: UuidType) -> Result<(), AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        query("DELETE FROM ticket_watchers WHERE ticket_id = $1 AND user_id = $2")
+            .bind(ticket_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // 取出到期的即时通知，并把下次尝试时间推迟到租约结束，避免被其他实例重复发送
+    pub async fn claim_due(
+        &self,
+        limit: i64,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Vec<DueNotification>, AppError> {
+        let notifications = query_as::<_, DueNotification>(
+            r#"
+            WITH due AS (
+                SELECT id
+                FROM notifications
+                WHERE status = 'pending' AND NOT digest AND next_attempt_at <= now()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE notifications n
+            SET next_attempt_at = $2
+            FROM due, users u
+            LEFT JOIN notification_preferences p ON p.user_id = u.id
+            WHERE n.id = due.id AND u.id = n.user_id
+            RETURNING n.id, n.user_id, n.kind, n.data, n.attempt_count, u.email, u.name,
+                COALESCE(p.language, 'zh') AS language
+            "#,
+        )
+        .bind(limit)
+        .bind(lease_until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    // 取出需要发送汇总邮件的用户的全部待发送通知：
+    // 用户最早一条待汇总的通知早于 due_before 时，汇总发送该用户的所有待发送通知
+    pub async fn claim_digests(
+        &self,
+        due_before: DateTime<Utc>,
+        max_users: i64,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Vec<DueNotification>, AppError> {
+        let notifications = query_as::<_, DueNotification>(
+            r#"
+            WITH due_users AS (
+                SELECT user_id
+                FROM notifications
+                WHERE status = 'pending' AND digest AND next_attempt_at <= now()
+                GROUP BY user_id
+                HAVING MIN(created_at) <= $1
+                LIMIT $2
+            ),
+            due AS (
+                SELECT id
+                FROM notifications
+                WHERE status = 'pending' AND digest AND next_attempt_at <= now()
+                  AND user_id IN (SELECT user_id FROM due_users)
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE notifications n
+            SET next_attempt_at = $3
+            FROM due, users u
+            LEFT JOIN notification_preferences p ON p.user_id = u.id
+            WHERE n.id = due.id AND u.id = n.user_id
+            RETURNING n.id, n.user_id, n.kind, n.data, n.attempt_count, u.email, u.name,
+                COALESCE(p.language, 'zh') AS language
+            "#,
+        )
+        .bind(due_before)
+        .bind(max_users)
+        .bind(lease_until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    pub async fn mark_sent(&self, ids: &[UuidType]) -> Result<(), AppError> {
+        query(
+            r#"
+            UPDATE notifications
+            SET status = 'sent', sent_at = $2, attempt_count = attempt_count + 1, error = NULL
+            WHERE id = ANY($1)
+            "#,
+        )
+        .bind(ids)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // 记录发送失败；retry_at 为空表示不再重试
+    pub async fn mark_failed(
+        &self,
+        ids: &[UuidType],
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), AppError> {
+        let status = match retry_at {
+            Some(_) => NotificationStatus::Pending,
+            None => NotificationStatus::Failed,
+        };
+
+        query(
+            r#"
+            UPDATE notifications
+            SET status = $2, error = $3, attempt_count = attempt_count + 1,
+                next_attempt_at = COALESCE($4, next_attempt_at)
+            WHERE id = ANY($1)
+            "#,
+        )
+        .bind(ids)
+        .bind(status)
+        .bind(error)
+        .bind(retry_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn ensure_ticket_exists(&self, ticket_id: UuidType) -> Result<(), AppError> {
+        let exists: bool = query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM tickets WHERE id = $1 AND deleted_at IS NULL)",
+        )
+        .bind(ticket_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if !exists {
+            return Err(AppError::not_found("工单"));
+        }
+
+        Ok(())
+    }
+}
+
+// 在调用方事务中根据工单事件生成通知：
+// 报告人、指派人、评论者和被提及的用户自动成为关注者；操作者本人不会收到通知
+pub(crate) async fn enqueue(
+    conn: &mut PgConnection,
+    ticket_id: UuidType,
+    actor_id: Option<UuidType>,
+    trigger: NotificationTrigger,
+) -> Result<(), AppError> {
+    match trigger {
+        NotificationTrigger::Created { assignee_id } => {
+            let watchers: Vec<UuidType> = actor_id.into_iter().chain(assignee_id).collect();
+            add_watchers(conn, ticket_id, &watchers).await?;
+            if let Some(assignee_id) = assignee_id {
+                let data = json!({});
+                let recipients = [assignee_id];
+                insert(
+                    conn,
+                    ticket_id,
+                    actor_id,
+                    NotificationKind::Assigned,
+                    &recipients,
+                    data,
+                )
+                .await?;
+            }
+        }
+        NotificationTrigger::Assigned { assignee_id } => {
+            add_watchers(conn, ticket_id, &[assignee_id]).await?;
+            let recipients = [assignee_id];
+            insert(
+                conn,
+                ticket_id,
+                actor_id,
+                NotificationKind::Assigned,
+                &recipients,
+                json!({}),
+            )
+            .await?;
+        }
+        NotificationTrigger::StatusChanged {
+            old_status,
+            new_status,
+        } => {
+            let watchers = watcher_ids(conn, ticket_id).await?;
+            let data = json!({ "old_status": old_status, "new_status": new_status });
+            insert(
+                conn,
+                ticket_id,
+                actor_id,
+                NotificationKind::StatusChanged,
+                &watchers,
+                data,
+            )
+            .await?;
+        }
+        NotificationTrigger::CommentAdded {
+            comment_id,
+            content,
+        } => {
+            let mentioned = mentioned_user_ids(conn, &content).await?;
+            let new_watchers: Vec<UuidType> = actor_id
+                .into_iter()
+                .chain(mentioned.iter().copied())
+                .collect();
+            add_watchers(conn, ticket_id, &new_watchers).await?;
+
+            // 被提及的用户只收到提及通知
+            let watchers: Vec<UuidType> = watcher_ids(conn, ticket_id)
+                .await?
+                .into_iter()
+                .filter(|id| !mentioned.contains(id))
+                .collect();
+            let data = json!({ "comment": { "id": comment_id, "excerpt": excerpt(&content) } });
+            insert(
+                conn,
+                ticket_id,
+                actor_id,
+                NotificationKind::Mentioned,
+                &mentioned,
+                data.clone(),
+            )
+            .await?;
+            insert(
+                conn,
+                ticket_id,
+                actor_id,
+                NotificationKind::CommentAdded,
+                &watchers,
+                data,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn add_watchers(
+    conn: &mut PgConnection,
+    ticket_id: UuidType,
+    user_ids: &[UuidType],
+) -> Result<(), AppError> {
+    if user_ids.is_empty() {
+        return Ok(());
+    }
+
+    query(
+        r#"
+        INSERT INTO ticket_watchers (ticket_id, user_id, created_at)
+        SELECT $1, id, $3 FROM users WHERE id = ANY($2)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(ticket_id)
+    .bind(user_ids)
+    .bind(chrono::Utc::now())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn watcher_ids(
+    conn: &mut PgConnection,
+    ticket_id: UuidType,
+) -> Result<Vec<UuidType>, AppError> {
+    let ids = query_scalar("SELECT user_id FROM ticket_watchers WHERE ticket_id = $1")
+        .bind(ticket_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(ids)
+}
+
+// 评论中 @邮箱 提及的有效用户
+async fn mentioned_user_ids(
+    conn: &mut PgConnection,
+    content: &str,
+) -> Result<Vec<UuidType>, AppError> {
+    let emails = extract_mentions(content);
+    if emails.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids = query_scalar("SELECT id FROM users WHERE lower(email) = ANY($1) AND is_active")
+        .bind(&emails)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(ids)
+}
+
+// 为收件人写入通知：跳过操作者本人、已停用的用户、关闭了邮件或该类通知的用户；
+// data 中附带工单与操作者的快照
+async fn insert(
+    conn: &mut PgConnection,
+    ticket_id: UuidType,
+    actor_id: Option<UuidType>,
+    kind: NotificationKind,
+    recipients: &[UuidType],
+    data: Value,
+) -> Result<(), AppError> {
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    // 偏好中的开关列与通知类型同名
+    let preference_column = match kind {
+        NotificationKind::Assigned => "assigned",
+        NotificationKind::StatusChanged => "status_changed",
+        NotificationKind::CommentAdded => "comment_added",
+        NotificationKind::Mentioned => "mentioned",
+    };
+    let now = chrono::Utc::now();
+
+    query(&format!(
+        r#"
+        INSERT INTO notifications (
+            id, user_id, ticket_id, actor_id, kind, data, digest, next_attempt_at, created_at
+        )
+        SELECT
+            gen_random_uuid(),
+            u.id,
+            $1,
+            $2,
+            $3,
+            jsonb_build_object(
+                'ticket', (
+                    SELECT jsonb_build_object(
+                        'id', t.id, 'title', t.title, 'status', t.status, 'priority', t.priority
+                    )
+                    FROM tickets t WHERE t.id = $1
+                ),
+                'actor', (SELECT jsonb_build_object('id', a.id, 'name', a.name) FROM users a WHERE a.id = $2)
+            ) || $4,
+            COALESCE(p.delivery, 'immediate') = 'digest',
+            $5,
+            $5
+        FROM users u
+        LEFT JOIN notification_preferences p ON p.user_id = u.id
+        WHERE u.id = ANY($6)
+          AND u.is_active
+          AND u.id IS DISTINCT FROM $2
+          AND COALESCE(p.delivery, 'immediate') != 'off'
+          AND COALESCE(p.{preference_column}, TRUE)
+        "#
+    ))
+    .bind(ticket_id)
+    .bind(actor_id)
+    .bind(kind)
+    .bind(data)
+    .bind(now)
+    .bind(recipients)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
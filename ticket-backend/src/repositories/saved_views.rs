@@ -0,0 +1,181 @@
+use crate::{
+    database::DbPool,
+    error::AppError,
+    models::{CreateSavedViewRequest, SavedView, UpdateSavedViewRequest, ViewFilters},
+    utils::{query_language::TicketFilter, sort::parse_sort},
+};
+use sqlx::{query, query_as, query_scalar, types::Json, Postgres, QueryBuilder};
+use uuid::Uuid as UuidType;
+
+const SAVED_VIEW_COLUMNS: &str = "id, owner_id, name, shared, filters, created_at, updated_at";
+
+pub struct SavedViewRepository {
+    pool: DbPool,
+}
+
+impl SavedViewRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 列出用户可见的视图：自己的视图在前，其后为他人共享的视图
+    pub async fn list_visible(&self, user_id: UuidType) -> Result<Vec<SavedView>, AppError> {
+        let views = query_as::<_, SavedView>(&format!(
+            r#"
+            SELECT {SAVED_VIEW_COLUMNS} FROM saved_views
+            WHERE owner_id = $1 OR shared
+            ORDER BY owner_id = $1 DESC, name
+            "#
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(views)
+    }
+
+    // 获取用户可见的视图，他人的私有视图视为不存在
+    pub async fn get_visible(
+        &self,
+        id: UuidType,
+        user_id: UuidType,
+    ) -> Result<SavedView, AppError> {
+        query_as::<_, SavedView>(&format!(
+            "SELECT {SAVED_VIEW_COLUMNS} FROM saved_views WHERE id = $1 AND (owner_id = $2 OR shared)"
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("视图"))
+    }
+
+    pub async fn create(
+        &self,
+        owner_id: UuidType,
+        request: CreateSavedViewRequest,
+    ) -> Result<SavedView, AppError> {
+        if self.name_exists(owner_id, &request.name, None).await? {
+            return Err(AppError::conflict("视图名称"));
+        }
+        validate_filters(&request.filters, owner_id)?;
+
+        let view = query_as::<_, SavedView>(&format!(
+            r#"
+            INSERT INTO saved_views (id, owner_id, name, shared, filters, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $6)
+            RETURNING {SAVED_VIEW_COLUMNS}
+            "#
+        ))
+        .bind(UuidType::new_v4())
+        .bind(owner_id)
+        .bind(request.name.trim())
+        .bind(request.shared)
+        .bind(Json(request.filters))
+        .bind(chrono::Utc::now())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(view)
+    }
+
+    // 更新视图（仅限创建者）
+    pub async fn update(
+        &self,
+        id: UuidType,
+        user_id: UuidType,
+        request: UpdateSavedViewRequest,
+    ) -> Result<SavedView, AppError> {
+        self.ensure_owner(id, user_id).await?;
+
+        if let Some(ref name) = request.name {
+            if self.name_exists(user_id, name, Some(id)).await? {
+                return Err(AppError::conflict("视图名称"));
+            }
+        }
+        if let Some(ref filters) = request.filters {
+            validate_filters(filters, user_id)?;
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new("UPDATE saved_views SET updated_at = ");
+        builder.push_bind(chrono::Utc::now());
+
+        if let Some(name) = request.name {
+            builder.push(", name = ").push_bind(name.trim().to_string());
+        }
+
+        if let Some(shared) = request.shared {
+            builder.push(", shared = ").push_bind(shared);
+        }
+
+        if let Some(filters) = request.filters {
+            builder.push(", filters = ").push_bind(Json(filters));
+        }
+
+        builder.push(" WHERE id = ").push_bind(id);
+        builder.push(format!(" RETURNING {SAVED_VIEW_COLUMNS}"));
+
+        let view = builder
+            .build_query_as::<SavedView>()
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(view)
+    }
+
+    // 删除视图（仅限创建者）
+    pub async fn delete(&self, id: UuidType, user_id: UuidType) -> Result<(), AppError> {
+        self.ensure_owner(id, user_id).await?;
+
+        query("DELETE FROM saved_views WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // 共享视图对其他用户只读
+    async fn ensure_owner(&self, id: UuidType, user_id: UuidType) -> Result<(), AppError> {
+        let view = self.get_visible(id, user_id).await?;
+        if view.owner_id != user_id {
+            return Err(AppError::forbidden("只有视图的创建者可以修改或删除视图"));
+        }
+
+        Ok(())
+    }
+
+    // 同一用户的视图名称不能重复
+    async fn name_exists(
+        &self,
+        owner_id: UuidType,
+        name: &str,
+        exclude_id: Option<UuidType>,
+    ) -> Result<bool, AppError> {
+        let exists: bool = query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM saved_views \
+             WHERE owner_id = $1 AND name = $2 AND ($3::uuid IS NULL OR id != $3))",
+        )
+        .bind(owner_id)
+        .bind(name.trim())
+        .bind(exclude_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+}
+
+// 保存前校验查询语言与排序参数，避免保存无法使用的视图
+fn validate_filters(filters: &ViewFilters, owner_id: UuidType) -> Result<(), AppError> {
+    if let Some(ref q) = filters.q {
+        TicketFilter::parse(q, owner_id)?;
+    }
+    parse_sort(
+        filters.sort.as_deref(),
+        filters.sort_by.as_deref(),
+        filters.sort_order.as_deref(),
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use crate::{database::DbPool, error::AppError, models::StreamEvent, repositories::webhooks};
+use serde_json::{json, Value};
+use sqlx::{query, query_as, query_scalar, PgConnection};
+use uuid::Uuid as UuidType;
+
+// 新事件写入后通过该频道通知所有后端实例
+pub const STREAM_EVENTS_CHANNEL: &str = "stream_events";
+
+const STREAM_EVENT_COLUMNS: &str = "id, event, payload, created_at";
+
+pub struct StreamEventRepository {
+    pool: DbPool,
+}
+
+impl StreamEventRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<StreamEvent>, AppError> {
+        let event = query_as::<_, StreamEvent>(&format!(
+            "SELECT {STREAM_EVENT_COLUMNS} FROM stream_events WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(event)
+    }
+
+    // 按顺序获取指定 id 之后的事件
+    pub async fn list_after(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<StreamEvent>, AppError> {
+        let events = query_as::<_, StreamEvent>(&format!(
+            "SELECT {STREAM_EVENT_COLUMNS} FROM stream_events WHERE id > $1 ORDER BY id LIMIT $2"
+        ))
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    pub async fn latest_id(&self) -> Result<i64, AppError> {
+        let id: Option<i64> = query_scalar("SELECT MAX(id) FROM stream_events")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(id.unwrap_or(0))
+    }
+
+    // 客户端收到的最后一个事件之后没有被清理的事件时才能完整补发。
+    // 序号来自序列，回滚的事务会留下空洞，因此与清理水位比较，而不是与最早保留的序号比较
+    pub async fn can_replay_after(&self, last_event_id: i64) -> Result<bool, AppError> {
+        let pruned_through: Option<i64> =
+            query_scalar("SELECT pruned_through FROM stream_event_prunes")
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(pruned_through.is_none_or(|pruned_through| last_event_id >= pruned_through))
+    }
+
+    // 清理超过保留期的事件并推进清理水位，返回删除的数量
+    pub async fn prune(&self, retention: chrono::Duration) -> Result<u64, AppError> {
+        let count: i64 = query_scalar(
+            r#"
+            WITH pruned AS (
+                DELETE FROM stream_events WHERE created_at < $1 RETURNING id
+            ),
+            watermark AS (
+                INSERT INTO stream_event_prunes (pruned_through)
+                SELECT MAX(id) FROM pruned HAVING COUNT(*) > 0
+                ON CONFLICT (id) DO UPDATE
+                SET pruned_through = GREATEST(
+                    stream_event_prunes.pruned_through, EXCLUDED.pruned_through
+                )
+            )
+            SELECT COUNT(*) FROM pruned
+            "#,
+        )
+        .bind(chrono::Utc::now() - retention)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as u64)
+    }
+}
+
+// 在调用方事务中发布事件：写入实时事件流并 NOTIFY（提交后才会送达各实例），
+// 同时为订阅了该事件的 Webhook 创建投递任务。
+// 指定 ticket_id 时在 data.ticket 中附带工单当前的快照
+pub(crate) async fn publish(
+    conn: &mut PgConnection,
+    event: &str,
+    ticket_id: Option<UuidType>,
+    data: Value,
+) -> Result<(), AppError> {
+    let now = chrono::Utc::now();
+    let payload = json!({
+        "event": event,
+        "occurred_at": now,
+        "data": data,
+    });
+
+    let (id, payload): (i64, Value) = query_as(
+        r#"
+        INSERT INTO stream_events (event, payload, created_at)
+        VALUES (
+            $1,
+            CASE
+                WHEN $3::uuid IS NULL THEN $2
+                ELSE jsonb_set($2, '{data,ticket}',
+                    COALESCE((SELECT to_jsonb(t) FROM tickets t WHERE t.id = $3), 'null'::jsonb))
+            END,
+            $4
+        )
+        RETURNING id, payload
+        "#,
+    )
+    .bind(event)
+    .bind(payload)
+    .bind(ticket_id)
+    .bind(now)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    query("SELECT pg_notify($1, $2)")
+        .bind(STREAM_EVENTS_CHANNEL)
+        .bind(id.to_string())
+        .execute(&mut *conn)
+        .await?;
+
+    webhooks::enqueue(conn, event, &payload).await
+}
@@ -0,0 +1,199 @@
+use crate::{
+    database::DbPool,
+    error::AppError,
+    models::{CreateTagGroupRequest, Tag, TagGroup, TagGroupWithTags, UpdateTagGroupRequest},
+    repositories::tags::TAG_COLUMNS,
+};
+use sqlx::{query, query_as, query_scalar, PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid as UuidType;
+
+const TAG_GROUP_COLUMNS: &str = "id, name, exclusive, created_at, updated_at";
+
+pub struct TagGroupRepository {
+    pool: DbPool,
+}
+
+impl TagGroupRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 获取所有分组及其标签
+    pub async fn list(&self) -> Result<Vec<TagGroupWithTags>, AppError> {
+        let groups = query_as::<_, TagGroup>(&format!(
+            "SELECT {TAG_GROUP_COLUMNS} FROM tag_groups ORDER BY name"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let ids: Vec<UuidType> = groups.iter().map(|group| group.id).collect();
+        let tags = query_as::<_, Tag>(&format!(
+            "SELECT {TAG_COLUMNS} FROM tags WHERE group_id = ANY($1) ORDER BY name"
+        ))
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(groups
+            .into_iter()
+            .map(|group| TagGroupWithTags {
+                tags: tags
+                    .iter()
+                    .filter(|tag| tag.group_id == Some(group.id))
+                    .cloned()
+                    .collect(),
+                group,
+            })
+            .collect())
+    }
+
+    // 获取单个分组及其标签
+    pub async fn get(&self, id: UuidType) -> Result<TagGroupWithTags, AppError> {
+        let group = query_as::<_, TagGroup>(&format!(
+            "SELECT {TAG_GROUP_COLUMNS} FROM tag_groups WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("标签分组"))?;
+
+        let tags = query_as::<_, Tag>(&format!(
+            "SELECT {TAG_COLUMNS} FROM tags WHERE group_id = $1 ORDER BY name"
+        ))
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(TagGroupWithTags { group, tags })
+    }
+
+    pub async fn create(&self, request: CreateTagGroupRequest) -> Result<TagGroup, AppError> {
+        if self.name_exists(&request.name, None).await? {
+            return Err(AppError::conflict("标签分组名称"));
+        }
+
+        let group = query_as::<_, TagGroup>(&format!(
+            r#"
+            INSERT INTO tag_groups (id, name, exclusive, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $4)
+            RETURNING {TAG_GROUP_COLUMNS}
+            "#
+        ))
+        .bind(UuidType::new_v4())
+        .bind(request.name.trim())
+        .bind(request.exclusive)
+        .bind(chrono::Utc::now())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(group)
+    }
+
+    // 更新分组；设为互斥时，已有工单不能带有该分组中的多个标签
+    pub async fn update(
+        &self,
+        id: UuidType,
+        request: UpdateTagGroupRequest,
+    ) -> Result<TagGroup, AppError> {
+        if let Some(ref name) = request.name {
+            if self.name_exists(name, Some(id)).await? {
+                return Err(AppError::conflict("标签分组名称"));
+            }
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let mut builder = QueryBuilder::<Postgres>::new("UPDATE tag_groups SET updated_at = ");
+        builder.push_bind(chrono::Utc::now());
+
+        if let Some(name) = request.name {
+            builder.push(", name = ").push_bind(name.trim().to_string());
+        }
+
+        if let Some(exclusive) = request.exclusive {
+            builder.push(", exclusive = ").push_bind(exclusive);
+        }
+
+        builder
+            .push(" WHERE id = ")
+            .push_bind(id)
+            .push(format!(" RETURNING {TAG_GROUP_COLUMNS}"));
+
+        let group = builder
+            .build_query_as::<TagGroup>()
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::not_found("标签分组"))?;
+
+        ensure_exclusive(&mut tx, id).await?;
+
+        tx.commit().await?;
+
+        Ok(group)
+    }
+
+    // 删除分组，分组中的标签保留并移出分组
+    pub async fn delete(&self, id: UuidType) -> Result<(), AppError> {
+        let result = query("DELETE FROM tag_groups WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("标签分组"));
+        }
+
+        Ok(())
+    }
+
+    async fn name_exists(
+        &self,
+        name: &str,
+        exclude_id: Option<UuidType>,
+    ) -> Result<bool, AppError> {
+        let exists: bool = query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM tag_groups \
+             WHERE name = $1 AND ($2::uuid IS NULL OR id != $2))",
+        )
+        .bind(name.trim())
+        .bind(exclude_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+}
+
+// 互斥分组中不能有工单同时带有多个标签（在调用方事务中检查，失败时由调用方回滚）
+pub(crate) async fn ensure_exclusive(
+    conn: &mut PgConnection,
+    group_id: UuidType,
+) -> Result<(), AppError> {
+    let conflict: Option<(String, i64)> = query_as(
+        r#"
+        SELECT g.name, COUNT(*)
+        FROM tag_groups g
+        INNER JOIN (
+            SELECT t.group_id, tt.ticket_id
+            FROM ticket_tags tt
+            INNER JOIN tags t ON t.id = tt.tag_id
+            WHERE t.group_id = $1
+            GROUP BY t.group_id, tt.ticket_id
+            HAVING COUNT(*) > 1
+        ) multi ON multi.group_id = g.id
+        WHERE g.id = $1 AND g.exclusive
+        GROUP BY g.name
+        "#,
+    )
+    .bind(group_id)
+    .fetch_optional(conn)
+    .await?;
+
+    match conflict {
+        Some((name, tickets)) => Err(AppError::bad_request(format!(
+            "有 {} 个工单带有互斥分组「{}」中的多个标签，请先调整这些工单的标签",
+            tickets, name
+        ))),
+        None => Ok(()),
+    }
+}
@@ -1,11 +1,27 @@
 use crate::{
     database::DbPool,
     error::AppError,
-    models::{CreateTagRequest, Tag, TagWithCount, UpdateTagRequest},
+    models::{
+        CreateTagRequest, Tag, TagAlias, TagMergeResult, TagNode, TagWithCount, TicketEventType,
+        UpdateTagRequest,
+    },
+    precondition::{self, IfMatch},
+    repositories::{
+        stream_events,
+        tag_groups::ensure_exclusive,
+        ticket_events::{record_all, NewTicketEvent},
+        TicketRepository,
+    },
+    utils::color,
 };
-use sqlx::{query, query_as, query_scalar, Row};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{query, query_as, query_scalar, PgConnection, Postgres, QueryBuilder};
 use uuid::Uuid as UuidType;
 
+pub(crate) const TAG_COLUMNS: &str =
+    "id, name, color, parent_id, group_id, version, created_at, updated_at";
+
 pub struct TagRepository {
     pool: DbPool,
 }
@@ -19,156 +35,125 @@ impl TagRepository {
     pub async fn create(&self, request: CreateTagRequest) -> Result<Tag, AppError> {
         let id = UuidType::new_v4();
         let now = chrono::Utc::now();
-        let color = request.color.unwrap_or_else(|| "#3B82F6".to_string());
+        let color = match request.color {
+            Some(ref color) => color::normalize(color),
+            None => self.next_palette_color().await?,
+        };
+
+        if let Some(parent_id) = request.parent_id {
+            self.ensure_parent_exists(parent_id).await?;
+        }
+        if let Some(group_id) = request.group_id {
+            self.ensure_group_exists(group_id).await?;
+        }
 
-        let tag = sqlx::query!(
+        let mut tx = self.pool.begin().await?;
+
+        let tag = query_as::<_, Tag>(&format!(
             r#"
-            INSERT INTO tags (id, name, color, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $4)
-            RETURNING id, name, color, created_at, updated_at
-            "#,
-            id,
-            request.name,
-            color,
-            now
-        )
-        .fetch_one(&self.pool)
+            INSERT INTO tags (id, name, color, parent_id, group_id, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $6)
+            RETURNING {TAG_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .bind(request.name)
+        .bind(color)
+        .bind(request.parent_id)
+        .bind(request.group_id)
+        .bind(now)
+        .fetch_one(&mut *tx)
         .await?;
 
-        let tag = Tag {
-            id: tag.id,
-            name: tag.name,
-            color: tag.color,
-            created_at: tag.created_at,
-            updated_at: tag.updated_at,
-        };
+        stream_events::publish(&mut tx, "tag.created", None, json!({ "tag": tag })).await?;
+
+        tx.commit().await?;
 
         Ok(tag)
     }
 
     // 根据ID获取标签
     pub async fn get_by_id(&self, id: UuidType) -> Result<Tag, AppError> {
-        let tag = sqlx::query!(
-            "SELECT id, name, color, created_at, updated_at FROM tags WHERE id = $1",
-            id
-        )
-        .fetch_optional(&self.pool)
-        .await?
-        .ok_or_else(|| AppError::not_found("标签"))?;
-
-        let tag = Tag {
-            id: tag.id,
-            name: tag.name,
-            color: tag.color,
-            created_at: tag.created_at,
-            updated_at: tag.updated_at,
-        };
-
-        Ok(tag)
+        query_as::<_, Tag>(&format!("SELECT {TAG_COLUMNS} FROM tags WHERE id = $1"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("标签"))
     }
 
-    // 根据名称获取标签
+    // 根据名称获取标签，名称未命中时按别名解析
     pub async fn get_by_name(&self, name: &str) -> Result<Tag, AppError> {
-        let tag = sqlx::query!(
-            "SELECT id, name, color, created_at, updated_at FROM tags WHERE name = $1",
-            name
-        )
+        query_as::<_, Tag>(&format!(
+            r#"
+            SELECT {TAG_COLUMNS} FROM tags
+            WHERE name = $1 OR id = (SELECT tag_id FROM tag_aliases WHERE name = $1)
+            ORDER BY name = $1 DESC
+            LIMIT 1
+            "#
+        ))
+        .bind(name)
         .fetch_optional(&self.pool)
         .await?
-        .ok_or_else(|| AppError::not_found("标签"))?;
-
-        let tag = Tag {
-            id: tag.id,
-            name: tag.name,
-            color: tag.color,
-            created_at: tag.created_at,
-            updated_at: tag.updated_at,
-        };
-
-        Ok(tag)
+        .ok_or_else(|| AppError::not_found("标签"))
     }
 
-    // 检查名称是否已存在
-    pub async fn name_exists(&self, name: &str, exclude_id: Option<UuidType>) -> Result<bool, AppError> {
-        let count: i64 = if let Some(exclude_id) = exclude_id {
-            query_scalar!(
-                "SELECT COUNT(*) FROM tags WHERE name = $1 AND id != $2",
-                name,
-                exclude_id
-            )
-            .fetch_one(&self.pool)
-            .await?
-        } else {
-            query_scalar!("SELECT COUNT(*) FROM tags WHERE name = $1", name)
-                .fetch_one(&self.pool)
-                .await?
-        };
+    // 检查名称是否已被其他标签或其别名占用
+    pub async fn name_exists(
+        &self,
+        name: &str,
+        exclude_id: Option<UuidType>,
+    ) -> Result<bool, AppError> {
+        let exists: bool = query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM tags WHERE name = $1 AND ($2::uuid IS NULL OR id != $2)) \
+             OR EXISTS(SELECT 1 FROM tag_aliases WHERE name = $1 AND ($2::uuid IS NULL OR tag_id != $2))",
+        )
+        .bind(name)
+        .bind(exclude_id)
+        .fetch_one(&self.pool)
+        .await?;
 
-        Ok(count > 0)
+        Ok(exists)
     }
 
     // 获取所有标签
     pub async fn list(&self) -> Result<Vec<Tag>, AppError> {
-        let rows = sqlx::query!(
-            "SELECT id, name, color, created_at, updated_at FROM tags ORDER BY name"
-        )
-        .fetch_all(&self.pool)
-        .await?;
-
-        let tags: Vec<Tag> = rows.into_iter().map(|row| {
-            Tag {
-                id: row.id,
-                name: row.name,
-                color: row.color,
-                created_at: row.created_at,
-                updated_at: row.updated_at,
-            }
-        }).collect();
+        let tags = query_as::<_, Tag>(&format!("SELECT {TAG_COLUMNS} FROM tags ORDER BY name"))
+            .fetch_all(&self.pool)
+            .await?;
 
         Ok(tags)
     }
 
     // 获取带工单数量的标签
     pub async fn list_with_counts(&self) -> Result<Vec<TagWithCount>, AppError> {
-        let rows = sqlx::query!(
+        let tags = query_as::<_, TagWithCount>(
             r#"
             SELECT
-                t.id, t.name, t.color, t.created_at, t.updated_at,
-                COALESCE(tt.ticket_count, 0) as "ticket_count!"
+                t.id, t.name, t.color, t.parent_id, t.group_id, t.version, t.created_at, t.updated_at,
+                COALESCE(tt.ticket_count, 0) AS ticket_count
             FROM tags t
             LEFT JOIN (
-                SELECT tag_id, COUNT(*) as ticket_count
-                FROM ticket_tags
-                GROUP BY tag_id
+                SELECT link.tag_id, COUNT(*) AS ticket_count
+                FROM ticket_tags link
+                INNER JOIN tickets tk ON tk.id = link.ticket_id AND tk.deleted_at IS NULL
+                GROUP BY link.tag_id
             ) tt ON t.id = tt.tag_id
             ORDER BY t.name
-            "#
+            "#,
         )
         .fetch_all(&self.pool)
         .await?;
 
-        let tags: Vec<TagWithCount> = rows.into_iter().map(|row| {
-            let tag = Tag {
-                id: row.id,
-                name: row.name,
-                color: row.color,
-                created_at: row.created_at,
-                updated_at: row.updated_at,
-            };
-
-            TagWithCount {
-                tag,
-                ticket_count: row.ticket_count,
-            }
-        }).collect();
-
         Ok(tags)
     }
 
     // 更新标签
-    pub async fn update(&self, id: UuidType, request: UpdateTagRequest) -> Result<Tag, AppError> {
-        let now = chrono::Utc::now();
-
+    pub async fn update(
+        &self,
+        id: UuidType,
+        request: UpdateTagRequest,
+        if_match: Option<&IfMatch>,
+    ) -> Result<Tag, AppError> {
         // 检查名称是否已存在
         if let Some(ref name) = request.name {
             if self.name_exists(name, Some(id)).await? {
@@ -176,162 +161,858 @@ impl TagRepository {
             }
         }
 
-        // 构建动态更新查询
-        let mut updates = Vec::new();
-        let mut query = sqlx::query("UPDATE tags SET updated_at = $1").bind(now);
+        let mut tx = self.pool.begin().await?;
+
+        let current = lock(&mut tx, id).await?;
+        ensure_precondition(if_match, &current)?;
+
+        if let Some(ref name) = request.name {
+            if *name != current.name {
+                // 改回自身的别名时，别名由正式名称取代
+                query("DELETE FROM tag_aliases WHERE name = $1")
+                    .bind(name)
+                    .execute(&mut *tx)
+                    .await?;
+
+                if request.keep_alias {
+                    query("INSERT INTO tag_aliases (name, tag_id) VALUES ($1, $2)")
+                        .bind(&current.name)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new("UPDATE tags SET updated_at = ");
+        builder.push_bind(chrono::Utc::now());
 
         if let Some(name) = request.name {
-            updates.push(", name = $2");
-            query = query.bind(name);
+            builder.push(", name = ").push_bind(name);
         }
 
-        if let Some(color) = request.color {
-            updates.push(", color = $3");
-            query = query.bind(color);
+        if let Some(ref color) = request.color {
+            builder
+                .push(", color = ")
+                .push_bind(color::normalize(color));
         }
 
-        let sql = format!("{} WHERE id = $4 RETURNING id, name, color, created_at, updated_at",
-                         format!("UPDATE tags SET updated_at = $1{}", updates.join("")));
+        builder
+            .push(" WHERE id = ")
+            .push_bind(id)
+            .push(format!(" RETURNING {TAG_COLUMNS}"));
 
-        let mut query = sqlx::query(&sql).bind(now);
+        let tag = builder.build_query_as::<Tag>().fetch_one(&mut *tx).await?;
 
-        if let Some(name) = request.name {
-            query = query.bind(name);
+        stream_events::publish(&mut tx, "tag.updated", None, json!({ "tag": tag })).await?;
+
+        tx.commit().await?;
+
+        Ok(tag)
+    }
+
+    // 移动标签到新的父标签下（parent_id 为空时成为根标签），不允许形成环
+    pub async fn move_to(
+        &self,
+        id: UuidType,
+        parent_id: Option<UuidType>,
+        if_match: Option<&IfMatch>,
+    ) -> Result<Tag, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        lock_hierarchy(&mut tx).await?;
+
+        let current = lock(&mut tx, id).await?;
+        ensure_precondition(if_match, &current)?;
+
+        if let Some(parent_id) = parent_id {
+            if parent_id == id {
+                return Err(AppError::bad_request("不能将标签移动到其自身下"));
+            }
+
+            let exists: bool = query_scalar("SELECT EXISTS(SELECT 1 FROM tags WHERE id = $1)")
+                .bind(parent_id)
+                .fetch_one(&mut *tx)
+                .await?;
+            if !exists {
+                return Err(AppError::not_found("父标签"));
+            }
+
+            let is_descendant: bool = query_scalar(
+                r#"
+                WITH RECURSIVE descendants AS (
+                    SELECT id FROM tags WHERE parent_id = $1
+                    UNION
+                    SELECT child.id FROM tags child
+                    INNER JOIN descendants d ON child.parent_id = d.id
+                )
+                SELECT EXISTS(SELECT 1 FROM descendants WHERE id = $2)
+                "#,
+            )
+            .bind(id)
+            .bind(parent_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if is_descendant {
+                return Err(AppError::bad_request("不能将标签移动到其子标签下"));
+            }
+        }
+
+        let tag = query_as::<_, Tag>(&format!(
+            "UPDATE tags SET parent_id = $2, updated_at = $3 WHERE id = $1 RETURNING {TAG_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(parent_id)
+        .bind(chrono::Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        stream_events::publish(&mut tx, "tag.updated", None, json!({ "tag": tag })).await?;
+
+        tx.commit().await?;
+
+        Ok(tag)
+    }
+
+    // 设置标签所属分组（group_id 为空时移出分组）；加入互斥分组时，已有工单不能因此带有该分组中的多个标签
+    pub async fn set_group(
+        &self,
+        id: UuidType,
+        group_id: Option<UuidType>,
+        if_match: Option<&IfMatch>,
+    ) -> Result<Tag, AppError> {
+        if let Some(group_id) = group_id {
+            self.ensure_group_exists(group_id).await?;
         }
-        if let Some(color) = request.color {
-            query = query.bind(color);
+
+        let mut tx = self.pool.begin().await?;
+
+        let current = lock(&mut tx, id).await?;
+        ensure_precondition(if_match, &current)?;
+
+        let tag = query_as::<_, Tag>(&format!(
+            "UPDATE tags SET group_id = $2, updated_at = $3 WHERE id = $1 RETURNING {TAG_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(group_id)
+        .bind(chrono::Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(group_id) = group_id {
+            ensure_exclusive(&mut tx, group_id).await?;
         }
-        query = query.bind(id);
 
-        let tag = query.fetch_one(&self.pool).await?;
+        stream_events::publish(&mut tx, "tag.updated", None, json!({ "tag": tag })).await?;
 
-        let tag = Tag {
-            id: tag.get(0),
-            name: tag.get(1),
-            color: tag.get(2),
-            created_at: tag.get(3),
-            updated_at: tag.get(4),
-        };
+        tx.commit().await?;
 
         Ok(tag)
     }
 
-    // 删除标签
-    pub async fn delete(&self, id: UuidType) -> Result<(), AppError> {
-        // 先删除与工单的关联
-        query!("DELETE FROM ticket_tags WHERE tag_id = $1", id)
-            .execute(&self.pool)
+    // 将源标签合并到目标标签：工单关联转移到目标标签并去重，源标签的子标签与别名归入目标标签，
+    // 源标签名称保留为目标标签的别名，最后删除源标签
+    pub async fn merge(
+        &self,
+        target_id: UuidType,
+        source_ids: &[UuidType],
+        actor_id: Option<UuidType>,
+    ) -> Result<TagMergeResult, AppError> {
+        let mut source_ids = source_ids.to_vec();
+        source_ids.sort();
+        source_ids.dedup();
+
+        if source_ids.contains(&target_id) {
+            return Err(AppError::bad_request("不能将标签合并到其自身"));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        lock_hierarchy(&mut tx).await?;
+
+        let target = lock(&mut tx, target_id).await?;
+        let sources = query_as::<_, TagRef>(
+            "SELECT id, name FROM tags WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+        )
+        .bind(&source_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        if sources.len() != source_ids.len() {
+            return Err(AppError::not_found("标签"));
+        }
+
+        let inside_source: bool = query_scalar(
+            r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT parent_id AS id FROM tags WHERE id = $1
+                UNION
+                SELECT t.parent_id FROM tags t INNER JOIN ancestors a ON t.id = a.id
+            )
+            SELECT EXISTS(SELECT 1 FROM ancestors WHERE id = ANY($2))
+            "#,
+        )
+        .bind(target_id)
+        .bind(&source_ids)
+        .fetch_one(&mut *tx)
+        .await?;
+        if inside_source {
+            return Err(AppError::bad_request("不能将标签合并到其子标签"));
+        }
+
+        let removed = query_as::<_, TicketTagLink>(
+            r#"
+            DELETE FROM ticket_tags tt
+            USING tags t
+            WHERE tt.tag_id = ANY($1) AND t.id = tt.tag_id
+            RETURNING tt.ticket_id, t.id, t.name
+            "#,
+        )
+        .bind(&source_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut ticket_ids: Vec<UuidType> = removed.iter().map(|link| link.ticket_id).collect();
+        ticket_ids.sort();
+        ticket_ids.dedup();
+
+        let added: Vec<UuidType> = query_scalar(
+            r#"
+            INSERT INTO ticket_tags (ticket_id, tag_id)
+            SELECT UNNEST($1::uuid[]), $2
+            ON CONFLICT DO NOTHING
+            RETURNING ticket_id
+            "#,
+        )
+        .bind(&ticket_ids)
+        .bind(target_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        touch_tickets(&mut tx, &ticket_ids).await?;
+
+        query("UPDATE tags SET parent_id = $1 WHERE parent_id = ANY($2) AND NOT (id = ANY($2))")
+            .bind(target_id)
+            .bind(&source_ids)
+            .execute(&mut *tx)
             .await?;
 
-        // 删除标签
-        let result = query!("DELETE FROM tags WHERE id = $1", id)
-            .execute(&self.pool)
+        query("UPDATE tag_aliases SET tag_id = $1 WHERE tag_id = ANY($2)")
+            .bind(target_id)
+            .bind(&source_ids)
+            .execute(&mut *tx)
             .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(AppError::not_found("标签"));
+        query(
+            "INSERT INTO tag_aliases (name, tag_id) SELECT name, $1 FROM tags WHERE id = ANY($2)",
+        )
+        .bind(target_id)
+        .bind(&source_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        query("DELETE FROM tags WHERE id = ANY($1)")
+            .bind(&source_ids)
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some(group_id) = target.group_id {
+            ensure_exclusive(&mut tx, group_id).await?;
+        }
+
+        let target_ref = TagRef::from(&target);
+        let removed = removed.into_iter().map(|link| {
+            NewTicketEvent::new(link.ticket_id, actor_id, TicketEventType::TagRemoved)
+                .old_value(&link.tag)
+        });
+        let added = added.into_iter().map(|ticket_id| {
+            NewTicketEvent::new(ticket_id, actor_id, TicketEventType::TagAdded)
+                .new_value(&target_ref)
+        });
+        record_all(&mut tx, removed.chain(added)).await?;
+
+        stream_events::publish(
+            &mut tx,
+            "tag.merged",
+            None,
+            json!({ "tag": target, "sources": sources, "retagged_tickets": ticket_ids.len() }),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(TagMergeResult {
+            tag: target,
+            aliases: self.aliases(target_id).await?,
+            retagged_tickets: ticket_ids.len() as i64,
+        })
+    }
+
+    // 获取标签的别名
+    pub async fn aliases(&self, tag_id: UuidType) -> Result<Vec<TagAlias>, AppError> {
+        let aliases = query_as::<_, TagAlias>(
+            "SELECT name, tag_id, created_at FROM tag_aliases WHERE tag_id = $1 ORDER BY name",
+        )
+        .bind(tag_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(aliases)
+    }
+
+    // 标签树：root 为空时返回全部标签，否则返回以 root 为根的子树（含 root），按路径排序
+    pub async fn tree(&self, root: Option<UuidType>) -> Result<Vec<TagNode>, AppError> {
+        if let Some(root) = root {
+            self.get_by_id(root).await?;
         }
 
+        let nodes = query_as::<_, TagNode>(
+            r#"
+            WITH RECURSIVE tree AS (
+                SELECT t.*, 0 AS depth, t.name::text AS path, ARRAY[t.id] AS lineage
+                FROM tags t WHERE t.parent_id IS NULL
+                UNION ALL
+                SELECT c.*, tree.depth + 1, tree.path || '/' || c.name, tree.lineage || c.id
+                FROM tags c INNER JOIN tree ON c.parent_id = tree.id
+            )
+            SELECT id, name, color, parent_id, group_id, version, created_at, updated_at, depth, path
+            FROM tree
+            WHERE $1::uuid IS NULL OR $1 = ANY(lineage)
+            ORDER BY path
+            "#,
+        )
+        .bind(root)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(nodes)
+    }
+
+    // 删除标签（工单关联由外键级联删除，子标签上移到被删除标签的父标签下）
+    pub async fn delete(
+        &self,
+        id: UuidType,
+        actor_id: Option<UuidType>,
+        if_match: Option<&IfMatch>,
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = lock(&mut tx, id).await?;
+        ensure_precondition(if_match, &current)?;
+
+        query("UPDATE tags SET parent_id = $2 WHERE parent_id = $1")
+            .bind(id)
+            .bind(current.parent_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // 先显式解除关联，以便为受影响的工单记录历史
+        let ticket_ids: Vec<UuidType> =
+            query_scalar("DELETE FROM ticket_tags WHERE tag_id = $1 RETURNING ticket_id")
+                .bind(id)
+                .fetch_all(&mut *tx)
+                .await?;
+        touch_tickets(&mut tx, &ticket_ids).await?;
+
+        let name: String = query_scalar("DELETE FROM tags WHERE id = $1 RETURNING name")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::not_found("标签"))?;
+
+        let tag = TagRef { id, name };
+        record_all(
+            &mut tx,
+            ticket_ids.into_iter().map(|ticket_id| {
+                NewTicketEvent::new(ticket_id, actor_id, TicketEventType::TagRemoved)
+                    .old_value(&tag)
+            }),
+        )
+        .await?;
+
+        stream_events::publish(&mut tx, "tag.deleted", None, json!({ "tag": tag })).await?;
+
+        tx.commit().await?;
+
         Ok(())
     }
 
     // 获取工单的标签
     pub async fn get_ticket_tags(&self, ticket_id: UuidType) -> Result<Vec<Tag>, AppError> {
-        let rows = sqlx::query!(
+        let tags = query_as::<_, Tag>(
             r#"
-            SELECT t.id, t.name, t.color, t.created_at, t.updated_at
+            SELECT t.id, t.name, t.color, t.parent_id, t.group_id, t.version, t.created_at, t.updated_at
             FROM tags t
             INNER JOIN ticket_tags tt ON t.id = tt.tag_id
             WHERE tt.ticket_id = $1
             ORDER BY t.name
             "#,
-            ticket_id
         )
+        .bind(ticket_id)
         .fetch_all(&self.pool)
         .await?;
 
-        let tags: Vec<Tag> = rows.into_iter().map(|row| {
-            Tag {
-                id: row.id,
-                name: row.name,
-                color: row.color,
-                created_at: row.created_at,
-                updated_at: row.updated_at,
-            }
-        }).collect();
-
         Ok(tags)
     }
 
+    // 替换工单的全部标签，返回修改后的工单版本与标签
+    pub async fn set_ticket_tags(
+        &self,
+        ticket_id: UuidType,
+        tag_ids: &[UuidType],
+        actor_id: Option<UuidType>,
+        if_match: Option<&IfMatch>,
+    ) -> Result<(i32, Vec<Tag>), AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+        self.ensure_tags_exist(tag_ids).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut version = lock_ticket(&mut tx, ticket_id).await?;
+        self.ensure_ticket_precondition(if_match, ticket_id, version)
+            .await?;
+        if replace_ticket_tags(&mut tx, ticket_id, tag_ids, actor_id).await? {
+            version = touch_ticket(&mut tx, ticket_id).await?;
+        }
+        tx.commit().await?;
+
+        Ok((version, self.get_ticket_tags(ticket_id).await?))
+    }
+
+    // 为工单添加单个标签，标签属于互斥分组时替换工单上该分组的其他标签
+    pub async fn add_ticket_tag(
+        &self,
+        ticket_id: UuidType,
+        tag_id: UuidType,
+        actor_id: Option<UuidType>,
+        if_match: Option<&IfMatch>,
+    ) -> Result<(i32, Vec<Tag>), AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+        let tag = self.get_by_id(tag_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut version = lock_ticket(&mut tx, ticket_id).await?;
+        self.ensure_ticket_precondition(if_match, ticket_id, version)
+            .await?;
+
+        let replaced = query_as::<_, TagRef>(
+            r#"
+            DELETE FROM ticket_tags tt
+            USING tags t, tag_groups g
+            WHERE tt.ticket_id = $1 AND tt.tag_id <> $2 AND t.id = tt.tag_id
+              AND t.group_id = $3 AND g.id = t.group_id AND g.exclusive
+            RETURNING t.id, t.name
+            "#,
+        )
+        .bind(ticket_id)
+        .bind(tag_id)
+        .bind(tag.group_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut changed = !replaced.is_empty();
+
+        record_all(
+            &mut tx,
+            replaced.into_iter().map(|removed| {
+                NewTicketEvent::new(ticket_id, actor_id, TicketEventType::TagRemoved)
+                    .old_value(&removed)
+            }),
+        )
+        .await?;
+
+        let result = query(
+            "INSERT INTO ticket_tags (ticket_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(ticket_id)
+        .bind(tag_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            NewTicketEvent::new(ticket_id, actor_id, TicketEventType::TagAdded)
+                .new_value(&TagRef::from(&tag))
+                .record(&mut tx)
+                .await?;
+            changed = true;
+        }
+
+        if changed {
+            version = touch_ticket(&mut tx, ticket_id).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok((version, self.get_ticket_tags(ticket_id).await?))
+    }
+
+    // 从工单中移除标签
+    pub async fn remove_ticket_tag(
+        &self,
+        ticket_id: UuidType,
+        tag_id: UuidType,
+        actor_id: Option<UuidType>,
+        if_match: Option<&IfMatch>,
+    ) -> Result<(i32, Vec<Tag>), AppError> {
+        self.ensure_ticket_exists(ticket_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut version = lock_ticket(&mut tx, ticket_id).await?;
+        self.ensure_ticket_precondition(if_match, ticket_id, version)
+            .await?;
+
+        let removed = query_as::<_, TagRef>(
+            r#"
+            DELETE FROM ticket_tags tt
+            USING tags t
+            WHERE tt.ticket_id = $1 AND tt.tag_id = $2 AND t.id = tt.tag_id
+            RETURNING t.id, t.name
+            "#,
+        )
+        .bind(ticket_id)
+        .bind(tag_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(tag) = removed {
+            NewTicketEvent::new(ticket_id, actor_id, TicketEventType::TagRemoved)
+                .old_value(&tag)
+                .record(&mut tx)
+                .await?;
+            version = touch_ticket(&mut tx, ticket_id).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok((version, self.get_ticket_tags(ticket_id).await?))
+    }
+
+    // 工单标签接口与工单修改共用版本号，版本不匹配时返回 412 并附带当前工单
+    async fn ensure_ticket_precondition(
+        &self,
+        if_match: Option<&IfMatch>,
+        ticket_id: UuidType,
+        version: i32,
+    ) -> Result<(), AppError> {
+        if precondition::is_satisfied(if_match, version) {
+            return Ok(());
+        }
+
+        let ticket = TicketRepository::new(self.pool.clone())
+            .get_with_tags(ticket_id)
+            .await?;
+        Err(AppError::precondition_failed(version, &ticket))
+    }
+
     // 搜索标签
     pub async fn search(&self, term: &str) -> Result<Vec<Tag>, AppError> {
         let search_pattern = format!("%{}%", term.trim());
 
-        let rows = sqlx::query!(
+        let tags = query_as::<_, Tag>(&format!(
             r#"
-            SELECT id, name, color, created_at, updated_at
+            SELECT {TAG_COLUMNS}
             FROM tags
             WHERE name ILIKE $1
+               OR EXISTS (SELECT 1 FROM tag_aliases a WHERE a.tag_id = tags.id AND a.name ILIKE $1)
             ORDER BY name
             LIMIT 20
-            "#,
-            search_pattern
-        )
+            "#
+        ))
+        .bind(search_pattern)
         .fetch_all(&self.pool)
         .await?;
 
-        let tags: Vec<Tag> = rows.into_iter().map(|row| {
-            Tag {
-                id: row.id,
-                name: row.name,
-                color: row.color,
-                created_at: row.created_at,
-                updated_at: row.updated_at,
-            }
-        }).collect();
-
         Ok(tags)
     }
 
     // 获取热门标签（按使用次数排序）
     pub async fn get_popular(&self, limit: Option<i64>) -> Result<Vec<TagWithCount>, AppError> {
-        let limit = limit.unwrap_or(10);
+        let limit = limit.unwrap_or(10).clamp(1, 100);
 
-        let rows = sqlx::query!(
+        let tags = query_as::<_, TagWithCount>(
             r#"
             SELECT
-                t.id, t.name, t.color, t.created_at, t.updated_at,
-                COALESCE(tt.ticket_count, 0) as "ticket_count!"
+                t.id, t.name, t.color, t.parent_id, t.group_id, t.version, t.created_at, t.updated_at,
+                tt.ticket_count
             FROM tags t
-            LEFT JOIN (
-                SELECT tag_id, COUNT(*) as ticket_count
-                FROM ticket_tags
-                GROUP BY tag_id
+            INNER JOIN (
+                SELECT link.tag_id, COUNT(*) AS ticket_count
+                FROM ticket_tags link
+                INNER JOIN tickets tk ON tk.id = link.ticket_id AND tk.deleted_at IS NULL
+                GROUP BY link.tag_id
             ) tt ON t.id = tt.tag_id
-            WHERE tt.ticket_count > 0
             ORDER BY tt.ticket_count DESC, t.name
             LIMIT $1
             "#,
-            limit
         )
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
-        let tags: Vec<TagWithCount> = rows.into_iter().map(|row| {
-            let tag = Tag {
-                id: row.id,
-                name: row.name,
-                color: row.color,
-                created_at: row.created_at,
-                updated_at: row.updated_at,
-            };
-
-            TagWithCount {
-                tag,
-                ticket_count: row.ticket_count,
-            }
-        }).collect();
-
         Ok(tags)
     }
-}
\ No newline at end of file
+
+    // 标签统计信息：(标签总数, 关联总数, 未使用标签数)
+    pub async fn usage_counts(&self) -> Result<(i64, i64, i64), AppError> {
+        let counts: (i64, i64, i64) = query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM tags),
+                (SELECT COUNT(*) FROM ticket_tags tt
+                 INNER JOIN tickets tk ON tk.id = tt.ticket_id AND tk.deleted_at IS NULL),
+                (SELECT COUNT(*) FROM tags t
+                 WHERE NOT EXISTS (
+                     SELECT 1 FROM ticket_tags tt
+                     INNER JOIN tickets tk ON tk.id = tt.ticket_id AND tk.deleted_at IS NULL
+                     WHERE tt.tag_id = t.id
+                 ))
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    // 调色板中使用次数最少的颜色，次数相同时按调色板顺序
+    async fn next_palette_color(&self) -> Result<String, AppError> {
+        let used: Vec<(String, i64)> =
+            query_as("SELECT color, COUNT(*) FROM tags WHERE color = ANY($1) GROUP BY color")
+                .bind(&color::PALETTE[..])
+                .fetch_all(&self.pool)
+                .await?;
+
+        let usage = |candidate: &str| {
+            used.iter()
+                .find(|(color, _)| color == candidate)
+                .map_or(0, |(_, count)| *count)
+        };
+        let color = color::PALETTE
+            .into_iter()
+            .min_by_key(|candidate| usage(candidate))
+            .unwrap_or(color::PALETTE[0]);
+
+        Ok(color.to_string())
+    }
+
+    async fn ensure_group_exists(&self, group_id: UuidType) -> Result<(), AppError> {
+        let exists: bool = query_scalar("SELECT EXISTS(SELECT 1 FROM tag_groups WHERE id = $1)")
+            .bind(group_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        if !exists {
+            return Err(AppError::not_found("标签分组"));
+        }
+
+        Ok(())
+    }
+
+    async fn ensure_parent_exists(&self, parent_id: UuidType) -> Result<(), AppError> {
+        let exists: bool = query_scalar("SELECT EXISTS(SELECT 1 FROM tags WHERE id = $1)")
+            .bind(parent_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        if !exists {
+            return Err(AppError::not_found("父标签"));
+        }
+
+        Ok(())
+    }
+
+    async fn ensure_ticket_exists(&self, ticket_id: UuidType) -> Result<(), AppError> {
+        let exists: bool = query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM tickets WHERE id = $1 AND deleted_at IS NULL)",
+        )
+        .bind(ticket_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if !exists {
+            return Err(AppError::not_found("工单"));
+        }
+
+        Ok(())
+    }
+
+    pub async fn ensure_tags_exist(&self, tag_ids: &[UuidType]) -> Result<(), AppError> {
+        let found: i64 = query_scalar("SELECT COUNT(*) FROM tags WHERE id = ANY($1)")
+            .bind(tag_ids)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let mut unique = tag_ids.to_vec();
+        unique.sort();
+        unique.dedup();
+
+        if found != unique.len() as i64 {
+            return Err(AppError::not_found("标签"));
+        }
+
+        Ok(())
+    }
+}
+
+// 历史记录中的标签引用（保留名称，标签删除后仍可读）
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct TagRef {
+    id: UuidType,
+    name: String,
+}
+
+// 被移除的工单标签关联
+#[derive(sqlx::FromRow)]
+struct TicketTagLink {
+    ticket_id: UuidType,
+    #[sqlx(flatten)]
+    tag: TagRef,
+}
+
+impl From<&Tag> for TagRef {
+    fn from(tag: &Tag) -> Self {
+        Self {
+            id: tag.id,
+            name: tag.name.clone(),
+        }
+    }
+}
+
+// 将工单标签替换为给定集合，并为增删的标签记录历史（在调用方事务中执行），返回标签是否有变化
+pub(crate) async fn replace_ticket_tags(
+    conn: &mut PgConnection,
+    ticket_id: UuidType,
+    tag_ids: &[UuidType],
+    actor_id: Option<UuidType>,
+) -> Result<bool, AppError> {
+    ensure_single_per_exclusive_group(conn, tag_ids).await?;
+
+    let removed = query_as::<_, TagRef>(
+        r#"
+        DELETE FROM ticket_tags tt
+        USING tags t
+        WHERE tt.ticket_id = $1 AND NOT (tt.tag_id = ANY($2)) AND t.id = tt.tag_id
+        RETURNING t.id, t.name
+        "#,
+    )
+    .bind(ticket_id)
+    .bind(tag_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let added = query_as::<_, TagRef>(
+        r#"
+        WITH inserted AS (
+            INSERT INTO ticket_tags (ticket_id, tag_id)
+            SELECT $1, UNNEST($2::uuid[])
+            ON CONFLICT DO NOTHING
+            RETURNING tag_id
+        )
+        SELECT t.id, t.name FROM inserted i INNER JOIN tags t ON t.id = i.tag_id
+        "#,
+    )
+    .bind(ticket_id)
+    .bind(tag_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let changed = !removed.is_empty() || !added.is_empty();
+    let removed = removed.into_iter().map(|tag| {
+        NewTicketEvent::new(ticket_id, actor_id, TicketEventType::TagRemoved).old_value(&tag)
+    });
+    let added = added.into_iter().map(|tag| {
+        NewTicketEvent::new(ticket_id, actor_id, TicketEventType::TagAdded).new_value(&tag)
+    });
+    record_all(conn, removed.chain(added)).await?;
+
+    Ok(changed)
+}
+
+// 同一互斥分组中的标签只能选择一个
+async fn ensure_single_per_exclusive_group(
+    conn: &mut PgConnection,
+    tag_ids: &[UuidType],
+) -> Result<(), AppError> {
+    let conflict: Option<(String, Vec<String>)> = query_as(
+        r#"
+        SELECT g.name, ARRAY_AGG(t.name::text ORDER BY t.name)
+        FROM tags t
+        INNER JOIN tag_groups g ON g.id = t.group_id AND g.exclusive
+        WHERE t.id = ANY($1)
+        GROUP BY g.id, g.name
+        HAVING COUNT(*) > 1
+        ORDER BY g.name
+        LIMIT 1
+        "#,
+    )
+    .bind(tag_ids)
+    .fetch_optional(conn)
+    .await?;
+
+    match conflict {
+        Some((group, tags)) => Err(AppError::bad_request(format!(
+            "标签 {} 属于互斥分组「{}」，只能选择其中一个",
+            tags.join("、"),
+            group
+        ))),
+        None => Ok(()),
+    }
+}
+
+// 锁定工单行，使同一工单的标签修改串行执行，保证互斥分组检查有效；返回当前版本
+async fn lock_ticket(conn: &mut PgConnection, ticket_id: UuidType) -> Result<i32, AppError> {
+    query_scalar("SELECT version FROM tickets WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+        .bind(ticket_id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("工单"))
+}
+
+// 工单响应包含标签，标签变化时更新工单行以触发版本自增，使基于旧 ETag 的修改返回 412；返回新版本
+async fn touch_ticket(conn: &mut PgConnection, ticket_id: UuidType) -> Result<i32, AppError> {
+    let version =
+        query_scalar("UPDATE tickets SET updated_at = now() WHERE id = $1 RETURNING version")
+            .bind(ticket_id)
+            .fetch_one(conn)
+            .await?;
+
+    Ok(version)
+}
+
+// 批量版本：合并、删除标签会同时改变多个工单的标签集合
+async fn touch_tickets(conn: &mut PgConnection, ticket_ids: &[UuidType]) -> Result<(), AppError> {
+    if ticket_ids.is_empty() {
+        return Ok(());
+    }
+
+    query("UPDATE tickets SET updated_at = now() WHERE id = ANY($1)")
+        .bind(ticket_ids)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+// 串行化层级调整（移动、合并），避免并发操作各自通过检查后形成环
+async fn lock_hierarchy(conn: &mut PgConnection) -> Result<(), AppError> {
+    query("SELECT pg_advisory_xact_lock(hashtext('tags.parent_id'))")
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+// 在事务中锁定标签行，防止并发修改
+async fn lock(conn: &mut PgConnection, id: UuidType) -> Result<Tag, AppError> {
+    query_as::<_, Tag>(&format!(
+        "SELECT {TAG_COLUMNS} FROM tags WHERE id = $1 FOR UPDATE"
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await?
+    .ok_or_else(|| AppError::not_found("标签"))
+}
+
+// 版本不匹配时返回 412，并附带当前标签
+fn ensure_precondition(if_match: Option<&IfMatch>, current: &Tag) -> Result<(), AppError> {
+    if precondition::is_satisfied(if_match, current.version) {
+        Ok(())
+    } else {
+        Err(AppError::precondition_failed(current.version, current))
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{
+    database::DbPool,
+    error::AppError,
+    models::{PaginatedResponse, TicketEvent, TicketEventType, TicketEventWithActor},
+    repositories::{
+        notifications::{self, NotificationTrigger},
+        stream_events, UserRepository,
+    },
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{query, query_as, query_scalar, PgConnection};
+use uuid::Uuid as UuidType;
+
+const TICKET_EVENT_COLUMNS: &str =
+    "id, ticket_id, actor_id, event_type, field, old_value, new_value, created_at";
+
+pub struct TicketEventRepository {
+    pool: DbPool,
+}
+
+impl TicketEventRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 分页列出工单历史（按时间正序）；已删除工单的历史仍可查询
+    pub async fn list(
+        &self,
+        ticket_id: UuidType,
+        limit: i64,
+        offset: i64,
+    ) -> Result<PaginatedResponse<TicketEventWithActor>, AppError> {
+        let total: i64 = query_scalar("SELECT COUNT(*) FROM ticket_events WHERE ticket_id = $1")
+            .bind(ticket_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        if total == 0 {
+            let exists: bool = query_scalar("SELECT EXISTS(SELECT 1 FROM tickets WHERE id = $1)")
+                .bind(ticket_id)
+                .fetch_one(&self.pool)
+                .await?;
+            if !exists {
+                return Err(AppError::not_found("工单"));
+            }
+        }
+
+        let events = query_as::<_, TicketEvent>(&format!(
+            r#"
+            SELECT {TICKET_EVENT_COLUMNS}
+            FROM ticket_events
+            WHERE ticket_id = $1
+            ORDER BY created_at ASC, id
+            LIMIT $2 OFFSET $3
+            "#
+        ))
+        .bind(ticket_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut actor_ids: Vec<UuidType> = events.iter().filter_map(|e| e.actor_id).collect();
+        actor_ids.sort_unstable();
+        actor_ids.dedup();
+        let actors = UserRepository::new(self.pool.clone())
+            .get_summaries(&actor_ids)
+            .await?;
+
+        let events = events
+            .into_iter()
+            .map(|event| TicketEventWithActor {
+                actor: event.actor_id.and_then(|id| actors.get(&id).cloned()),
+                event,
+            })
+            .collect();
+
+        Ok(PaginatedResponse::new(events, total, limit, offset))
+    }
+}
+
+// 待写入的工单事件，须在变更所在的事务中调用 record
+pub struct NewTicketEvent {
+    ticket_id: UuidType,
+    actor_id: Option<UuidType>,
+    event_type: TicketEventType,
+    field: Option<&'static str>,
+    old_value: Option<Value>,
+    new_value: Option<Value>,
+}
+
+impl NewTicketEvent {
+    pub fn new(
+        ticket_id: UuidType,
+        actor_id: Option<UuidType>,
+        event_type: TicketEventType,
+    ) -> Self {
+        Self {
+            ticket_id,
+            actor_id,
+            event_type,
+            field: None,
+            old_value: None,
+            new_value: None,
+        }
+    }
+
+    // 字段变更事件，新旧值相同时返回 None
+    pub fn field_change<T: Serialize>(
+        ticket_id: UuidType,
+        actor_id: Option<UuidType>,
+        field: &'static str,
+        old_value: &T,
+        new_value: &T,
+    ) -> Option<Self> {
+        let old_value = to_json(old_value);
+        let new_value = to_json(new_value);
+        if old_value == new_value {
+            return None;
+        }
+
+        Some(Self {
+            field: Some(field),
+            old_value: Some(old_value),
+            new_value: Some(new_value),
+            ..Self::new(ticket_id, actor_id, TicketEventType::FieldChanged)
+        })
+    }
+
+    pub fn old_value<T: Serialize>(mut self, value: &T) -> Self {
+        self.old_value = Some(to_json(value));
+        self
+    }
+
+    pub fn new_value<T: Serialize>(mut self, value: &T) -> Self {
+        self.new_value = Some(to_json(value));
+        self
+    }
+
+    // 对应的对外事件名（实时事件流与 Webhook 共用）
+    fn event_name(&self) -> &'static str {
+        match self.event_type {
+            TicketEventType::Created => "ticket.created",
+            TicketEventType::FieldChanged if self.field == Some("status") => {
+                "ticket.status_changed"
+            }
+            TicketEventType::FieldChanged => "ticket.updated",
+            TicketEventType::TagAdded => "ticket.tag_added",
+            TicketEventType::TagRemoved => "ticket.tag_removed",
+            TicketEventType::CommentAdded => "ticket.comment_added",
+            TicketEventType::CommentUpdated => "ticket.comment_updated",
+            TicketEventType::CommentDeleted => "ticket.comment_deleted",
+            TicketEventType::Deleted => "ticket.deleted",
+            TicketEventType::Restored => "ticket.restored",
+            TicketEventType::Purged => "ticket.purged",
+            TicketEventType::AttachmentAdded => "ticket.attachment_added",
+            TicketEventType::AttachmentDeleted => "ticket.attachment_deleted",
+        }
+    }
+
+    // 需要发送通知的事件：指派、状态变更和新评论
+    fn notification_trigger(&self) -> Option<NotificationTrigger> {
+        let new_value = self.new_value.as_ref();
+        let uuid_of = |value: Option<&Value>| value?.as_str()?.parse::<UuidType>().ok();
+
+        match (self.event_type, self.field) {
+            (TicketEventType::Created, _) => Some(NotificationTrigger::Created {
+                assignee_id: uuid_of(new_value.map(|ticket| &ticket["assignee_id"])),
+            }),
+            (TicketEventType::FieldChanged, Some("assignee_id")) => {
+                uuid_of(new_value).map(|assignee_id| NotificationTrigger::Assigned { assignee_id })
+            }
+            (TicketEventType::FieldChanged, Some("status")) => {
+                Some(NotificationTrigger::StatusChanged {
+                    old_status: self.old_value.clone().unwrap_or_default(),
+                    new_status: new_value.cloned().unwrap_or_default(),
+                })
+            }
+            (TicketEventType::CommentAdded, _) => {
+                let comment = new_value?;
+                Some(NotificationTrigger::CommentAdded {
+                    comment_id: comment["id"].clone(),
+                    content: comment["content"].as_str().unwrap_or_default().to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // 写入事件，并在同一事务中发布到实时事件流和 Webhook、生成邮件通知
+    pub async fn record(self, conn: &mut PgConnection) -> Result<(), AppError> {
+        record_all(conn, [self]).await
+    }
+
+    // 写入事件行，返回对外发布的变更内容
+    async fn insert(self, conn: &mut PgConnection) -> Result<Value, AppError> {
+        let id = UuidType::new_v4();
+        let now = chrono::Utc::now();
+        let change = json!({
+            "event_id": id,
+            "event_type": self.event_type,
+            "actor_id": self.actor_id,
+            "field": self.field,
+            "old_value": self.old_value,
+            "new_value": self.new_value,
+        });
+
+        query(
+            r#"
+            INSERT INTO ticket_events (
+                id, ticket_id, actor_id, event_type, field, old_value, new_value, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(id)
+        .bind(self.ticket_id)
+        .bind(self.actor_id)
+        .bind(self.event_type)
+        .bind(self.field)
+        .bind(self.old_value)
+        .bind(self.new_value)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(change)
+    }
+}
+
+// 同一工单在一次修改中的字段变更，合并为一条对外事件
+struct FieldChanges {
+    ticket_id: UuidType,
+    event_name: &'static str,
+    changes: Vec<Value>,
+}
+
+// 批量写入事件：每个字段变更各写一行历史，
+// 但同一工单的字段变更只发布一条事件（data.changes 列出全部变更），含状态变更时为 ticket.status_changed
+pub async fn record_all(
+    conn: &mut PgConnection,
+    events: impl IntoIterator<Item = NewTicketEvent>,
+) -> Result<(), AppError> {
+    let mut field_changes: Vec<FieldChanges> = Vec::new();
+
+    for event in events {
+        let ticket_id = event.ticket_id;
+        let actor_id = event.actor_id;
+        let event_type = event.event_type;
+        let event_name = event.event_name();
+        let notification = event.notification_trigger();
+        let change = event.insert(&mut *conn).await?;
+
+        if event_type == TicketEventType::FieldChanged {
+            match field_changes
+                .iter_mut()
+                .find(|group| group.ticket_id == ticket_id)
+            {
+                Some(group) => {
+                    if event_name == "ticket.status_changed" {
+                        group.event_name = event_name;
+                    }
+                    group.changes.push(change);
+                }
+                None => field_changes.push(FieldChanges {
+                    ticket_id,
+                    event_name,
+                    changes: vec![change],
+                }),
+            }
+        } else {
+            stream_events::publish(
+                &mut *conn,
+                event_name,
+                Some(ticket_id),
+                json!({ "change": change }),
+            )
+            .await?;
+        }
+
+        if let Some(trigger) = notification {
+            notifications::enqueue(&mut *conn, ticket_id, actor_id, trigger).await?;
+        }
+    }
+
+    for group in field_changes {
+        stream_events::publish(
+            &mut *conn,
+            group.event_name,
+            Some(group.ticket_id),
+            json!({ "changes": group.changes }),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
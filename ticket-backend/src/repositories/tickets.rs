@@ -2,13 +2,64 @@ use crate::{
     database::DbPool,
     error::AppError,
     models::{
-        CreateTicketRequest, PaginatedResponse, Ticket, TicketQuery, TicketStatus,
-        TicketWithTags, UpdateTicketRequest,
+        CreateTicketRequest, CursorPage, PaginatedResponse, Priority, SearchHighlights,
+        StatusCategory, Tag, TagRef, Ticket, TicketEventType, TicketQuery, TicketSearchHit,
+        TicketStatus, TicketWithTags, UpdateTicketRequest, WorkflowWithStates,
     },
+    precondition::{self, IfMatch},
+    repositories::{
+        tags::replace_ticket_tags,
+        ticket_events::{record_all, NewTicketEvent},
+        TagRepository, UserRepository, WorkflowRepository,
+    },
+    utils::{
+        cursor,
+        query_language::{Condition, DateField, TicketFilter, UserRef},
+        search::{
+            fallback_highlight, like_pattern, render_highlight, SearchTerms, HIGHLIGHT_START,
+        },
+        sort::{TicketSortField, TicketSortKey},
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{
+    query, query_as, query_scalar, types::Json, FromRow, PgConnection, Postgres, QueryBuilder,
 };
-use sqlx::{query, query_as, query_scalar, Row};
+use std::collections::HashMap;
 use uuid::Uuid as UuidType;
 
+const TICKET_COLUMNS: &str = "id, title, description, workflow_id, status, priority, assignee_id, \
+                              reporter_id, version, created_at, updated_at, resolved_at, due_date, \
+                              deleted_at";
+
+// 状态在所属工作流中的位置，按状态排序时使用
+const STATUS_POSITION: &str = "COALESCE((SELECT ws.position FROM workflow_states ws \
+                               WHERE ws.workflow_id = t.workflow_id AND ws.key = t.status), 0)";
+
+// ts_headline 选项：标题整体高亮，描述截取匹配片段
+const TITLE_HEADLINE_OPTIONS: &str = "HighlightAll=true, StartSel=\u{2}, StopSel=\u{3}";
+const DESCRIPTION_HEADLINE_OPTIONS: &str =
+    "MaxFragments=2, MaxWords=30, MinWords=10, FragmentDelimiter=\" … \", StartSel=\u{2}, StopSel=\u{3}";
+
+// 搜索查询的行：工单字段与相关度、高亮结果
+#[derive(FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    ticket: Ticket,
+    rank: f32,
+    matched: bool,
+    title_highlight: String,
+    description_highlight: String,
+}
+
+// 回收站清理结果
+pub struct PurgedTickets {
+    pub count: u64,
+    pub attachment_keys: Vec<String>,
+}
+
 pub struct TicketRepository {
     pool: DbPool,
 }
@@ -18,400 +69,1286 @@ impl TicketRepository {
         Self { pool }
     }
 
-    // 创建工单
-    pub async fn create(&self, request: CreateTicketRequest) -> Result<Ticket, AppError> {
+    // 创建工单，报告人为发起请求的用户
+    pub async fn create(
+        &self,
+        request: CreateTicketRequest,
+        reporter_id: Option<UuidType>,
+    ) -> Result<TicketWithTags, AppError> {
         let id = UuidType::new_v4();
         let now = chrono::Utc::now();
         let priority = request.priority.unwrap_or_default();
+        let tag_ids = request.tag_ids.unwrap_or_default();
 
-        let ticket = sqlx::query!(
+        if !tag_ids.is_empty() {
+            TagRepository::new(self.pool.clone())
+                .ensure_tags_exist(&tag_ids)
+                .await?;
+        }
+        self.ensure_users_active(request.assignee_id, reporter_id)
+            .await?;
+
+        let workflows = WorkflowRepository::new(self.pool.clone());
+        let workflow = match request.workflow_id {
+            Some(workflow_id) => workflows.get(workflow_id).await.map_err(|e| match e {
+                AppError::NotFound(_) => AppError::bad_request("工作流不存在"),
+                e => e,
+            })?,
+            None => workflows.get_default().await?,
+        };
+        let status = initial_status(&workflow)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let ticket = query_as::<_, Ticket>(&format!(
             r#"
             INSERT INTO tickets (
-                id, title, description, status, priority,
-                assignee_id, reporter_id, created_at, updated_at
+                id, title, description, workflow_id, status, priority,
+                assignee_id, reporter_id, due_date, created_at, updated_at
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
-            RETURNING id, title, description, status, priority, assignee_id, reporter_id,
-            created_at, updated_at, resolved_at
-            "#,
-            id,
-            request.title,
-            request.description,
-            TicketStatus::Open as TicketStatus,
-            priority as crate::models::Priority,
-            request.assignee_id,
-            request.reporter_id,
-            now
-        )
-        .fetch_one(&self.pool)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10)
+            RETURNING {TICKET_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .bind(request.title)
+        .bind(request.description)
+        .bind(workflow.workflow.id)
+        .bind(status)
+        .bind(priority)
+        .bind(request.assignee_id)
+        .bind(reporter_id)
+        .bind(request.due_date)
+        .bind(now)
+        .fetch_one(&mut *tx)
         .await?;
 
-        // 转换为Ticket结构
-        let ticket = Ticket {
-            id: ticket.id,
-            title: ticket.title,
-            description: ticket.description,
-            status: ticket.status,
-            priority: ticket.priority,
-            assignee_id: ticket.assignee_id,
-            reporter_id: ticket.reporter_id,
-            created_at: ticket.created_at,
-            updated_at: ticket.updated_at,
-            resolved_at: ticket.resolved_at,
-        };
+        NewTicketEvent::new(ticket.id, reporter_id, TicketEventType::Created)
+            .new_value(&ticket)
+            .record(&mut tx)
+            .await?;
 
         // 如果有标签，关联标签
-        if let Some(tag_ids) = &request.tag_ids {
-            println!("DEBUG: Creating ticket {} with {} tags: {:?}", ticket.id, tag_ids.len(), tag_ids);
-            if !tag_ids.is_empty() {
-                self.associate_tags(ticket.id, tag_ids).await?;
-                println!("DEBUG: Tags associated successfully");
-            }
-        } else {
-            println!("DEBUG: No tags provided for ticket {}", ticket.id);
+        if !tag_ids.is_empty() {
+            replace_ticket_tags(&mut tx, ticket.id, &tag_ids, reporter_id).await?;
         }
 
-        Ok(ticket)
+        tx.commit().await?;
+
+        self.enrich_one(ticket).await
     }
 
     // 根据ID获取工单
     pub async fn get_by_id(&self, id: UuidType) -> Result<Ticket, AppError> {
-        let ticket = sqlx::query!(
-            r#"
-            SELECT id, title, description, status, priority, assignee_id, reporter_id,
-            created_at, updated_at, resolved_at
-            FROM tickets
-            WHERE id = $1
-            "#,
-            id
-        )
+        query_as::<_, Ticket>(&format!(
+            "SELECT {TICKET_COLUMNS} FROM tickets WHERE id = $1 AND deleted_at IS NULL"
+        ))
+        .bind(id)
         .fetch_optional(&self.pool)
         .await?
-        .ok_or_else(|| AppError::not_found("工单"))?;
-
-        let ticket = Ticket {
-            id: ticket.id,
-            title: ticket.title,
-            description: ticket.description,
-            status: ticket.status,
-            priority: ticket.priority,
-            assignee_id: ticket.assignee_id,
-            reporter_id: ticket.reporter_id,
-            created_at: ticket.created_at,
-            updated_at: ticket.updated_at,
-            resolved_at: ticket.resolved_at,
-        };
-
-        Ok(ticket)
+        .ok_or_else(|| AppError::not_found("工单"))
     }
 
     // 获取带标签的工单
     pub async fn get_with_tags(&self, id: UuidType) -> Result<TicketWithTags, AppError> {
         let ticket = self.get_by_id(id).await?;
+        self.enrich_one(ticket).await
+    }
 
-        let tags = sqlx::query_as!(
-            crate::models::Tag,
-            r#"
-            SELECT t.id, t.name, t.color, t.created_at, t.updated_at
-            FROM tags t
-            INNER JOIN ticket_tags tt ON t.id = tt.tag_id
-            WHERE tt.ticket_id = $1
-            "#,
-            id
-        )
-        .fetch_all(&self.pool)
-        .await?;
+    // 更新工单，逐字段记录变更历史
+    pub async fn update(
+        &self,
+        id: UuidType,
+        request: UpdateTicketRequest,
+        actor_id: Option<UuidType>,
+        if_match: Option<&IfMatch>,
+    ) -> Result<TicketWithTags, AppError> {
+        if let Some(ref tag_ids) = request.tag_ids {
+            TagRepository::new(self.pool.clone())
+                .ensure_tags_exist(tag_ids)
+                .await?;
+        }
+        self.ensure_users_active(request.assignee_id.flatten(), None)
+            .await?;
 
-        Ok(TicketWithTags { ticket, tags })
-    }
+        let mut tx = self.pool.begin().await?;
 
-    // 更新工单
-    pub async fn update(&self, id: UuidType, request: UpdateTicketRequest) -> Result<Ticket, AppError> {
-        let now = chrono::Utc::now();
+        let current = self.lock(&mut tx, id).await?;
+        self.ensure_precondition(if_match, &current).await?;
 
-        // 构建动态更新查询
-        let mut updates = Vec::new();
-        let mut params = Vec::new();
+        let mut events = Vec::new();
+        let mut builder = QueryBuilder::<Postgres>::new("UPDATE tickets SET updated_at = ");
+        builder.push_bind(chrono::Utc::now());
 
         if let Some(title) = request.title {
-            updates.push("title = $2");
-            params.push(title);
+            events.push(NewTicketEvent::field_change(
+                id,
+                actor_id,
+                "title",
+                &current.title,
+                &title,
+            ));
+            builder.push(", title = ").push_bind(title);
         }
 
         if let Some(description) = request.description {
-            updates.push("description = $3");
-            params.push(description);
+            let description = Some(description);
+            events.push(NewTicketEvent::field_change(
+                id,
+                actor_id,
+                "description",
+                &current.description,
+                &description,
+            ));
+            builder.push(", description = ").push_bind(description);
         }
 
         if let Some(status) = request.status {
-            updates.push("status = $4");
-            params.push(status);
+            let workflow = WorkflowRepository::new(self.pool.clone())
+                .get(current.workflow_id)
+                .await?;
+            ensure_transition(&workflow, &current.status, &status)?;
+            if let Some(resolved_at) = resolved_at_change(&workflow, &current.status, &status) {
+                builder.push(", resolved_at = ").push_bind(resolved_at);
+            }
+            events.push(NewTicketEvent::field_change(
+                id,
+                actor_id,
+                "status",
+                &current.status,
+                &status,
+            ));
+            builder.push(", status = ").push_bind(status);
         }
 
         if let Some(priority) = request.priority {
-            updates.push("priority = $5");
-            params.push(priority);
+            events.push(NewTicketEvent::field_change(
+                id,
+                actor_id,
+                "priority",
+                &current.priority,
+                &priority,
+            ));
+            builder.push(", priority = ").push_bind(priority);
         }
 
         if let Some(assignee_id) = request.assignee_id {
-            updates.push("assignee_id = $6");
-            params.push(assignee_id);
+            events.push(NewTicketEvent::field_change(
+                id,
+                actor_id,
+                "assignee_id",
+                &current.assignee_id,
+                &assignee_id,
+            ));
+            builder.push(", assignee_id = ").push_bind(assignee_id);
         }
 
-        if updates.is_empty() {
-            return self.get_by_id(id).await;
+        if let Some(due_date) = request.due_date {
+            events.push(NewTicketEvent::field_change(
+                id,
+                actor_id,
+                "due_date",
+                &current.due_date,
+                &due_date,
+            ));
+            builder.push(", due_date = ").push_bind(due_date);
         }
 
-        updates.push("updated_at = $7");
+        builder
+            .push(" WHERE id = ")
+            .push_bind(id)
+            .push(format!(" RETURNING {TICKET_COLUMNS}"));
 
-        let sql = format!(
-            "UPDATE tickets SET {} WHERE id = $1 RETURNING id, title, description, status, priority, assignee_id, reporter_id, created_at, updated_at, resolved_at",
-            updates.join(", ")
-        );
+        let ticket = builder
+            .build_query_as::<Ticket>()
+            .fetch_one(&mut *tx)
+            .await?;
 
-        let mut query = sqlx::query(&sql).bind(id);
-
-        // 动态绑定参数
-        for param in params {
-            if let Some(title) = request.title {
-                query = query.bind(title);
-            }
-            if let Some(description) = request.description {
-                query = query.bind(description);
-            }
-            if let Some(status) = request.status {
-                query = query.bind(status);
-            }
-            if let Some(priority) = request.priority {
-                query = query.bind(priority);
-            }
-            if let Some(assignee_id) = request.assignee_id {
-                query = query.bind(assignee_id);
-            }
-        }
-        query = query.bind(now);
-
-        let ticket = query.fetch_one(&self.pool).await?;
-
-        let ticket = Ticket {
-            id: ticket.get(0),
-            title: ticket.get(1),
-            description: ticket.get(2),
-            status: ticket.get(3),
-            priority: ticket.get(4),
-            assignee_id: ticket.get(5),
-            reporter_id: ticket.get(6),
-            created_at: ticket.get(7),
-            updated_at: ticket.get(8),
-            resolved_at: ticket.get(9),
-        };
+        record_all(&mut tx, events.into_iter().flatten()).await?;
 
         // 更新标签关联
         if let Some(tag_ids) = request.tag_ids {
-            self.update_tags(id, tag_ids).await?;
+            replace_ticket_tags(&mut tx, id, &tag_ids, actor_id).await?;
         }
 
-        Ok(ticket)
+        tx.commit().await?;
+
+        self.enrich_one(ticket).await
     }
 
-    // 删除工单
-    pub async fn delete(&self, id: UuidType) -> Result<(), AppError> {
-        // 先删除标签关联
-        query!("DELETE FROM ticket_tags WHERE ticket_id = $1", id)
-            .execute(&self.pool)
-            .await?;
+    // 删除工单：移入回收站，评论与标签关联保留以便恢复
+    pub async fn delete(
+        &self,
+        id: UuidType,
+        actor_id: Option<UuidType>,
+        if_match: Option<&IfMatch>,
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = self.lock(&mut tx, id).await?;
+        self.ensure_precondition(if_match, &current).await?;
 
-        // 再删除评论
-        query!("DELETE FROM comments WHERE ticket_id = $1", id)
-            .execute(&self.pool)
+        query("UPDATE tickets SET deleted_at = $1 WHERE id = $2")
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(&mut *tx)
             .await?;
 
-        // 最后删除工单
-        let result = query!("DELETE FROM tickets WHERE id = $1", id)
-            .execute(&self.pool)
+        NewTicketEvent::new(id, actor_id, TicketEventType::Deleted)
+            .old_value(&current)
+            .record(&mut tx)
             .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(AppError::not_found("工单"));
-        }
+        tx.commit().await?;
 
         Ok(())
     }
 
+    // 从回收站恢复工单
+    pub async fn restore(
+        &self,
+        id: UuidType,
+        actor_id: Option<UuidType>,
+    ) -> Result<TicketWithTags, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let ticket = query_as::<_, Ticket>(&format!(
+            r#"
+            UPDATE tickets SET deleted_at = NULL
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING {TICKET_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(ticket) = ticket else {
+            // 区分“工单不存在”与“工单未被删除”
+            return match self.get_by_id(id).await {
+                Ok(_) => Err(AppError::Conflict("工单不在回收站中".to_string())),
+                Err(err) => Err(err),
+            };
+        };
+
+        NewTicketEvent::new(id, actor_id, TicketEventType::Restored)
+            .record(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+
+        self.enrich_one(ticket).await
+    }
+
+    // 分页列出回收站中的工单（最近删除的在前）
+    pub async fn list_deleted(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<PaginatedResponse<TicketWithTags>, AppError> {
+        let total: i64 = query_scalar("SELECT COUNT(*) FROM tickets WHERE deleted_at IS NOT NULL")
+            .fetch_one(&self.pool)
+            .await?;
+
+        let tickets = query_as::<_, Ticket>(&format!(
+            r#"
+            SELECT {TICKET_COLUMNS}
+            FROM tickets
+            WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC, id
+            LIMIT $1 OFFSET $2
+            "#
+        ))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let data = self.enrich(tickets).await?;
+        Ok(PaginatedResponse::new(data, total, limit, offset))
+    }
+
+    // 永久删除在回收站中超过保留期的工单（评论、附件与标签关联由外键级联删除，历史记录保留）
+    pub async fn purge_deleted(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> Result<PurgedTickets, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        // 先取出附件的存储键，由调用方删除存储对象
+        let attachment_keys: Vec<String> = query_scalar(
+            r#"
+            DELETE FROM attachments a
+            USING tickets t
+            WHERE a.ticket_id = t.id AND t.deleted_at < $1
+            RETURNING a.storage_key
+            "#,
+        )
+        .bind(deleted_before)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let ids: Vec<UuidType> =
+            query_scalar("DELETE FROM tickets WHERE deleted_at < $1 RETURNING id")
+                .bind(deleted_before)
+                .fetch_all(&mut *tx)
+                .await?;
+
+        let events: Vec<NewTicketEvent> = ids
+            .iter()
+            .map(|id| NewTicketEvent::new(*id, None, TicketEventType::Purged))
+            .collect();
+        record_all(&mut tx, events).await?;
+
+        tx.commit().await?;
+
+        Ok(PurgedTickets {
+            count: ids.len() as u64,
+            attachment_keys,
+        })
+    }
+
     // 列出工单（支持分页和过滤）
-    pub async fn list(&self, query_params: TicketQuery) -> Result<PaginatedResponse<Ticket>, AppError> {
-        let limit = query_params.limit.unwrap_or(20).min(100);
-        let offset = query_params.offset.unwrap_or(0);
+    pub async fn list(
+        &self,
+        query_params: TicketQuery,
+    ) -> Result<PaginatedResponse<TicketWithTags>, AppError> {
+        let limit = query_params.limit.unwrap_or(20).clamp(1, 100);
+        let offset = query_params.offset.unwrap_or(0).max(0);
 
-        // 构建WHERE条件
-        let mut where_conditions = Vec::new();
-        let mut count_where_conditions = Vec::new();
+        // 查询总数
+        let total = self.count_matching(&query_params).await?;
 
-        if let Some(status) = query_params.status {
-            where_conditions.push(format!("status = '{}'", status));
-            count_where_conditions.push(format!("status = '{}'", status));
-        }
+        // 查询数据
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {} FROM tickets t",
+            prefixed_columns("t")
+        ));
+        push_filters(&mut builder, &query_params);
+        push_order_by(&mut builder, &sort_keys(&query_params));
+        builder.push(" LIMIT ").push_bind(limit);
+        builder.push(" OFFSET ").push_bind(offset);
 
-        if let Some(priority) = query_params.priority {
-            where_conditions.push(format!("priority = '{}'", priority));
-            count_where_conditions.push(format!("priority = '{}'", priority));
-        }
+        let tickets = builder
+            .build_query_as::<Ticket>()
+            .fetch_all(&self.pool)
+            .await?;
 
-        if let Some(assignee_id) = query_params.assignee_id {
-            where_conditions.push(format!("assignee_id = '{}'", assignee_id));
-            count_where_conditions.push(format!("assignee_id = '{}'", assignee_id));
-        }
+        let data = self.enrich(tickets).await?;
+        Ok(PaginatedResponse::new(data, total, limit, offset))
+    }
 
-        if let Some(reporter_id) = query_params.reporter_id {
-            where_conditions.push(format!("reporter_id = '{}'", reporter_id));
-            count_where_conditions.push(format!("reporter_id = '{}'", reporter_id));
-        }
+    // 游标分页：按排序键与 id 做 keyset 查询，翻页期间新建的工单不会导致重复或遗漏
+    // cursor 为空字符串时返回第一页；with_total 为 false 时跳过计数查询
+    pub async fn list_after(
+        &self,
+        query_params: TicketQuery,
+        cursor: &str,
+        with_total: bool,
+    ) -> Result<CursorPage<TicketWithTags>, AppError> {
+        let limit = query_params.limit.unwrap_or(20).clamp(1, 100);
+        let keys = sort_keys(&query_params);
 
-        if let Some(search) = query_params.search {
-            if !search.trim().is_empty() {
-                let search_condition = format!(
-                    "(title ILIKE '%{}%' OR description ILIKE '%{}%')",
-                    search.trim(),
-                    search.trim()
-                );
-                where_conditions.push(search_condition.clone());
-                count_where_conditions.push(search_condition);
+        let after = match cursor.trim() {
+            "" => None,
+            cursor => Some(cursor::decode::<TicketCursor>(cursor)?),
+        };
+        if let Some(ref after) = after {
+            if after.sort != keys || after.values.len() != keys.len() {
+                return Err(AppError::bad_request(
+                    "分页游标与当前排序条件不一致，请从第一页重新开始",
+                ));
             }
         }
 
-        if let Some(tag_id) = query_params.tag_id {
-            where_conditions.push(format!(
-                "id IN (SELECT ticket_id FROM ticket_tags WHERE tag_id = '{}')",
-                tag_id
-            ));
-            count_where_conditions.push(format!(
-                "id IN (SELECT ticket_id FROM ticket_tags WHERE tag_id = '{}')",
-                tag_id
-            ));
+        let total = match with_total {
+            true => Some(self.count_matching(&query_params).await?),
+            false => None,
+        };
+
+        let sort_values: Vec<String> = keys.iter().map(sort_value_expression).collect();
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {}, jsonb_build_array({}) AS sort_values FROM tickets t",
+            prefixed_columns("t"),
+            sort_values.join(", ")
+        ));
+        push_filters(&mut builder, &query_params);
+        if let Some(ref after) = after {
+            push_keyset(&mut builder, after)?;
         }
+        push_order_by(&mut builder, &keys);
+        // 多取一条用于判断是否还有下一页
+        builder.push(" LIMIT ").push_bind(limit + 1);
 
-        let where_clause = if where_conditions.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", where_conditions.join(" AND "))
-        };
+        let mut rows = builder
+            .build_query_as::<CursorRow>()
+            .fetch_all(&self.pool)
+            .await?;
 
-        let count_where_clause = if count_where_conditions.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", count_where_conditions.join(" AND "))
-        };
+        let has_more = rows.len() as i64 > limit;
+        rows.truncate(limit as usize);
+        let next_cursor = rows.last().filter(|_| has_more).map(|last| {
+            cursor::encode(&TicketCursor {
+                sort: keys,
+                values: last.sort_values.0.clone(),
+                id: last.ticket.id,
+            })
+        });
+        let tickets = rows.into_iter().map(|row| row.ticket).collect();
 
-        // 构建ORDER BY
-        let sort_by = query_params.sort_by.unwrap_or_else(|| "created_at".to_string());
-        let sort_order = query_params.sort_order.unwrap_or_else(|| "desc".to_string());
-        let order_clause = format!("ORDER BY {} {}", sort_by, sort_order);
+        let data = self.enrich(tickets).await?;
+        Ok(CursorPage {
+            data,
+            limit,
+            next_cursor,
+            total,
+        })
+    }
 
-        // 查询总数
-        let count_sql = format!(
-            "SELECT COUNT(*) FROM tickets {}",
-            count_where_clause
+    async fn count_matching(&self, query_params: &TicketQuery) -> Result<i64, AppError> {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM tickets t");
+        push_filters(&mut builder, query_params);
+        let total: i64 = builder.build_query_scalar().fetch_one(&self.pool).await?;
+
+        Ok(total)
+    }
+
+    // 全文搜索工单，按相关度排序并返回高亮片段
+    pub async fn search(&self, input: &str, limit: i64) -> Result<Vec<TicketSearchHit>, AppError> {
+        let terms = SearchTerms::parse(input);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {}, COALESCE(ts_rank(s.document, q.query), 0)::real AS rank, \
+             COALESCE(s.document @@ q.query, FALSE) AS matched, \
+             ts_headline('ticket_search', t.title, q.query, ",
+            prefixed_columns("t")
+        ));
+        builder.push_bind(TITLE_HEADLINE_OPTIONS);
+        builder.push(
+            ") AS title_highlight, \
+             ts_headline('ticket_search', COALESCE(t.description, ''), q.query, ",
+        );
+        builder.push_bind(DESCRIPTION_HEADLINE_OPTIONS);
+        builder.push(
+            ") AS description_highlight \
+             FROM tickets t \
+             CROSS JOIN (SELECT to_tsquery('ticket_search', ",
         );
+        builder.push_bind(terms.to_tsquery());
+        builder.push(
+            ") AS query) q \
+             LEFT JOIN ticket_search s ON s.ticket_id = t.id \
+             WHERE t.deleted_at IS NULL AND (s.document @@ q.query OR ",
+        );
+        push_fallback_match(&mut builder, &terms);
+        builder.push(") ORDER BY rank DESC, similarity(t.title, ");
+        builder.push_bind(terms.plain_text());
+        builder.push(") DESC, t.created_at DESC, t.id LIMIT ");
+        builder.push_bind(limit);
 
-        let total: i64 = sqlx::query_scalar(&count_sql)
-            .fetch_one(&self.pool)
+        let rows = builder
+            .build_query_as::<SearchRow>()
+            .fetch_all(&self.pool)
             .await?;
 
-        // 查询数据
-        let sql = format!(
+        let highlight_texts = terms.highlight_texts();
+        let mut highlights = Vec::with_capacity(rows.len());
+        let mut ranks = Vec::with_capacity(rows.len());
+        let mut tickets = Vec::with_capacity(rows.len());
+        for row in rows {
+            highlights.push(SearchHighlights {
+                title: highlight(
+                    row.matched,
+                    &row.title_highlight,
+                    &row.ticket.title,
+                    &highlight_texts,
+                    false,
+                ),
+                description: row.ticket.description.as_deref().map(|description| {
+                    highlight(
+                        row.matched,
+                        &row.description_highlight,
+                        description,
+                        &highlight_texts,
+                        true,
+                    )
+                }),
+            });
+            ranks.push(row.rank);
+            tickets.push(row.ticket);
+        }
+
+        let tickets = self.enrich(tickets).await?;
+        Ok(tickets
+            .into_iter()
+            .zip(ranks)
+            .zip(highlights)
+            .map(|((ticket, rank), highlights)| TicketSearchHit {
+                ticket,
+                rank,
+                highlights,
+            })
+            .collect())
+    }
+
+    // 重新打开已解决或已关闭的工单
+    pub async fn reopen(
+        &self,
+        id: UuidType,
+        actor_id: Option<UuidType>,
+    ) -> Result<TicketWithTags, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = self.lock(&mut tx, id).await?;
+        let workflow = WorkflowRepository::new(self.pool.clone())
+            .get(current.workflow_id)
+            .await?;
+        if !workflow.is_done(&current.status) {
+            return Err(AppError::Conflict(format!(
+                "只有已完成的工单可以重新打开，当前状态: {}",
+                current.status
+            )));
+        }
+
+        let ticket = query_as::<_, Ticket>(&format!(
+            r#"
+            UPDATE tickets SET status = $1, resolved_at = NULL, updated_at = $2
+            WHERE id = $3
+            RETURNING {TICKET_COLUMNS}
+            "#
+        ))
+        .bind(initial_status(&workflow)?)
+        .bind(chrono::Utc::now())
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        record_all(
+            &mut tx,
+            NewTicketEvent::field_change(id, actor_id, "status", &current.status, &ticket.status),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        self.enrich_one(ticket).await
+    }
+
+    // 批量更新工单状态；不存在或不允许流转的工单会被跳过
+    pub async fn bulk_update_status(
+        &self,
+        ids: &[UuidType],
+        status: TicketStatus,
+        actor_id: Option<UuidType>,
+    ) -> Result<BulkStatusOutcome, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = query_as::<_, (UuidType, UuidType, TicketStatus)>(
             r#"
-            SELECT id, title, description, status, priority, assignee_id, reporter_id,
-            created_at, updated_at, resolved_at
-            FROM tickets {}
-            {} LIMIT {} OFFSET {}
+            SELECT id, workflow_id, status FROM tickets
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            FOR UPDATE
             "#,
-            where_clause, order_clause, limit, offset
-        );
+        )
+        .bind(ids)
+        .fetch_all(&mut *tx)
+        .await?;
 
-        let rows = sqlx::query(&sql)
-            .fetch_all(&self.pool)
+        let mut workflow_ids: Vec<UuidType> = current.iter().map(|(_, w, _)| *w).collect();
+        workflow_ids.sort_unstable();
+        workflow_ids.dedup();
+        let workflows = WorkflowRepository::new(self.pool.clone())
+            .get_many(&workflow_ids)
             .await?;
 
-        let tickets: Vec<Ticket> = rows.into_iter().map(|row| {
-            Ticket {
-                id: row.get(0),
-                title: row.get(1),
-                description: row.get(2),
-                status: row.get(3),
-                priority: row.get(4),
-                assignee_id: row.get(5),
-                reporter_id: row.get(6),
-                created_at: row.get(7),
-                updated_at: row.get(8),
-                resolved_at: row.get(9),
-            }
-        }).collect();
+        let now = chrono::Utc::now();
+        let mut outcome = BulkStatusOutcome::default();
+        for (id, workflow_id, previous) in current {
+            let Some(workflow) = workflows
+                .get(&workflow_id)
+                .filter(|workflow| ensure_transition(workflow, &previous, &status).is_ok())
+            else {
+                outcome.rejected.push((id, previous));
+                continue;
+            };
 
-        Ok(PaginatedResponse::new(tickets, total, limit, offset))
-    }
+            let mut builder = QueryBuilder::<Postgres>::new("UPDATE tickets SET status = ");
+            builder.push_bind(&status);
+            builder.push(", updated_at = ").push_bind(now);
+            if let Some(resolved_at) = resolved_at_change(workflow, &previous, &status) {
+                builder.push(", resolved_at = ").push_bind(resolved_at);
+            }
+            builder.push(" WHERE id = ").push_bind(id);
+            builder.build().execute(&mut *tx).await?;
 
-    // 关联标签
-    async fn associate_tags(&self, ticket_id: UuidType, tag_ids: &[UuidType]) -> Result<(), AppError> {
-        for tag_id in tag_ids {
-            query!(
-                "INSERT INTO ticket_tags (ticket_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
-                ticket_id,
-                tag_id
+            record_all(
+                &mut tx,
+                NewTicketEvent::field_change(id, actor_id, "status", &previous, &status),
             )
-            .execute(&self.pool)
             .await?;
+            outcome.updated.push(id);
         }
-        Ok(())
+
+        tx.commit().await?;
+
+        Ok(outcome)
     }
 
-    // 更新标签关联
-    async fn update_tags(&self, ticket_id: UuidType, tag_ids: Vec<UuidType>) -> Result<(), AppError> {
-        // 先删除现有关联
-        query!("DELETE FROM ticket_tags WHERE ticket_id = $1", ticket_id)
-            .execute(&self.pool)
-            .await?;
+    // 按状态统计工单数量
+    pub async fn count_by_status(
+        &self,
+        assignee_id: Option<UuidType>,
+    ) -> Result<Vec<(TicketStatus, i64)>, AppError> {
+        let counts = query_as::<_, (TicketStatus, i64)>(
+            r#"
+            SELECT status, COUNT(*)
+            FROM tickets
+            WHERE deleted_at IS NULL AND ($1::uuid IS NULL OR assignee_id = $1)
+            GROUP BY status
+            "#,
+        )
+        .bind(assignee_id)
+        .fetch_all(&self.pool)
+        .await?;
 
-        // 添加新关联
-        if !tag_ids.is_empty() {
-            self.associate_tags(ticket_id, &tag_ids).await?;
+        Ok(counts)
+    }
+
+    // 按状态分类统计工单数量
+    pub async fn count_by_category(
+        &self,
+        assignee_id: Option<UuidType>,
+    ) -> Result<Vec<(StatusCategory, i64)>, AppError> {
+        let counts = query_as::<_, (StatusCategory, i64)>(
+            r#"
+            SELECT ws.category, COUNT(*)
+            FROM tickets t
+            JOIN workflow_states ws ON ws.workflow_id = t.workflow_id AND ws.key = t.status
+            WHERE t.deleted_at IS NULL AND ($1::uuid IS NULL OR t.assignee_id = $1)
+            GROUP BY ws.category
+            "#,
+        )
+        .bind(assignee_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    // 按优先级统计工单数量
+    pub async fn count_by_priority(
+        &self,
+        assignee_id: Option<UuidType>,
+    ) -> Result<Vec<(Priority, i64)>, AppError> {
+        let counts = query_as::<_, (Priority, i64)>(
+            r#"
+            SELECT priority, COUNT(*)
+            FROM tickets
+            WHERE deleted_at IS NULL AND ($1::uuid IS NULL OR assignee_id = $1)
+            GROUP BY priority
+            "#,
+        )
+        .bind(assignee_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    // 检查指派人与报告人为启用状态的用户
+    async fn ensure_users_active(
+        &self,
+        assignee_id: Option<UuidType>,
+        reporter_id: Option<UuidType>,
+    ) -> Result<(), AppError> {
+        let users = UserRepository::new(self.pool.clone());
+
+        if let Some(assignee_id) = assignee_id {
+            users.ensure_active(assignee_id, "指派人").await?;
+        }
+
+        if let Some(reporter_id) = reporter_id {
+            users.ensure_active(reporter_id, "报告人").await?;
         }
 
         Ok(())
     }
 
-    // 搜索工单
-    pub async fn search(&self, term: &str) -> Result<Vec<Ticket>, AppError> {
-        let search_pattern = format!("%{}%", term.trim());
+    // 在事务中锁定工单行，防止并发修改
+    async fn lock(&self, conn: &mut PgConnection, id: UuidType) -> Result<Ticket, AppError> {
+        query_as::<_, Ticket>(&format!(
+            "SELECT {TICKET_COLUMNS} FROM tickets WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"
+        ))
+        .bind(id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::not_found("工单"))
+    }
+
+    // 版本不匹配时返回 412，并附带当前工单
+    async fn ensure_precondition(
+        &self,
+        if_match: Option<&IfMatch>,
+        current: &Ticket,
+    ) -> Result<(), AppError> {
+        if precondition::is_satisfied(if_match, current.version) {
+            return Ok(());
+        }
+
+        let ticket = self.enrich_one(current.clone()).await?;
+        Err(AppError::precondition_failed(current.version, &ticket))
+    }
+
+    async fn enrich_one(&self, ticket: Ticket) -> Result<TicketWithTags, AppError> {
+        let mut enriched = self.enrich(vec![ticket]).await?;
+        enriched
+            .pop()
+            .ok_or_else(|| AppError::internal("工单关联数据加载失败"))
+    }
+
+    // 为一组工单附加标签与用户摘要，保持原有顺序
+    async fn enrich(&self, tickets: Vec<Ticket>) -> Result<Vec<TicketWithTags>, AppError> {
+        let ids: Vec<UuidType> = tickets.iter().map(|t| t.id).collect();
+        let mut tags_by_ticket = self.load_tags(&ids).await?;
+
+        let user_ids: Vec<UuidType> = tickets
+            .iter()
+            .flat_map(|t| [t.assignee_id, t.reporter_id])
+            .flatten()
+            .collect();
+        let users = UserRepository::new(self.pool.clone())
+            .get_summaries(&user_ids)
+            .await?;
 
-        let rows = sqlx::query(
+        Ok(tickets
+            .into_iter()
+            .map(|ticket| {
+                let tags = tags_by_ticket.remove(&ticket.id).unwrap_or_default();
+                let assignee = ticket.assignee_id.and_then(|id| users.get(&id).cloned());
+                let reporter = ticket.reporter_id.and_then(|id| users.get(&id).cloned());
+                TicketWithTags {
+                    ticket,
+                    tags,
+                    assignee,
+                    reporter,
+                }
+            })
+            .collect())
+    }
+
+    // 批量加载工单标签
+    async fn load_tags(
+        &self,
+        ticket_ids: &[UuidType],
+    ) -> Result<HashMap<UuidType, Vec<Tag>>, AppError> {
+        if ticket_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows = query_as::<_, TicketTagRow>(
             r#"
-            SELECT id, title, description, status, priority, assignee_id, reporter_id,
-            created_at, updated_at, resolved_at
-            FROM tickets
-            WHERE title ILIKE $1 OR description ILIKE $1
-            ORDER BY created_at DESC
-            LIMIT 50
-            "#
+            SELECT tt.ticket_id, t.id, t.name, t.color, t.parent_id, t.group_id, t.version, t.created_at, t.updated_at
+            FROM ticket_tags tt
+            INNER JOIN tags t ON t.id = tt.tag_id
+            WHERE tt.ticket_id = ANY($1)
+            ORDER BY t.name
+            "#,
         )
-        .bind(&search_pattern)
+        .bind(ticket_ids)
         .fetch_all(&self.pool)
         .await?;
 
-        let tickets: Vec<Ticket> = rows.into_iter().map(|row| {
-            Ticket {
-                id: row.get(0),
-                title: row.get(1),
-                description: row.get(2),
-                status: row.get(3),
-                priority: row.get(4),
-                assignee_id: row.get(5),
-                reporter_id: row.get(6),
-                created_at: row.get(7),
-                updated_at: row.get(8),
-                resolved_at: row.get(9),
+        let mut tags_by_ticket: HashMap<UuidType, Vec<Tag>> = HashMap::new();
+        for row in rows {
+            tags_by_ticket
+                .entry(row.ticket_id)
+                .or_default()
+                .push(row.tag);
+        }
+
+        Ok(tags_by_ticket)
+    }
+}
+
+// 批量更新状态的结果
+#[derive(Debug, Default)]
+pub struct BulkStatusOutcome {
+    pub updated: Vec<UuidType>,
+    // 因状态流转规则被拒绝的工单及其当前状态
+    pub rejected: Vec<(UuidType, TicketStatus)>,
+}
+
+// 校验目标状态属于工单的工作流且流转合法
+fn ensure_transition(
+    workflow: &WorkflowWithStates,
+    current: &str,
+    next: &str,
+) -> Result<(), AppError> {
+    if workflow.state(next).is_none() {
+        return Err(AppError::bad_request(format!(
+            "状态 {} 不属于工作流 {}",
+            next, workflow.workflow.name
+        )));
+    }
+
+    if workflow.can_transition(current, next) {
+        return Ok(());
+    }
+
+    let allowed = workflow.next_states(current);
+    if allowed.is_empty() {
+        return Err(AppError::Conflict(format!(
+            "工单处于 {} 状态，请先重新打开后再修改状态",
+            current
+        )));
+    }
+
+    Err(AppError::Conflict(format!(
+        "不允许将工单状态从 {} 变更为 {}，可选: {}",
+        current,
+        next,
+        allowed.join(", ")
+    )))
+}
+
+// 进入 done 分类时记录解决时间，退回未完成状态时清空；None 表示无需修改
+fn resolved_at_change(
+    workflow: &WorkflowWithStates,
+    current: &str,
+    next: &str,
+) -> Option<Option<DateTime<Utc>>> {
+    match (workflow.is_done(current), workflow.is_done(next)) {
+        (false, true) => Some(Some(Utc::now())),
+        (true, false) => Some(None),
+        _ => None,
+    }
+}
+
+fn initial_status(workflow: &WorkflowWithStates) -> Result<TicketStatus, AppError> {
+    workflow
+        .initial_state()
+        .map(|state| state.key.clone())
+        .ok_or_else(|| {
+            AppError::internal(format!("工作流 {} 缺少初始状态", workflow.workflow.name))
+        })
+}
+
+#[derive(sqlx::FromRow)]
+struct TicketTagRow {
+    ticket_id: UuidType,
+    #[sqlx(flatten)]
+    tag: Tag,
+}
+
+// 追加列表查询的 WHERE 条件（所有值均通过参数绑定）
+fn push_filters(builder: &mut QueryBuilder<'_, Postgres>, query_params: &TicketQuery) {
+    builder.push(" WHERE t.deleted_at IS NULL");
+
+    if let Some(ref status) = query_params.status {
+        builder.push(" AND t.status = ").push_bind(status.clone());
+    }
+
+    if let Some(category) = query_params.status_category {
+        builder
+            .push(
+                " AND EXISTS (SELECT 1 FROM workflow_states ws \
+                 WHERE ws.workflow_id = t.workflow_id AND ws.key = t.status AND ws.category = ",
+            )
+            .push_bind(category)
+            .push(")");
+    }
+
+    if let Some(workflow_id) = query_params.workflow_id {
+        builder.push(" AND t.workflow_id = ").push_bind(workflow_id);
+    }
+
+    if let Some(ref priority) = query_params.priority {
+        builder
+            .push(" AND t.priority = ")
+            .push_bind(priority.clone());
+    }
+
+    if let Some(assignee_id) = query_params.assignee_id {
+        builder.push(" AND t.assignee_id = ").push_bind(assignee_id);
+    }
+
+    if let Some(reporter_id) = query_params.reporter_id {
+        builder.push(" AND t.reporter_id = ").push_bind(reporter_id);
+    }
+
+    if let Some(ref search) = query_params.search {
+        let terms = SearchTerms::parse(search);
+        if !terms.is_empty() {
+            builder.push(" AND ");
+            push_search_match(builder, &terms);
+        }
+    }
+
+    if let Some(ref filter) = query_params.filter {
+        push_ticket_filter(builder, filter);
+    }
+
+    // 标签条件均为相关子查询，不会产生重复行，total 与列表一致
+    let descendants = query_params.include_descendant_tags;
+    for tag in &query_params.tags_all {
+        builder.push(" AND ");
+        push_tag_exists(builder, std::slice::from_ref(tag), descendants);
+    }
+
+    if !query_params.tags_any.is_empty() {
+        builder.push(" AND ");
+        push_tag_exists(builder, &query_params.tags_any, descendants);
+    }
+
+    if !query_params.tags_none.is_empty() {
+        builder.push(" AND NOT ");
+        push_tag_exists(builder, &query_params.tags_none, descendants);
+    }
+}
+
+// 工单带有其中任一标签（descendants 为 true 时也匹配这些标签的所有子标签）
+fn push_tag_exists(builder: &mut QueryBuilder<'_, Postgres>, tags: &[TagRef], descendants: bool) {
+    let mut ids = Vec::new();
+    let mut names = Vec::new();
+    for tag in tags {
+        match tag {
+            TagRef::Id(id) => ids.push(*id),
+            TagRef::Name(name) => names.push(name.to_lowercase()),
+        }
+    }
+
+    builder
+        .push("EXISTS (SELECT 1 FROM ticket_tags tt WHERE tt.ticket_id = t.id AND tt.tag_id IN (");
+    if descendants {
+        builder.push("WITH RECURSIVE matched AS (");
+    }
+    builder
+        .push("SELECT id FROM tags WHERE id = ANY(")
+        .push_bind(ids)
+        .push(") OR LOWER(name) = ANY(")
+        .push_bind(names.clone())
+        .push(") OR id IN (SELECT tag_id FROM tag_aliases WHERE LOWER(name) = ANY(")
+        .push_bind(names)
+        .push("))");
+    if descendants {
+        builder.push(
+            " UNION SELECT child.id FROM tags child \
+             INNER JOIN matched ON child.parent_id = matched.id) \
+             SELECT id FROM matched",
+        );
+    }
+    builder.push("))");
+}
+
+// 查询语言条件：取反使用 IS NOT TRUE，使 `-assignee:me` 等条件包含字段为空的工单
+fn push_ticket_filter(builder: &mut QueryBuilder<'_, Postgres>, filter: &TicketFilter) {
+    for clause in &filter.clauses {
+        builder.push(" AND (");
+        push_condition(builder, &clause.condition);
+        builder.push(if clause.negated { ") IS NOT TRUE" } else { ")" });
+    }
+}
+
+fn push_condition(builder: &mut QueryBuilder<'_, Postgres>, condition: &Condition) {
+    match condition {
+        Condition::Status(statuses) => {
+            builder
+                .push("t.status = ANY(")
+                .push_bind(statuses.clone())
+                .push(")");
+        }
+        Condition::Category(categories) => {
+            let categories: Vec<&str> = categories.iter().map(StatusCategory::as_str).collect();
+            builder
+                .push(
+                    "EXISTS (SELECT 1 FROM workflow_states ws \
+                     WHERE ws.workflow_id = t.workflow_id AND ws.key = t.status \
+                     AND ws.category = ANY(",
+                )
+                .push_bind(categories)
+                .push("))");
+        }
+        Condition::Priority(priorities) => {
+            let priorities: Vec<&str> = priorities.iter().map(Priority::as_str).collect();
+            builder
+                .push("t.priority = ANY(")
+                .push_bind(priorities)
+                .push(")");
+        }
+        Condition::Tag(names) => {
+            let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+            builder
+                .push(
+                    "EXISTS (SELECT 1 FROM ticket_tags tt INNER JOIN tags tg ON tg.id = tt.tag_id \
+                     WHERE tt.ticket_id = t.id AND (LOWER(tg.name) = ANY(",
+                )
+                .push_bind(names.clone())
+                .push(") OR tg.id IN (SELECT tag_id FROM tag_aliases WHERE LOWER(name) = ANY(")
+                .push_bind(names)
+                .push("))))");
+        }
+        Condition::Assignee(users) => push_user_match(builder, "t.assignee_id", users),
+        Condition::Reporter(users) => push_user_match(builder, "t.reporter_id", users),
+        Condition::Date { field, bounds } => {
+            let column = match field {
+                DateField::Created => "t.created_at",
+                DateField::Updated => "t.updated_at",
+                DateField::Resolved => "t.resolved_at",
+            };
+            builder.push("TRUE");
+            for (comparison, time) in bounds {
+                builder
+                    .push(format!(" AND {} {} ", column, comparison.as_operator()))
+                    .push_bind(*time);
             }
-        }).collect();
+        }
+        Condition::Text(term) => {
+            let terms = SearchTerms {
+                groups: vec![vec![term.clone()]],
+                excluded: Vec::new(),
+            };
+            push_search_match(builder, &terms);
+        }
+    }
+}
+
+fn push_user_match(builder: &mut QueryBuilder<'_, Postgres>, column: &str, users: &[UserRef]) {
+    builder.push("FALSE");
+    for user in users {
+        match user {
+            UserRef::Id(id) => {
+                builder.push(format!(" OR {} = ", column)).push_bind(*id);
+            }
+            UserRef::Email(email) => {
+                builder
+                    .push(format!(
+                        " OR {} IN (SELECT id FROM users WHERE LOWER(email) = LOWER(",
+                        column
+                    ))
+                    .push_bind(email.clone())
+                    .push("))");
+            }
+            UserRef::Nobody => {
+                builder.push(format!(" OR {} IS NULL", column));
+            }
+        }
+    }
+}
+
+// 全文检索命中或子串回退命中
+fn push_search_match(builder: &mut QueryBuilder<'_, Postgres>, terms: &SearchTerms) {
+    builder.push(
+        "(EXISTS (SELECT 1 FROM ticket_search s \
+         WHERE s.ticket_id = t.id AND s.document @@ to_tsquery('ticket_search', ",
+    );
+    builder.push_bind(terms.to_tsquery());
+    builder.push(")) OR ");
+    push_fallback_match(builder, terms);
+    builder.push(")");
+}
+
+// 子串匹配回退（中文等未分词文本），条件结构与 tsquery 一致：组间 AND、组内 OR、排除项取反
+fn push_fallback_match(builder: &mut QueryBuilder<'_, Postgres>, terms: &SearchTerms) {
+    builder.push("(TRUE");
+    for group in &terms.groups {
+        builder.push(" AND (FALSE");
+        for term in group {
+            builder.push(" OR ");
+            push_text_match(builder, &term.text());
+        }
+        builder.push(")");
+    }
+    for term in &terms.excluded {
+        builder.push(" AND NOT ");
+        push_text_match(builder, &term.text());
+    }
+    builder.push(")");
+}
+
+fn push_text_match(builder: &mut QueryBuilder<'_, Postgres>, text: &str) {
+    let pattern = like_pattern(text);
+    builder
+        .push("(t.title ILIKE ")
+        .push_bind(pattern.clone())
+        .push(" OR COALESCE(t.description, '') ILIKE ")
+        .push_bind(pattern.clone())
+        .push(" OR EXISTS (SELECT 1 FROM comments c WHERE c.ticket_id = t.id AND c.content ILIKE ")
+        .push_bind(pattern)
+        .push("))");
+}
+
+// 全文检索命中时使用 ts_headline 的结果，子串回退命中或未标记任何匹配时在原文中标记
+fn highlight(
+    matched: bool,
+    headline: &str,
+    original: &str,
+    terms: &[String],
+    snippet: bool,
+) -> String {
+    if matched && headline.contains(HIGHLIGHT_START) {
+        render_highlight(headline)
+    } else {
+        fallback_highlight(original, terms, snippet)
+    }
+}
+
+fn prefixed_columns(alias: &str) -> String {
+    TICKET_COLUMNS
+        .split(',')
+        .map(|column| format!("{}.{}", alias, column.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// 排序键对应的 SQL 表达式：priority 按严重程度排名，status 按状态在工作流中的位置，
+// due_date 以 ±infinity 代替空值，使未设置截止时间的工单在两个方向上都排在最后
+fn sort_expression(key: &TicketSortKey) -> String {
+    match key.field {
+        TicketSortField::CreatedAt => "t.created_at".to_string(),
+        TicketSortField::UpdatedAt => "t.updated_at".to_string(),
+        TicketSortField::Title => "t.title".to_string(),
+        TicketSortField::Priority => {
+            let ranks: Vec<String> = Priority::ALL
+                .iter()
+                .enumerate()
+                .map(|(rank, priority)| format!("WHEN '{}' THEN {}", priority.as_str(), rank))
+                .collect();
+            format!("(CASE t.priority {} END)", ranks.join(" "))
+        }
+        TicketSortField::Status => STATUS_POSITION.to_string(),
+        TicketSortField::DueDate => {
+            format!("COALESCE(t.due_date, {})", missing_due_date(key.descending))
+        }
+    }
+}
+
+// 游标中保存的排序键取值：截止时间保存原值（可能为空）
+fn sort_value_expression(key: &TicketSortKey) -> String {
+    match key.field {
+        TicketSortField::DueDate => "t.due_date".to_string(),
+        _ => sort_expression(key),
+    }
+}
 
-        Ok(tickets)
+fn missing_due_date(descending: bool) -> &'static str {
+    if descending {
+        "'-infinity'::timestamptz"
+    } else {
+        "'infinity'::timestamptz"
     }
-}
\ No newline at end of file
+}
+
+// 按字段类型绑定游标中的排序键取值
+fn push_sort_value(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    key: &TicketSortKey,
+    value: &Value,
+) -> Result<(), AppError> {
+    let invalid = || AppError::bad_request("无效的分页游标");
+    match key.field {
+        TicketSortField::CreatedAt | TicketSortField::UpdatedAt => {
+            let time =
+                serde_json::from_value::<DateTime<Utc>>(value.clone()).map_err(|_| invalid())?;
+            builder.push_bind(time);
+        }
+        TicketSortField::Title => {
+            let text = value.as_str().ok_or_else(invalid)?;
+            builder.push_bind(text.to_string());
+        }
+        TicketSortField::Priority | TicketSortField::Status => {
+            let rank = value
+                .as_i64()
+                .and_then(|rank| i32::try_from(rank).ok())
+                .ok_or_else(invalid)?;
+            builder.push_bind(rank);
+        }
+        TicketSortField::DueDate if value.is_null() => {
+            builder.push(missing_due_date(key.descending));
+        }
+        TicketSortField::DueDate => {
+            let time =
+                serde_json::from_value::<DateTime<Utc>>(value.clone()).map_err(|_| invalid())?;
+            builder.push_bind(time);
+        }
+    }
+
+    Ok(())
+}
+
+// 游标内容：生成时的排序键、最后一条记录的排序键取值与 id
+#[derive(Debug, Serialize, Deserialize)]
+struct TicketCursor {
+    sort: Vec<TicketSortKey>,
+    values: Vec<Value>,
+    id: UuidType,
+}
+
+// 游标分页查询的行：额外返回排序键取值，用于生成下一页游标
+#[derive(FromRow)]
+struct CursorRow {
+    #[sqlx(flatten)]
+    ticket: Ticket,
+    sort_values: Json<Vec<Value>>,
+}
+
+fn sort_keys(query_params: &TicketQuery) -> Vec<TicketSortKey> {
+    if query_params.sort.is_empty() {
+        TicketSortKey::default_keys()
+    } else {
+        query_params.sort.clone()
+    }
+}
+
+// id 作为最后的排序键保证顺序稳定，方向与最后一个排序键一致
+fn push_order_by(builder: &mut QueryBuilder<'_, Postgres>, keys: &[TicketSortKey]) {
+    let direction = |descending: bool| if descending { "DESC" } else { "ASC" };
+
+    let mut order_by: Vec<String> = keys
+        .iter()
+        .map(|key| format!("{} {}", sort_expression(key), direction(key.descending)))
+        .collect();
+    let id_descending = keys.last().is_some_and(|key| key.descending);
+    order_by.push(format!("t.id {}", direction(id_descending)));
+
+    builder.push(" ORDER BY ").push(order_by.join(", "));
+}
+
+// keyset 条件：(k1 在后) OR (k1 相等 AND k2 在后) OR … OR (全部相等 AND id 在后)
+fn push_keyset(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    cursor: &TicketCursor,
+) -> Result<(), AppError> {
+    let after = |descending: bool| if descending { " < " } else { " > " };
+
+    builder.push(" AND (FALSE");
+    for index in 0..=cursor.sort.len() {
+        builder.push(" OR (TRUE");
+        for (key, value) in cursor.sort[..index].iter().zip(&cursor.values) {
+            builder.push(format!(" AND {} = ", sort_expression(key)));
+            push_sort_value(builder, key, value)?;
+        }
+        match cursor.sort.get(index) {
+            Some(key) => {
+                builder.push(format!(
+                    " AND {}{}",
+                    sort_expression(key),
+                    after(key.descending)
+                ));
+                push_sort_value(builder, key, &cursor.values[index])?;
+            }
+            None => {
+                let id_descending = cursor.sort.last().is_some_and(|key| key.descending);
+                builder
+                    .push(format!(" AND t.id{}", after(id_descending)))
+                    .push_bind(cursor.id);
+            }
+        }
+        builder.push(")");
+    }
+    builder.push(")");
+
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use crate::{
+    auth::hash_password,
+    database::DbPool,
+    error::AppError,
+    models::{CreateUserRequest, PaginatedResponse, UpdateUserRequest, User, UserQuery, UserSummary},
+    utils::resolve_pagination,
+};
+use sqlx::{query_as, query_scalar, Postgres, QueryBuilder};
+use std::collections::HashMap;
+use uuid::Uuid as UuidType;
+
+const USER_COLUMNS: &str = "id, name, email, avatar_color, role, is_active, created_at, updated_at";
+
+// 未指定头像颜色时按邮箱从中挑选
+const AVATAR_COLORS: [&str; 8] = [
+    "#EF4444", "#F59E0B", "#10B981", "#3B82F6", "#6366F1", "#8B5CF6", "#EC4899", "#14B8A6",
+];
+
+pub struct UserRepository {
+    pool: DbPool,
+}
+
+impl UserRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 创建用户
+    pub async fn create(&self, request: CreateUserRequest) -> Result<User, AppError> {
+        let email = normalize_email(&request.email);
+        if self.email_exists(&email, None).await? {
+            return Err(AppError::conflict("邮箱"));
+        }
+
+        let id = UuidType::new_v4();
+        let now = chrono::Utc::now();
+        let avatar_color = request
+            .avatar_color
+            .unwrap_or_else(|| default_avatar_color(&email).to_string());
+        let password_hash = request.password.as_deref().map(hash_password).transpose()?;
+
+        let user = query_as::<_, User>(&format!(
+            r#"
+            INSERT INTO users (
+                id, name, email, avatar_color, password_hash, role, is_active,
+                created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, TRUE, $7, $7)
+            RETURNING {USER_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .bind(request.name.trim())
+        .bind(email)
+        .bind(avatar_color)
+        .bind(password_hash)
+        .bind(request.role.unwrap_or_default())
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    // 根据ID获取用户
+    pub async fn get_by_id(&self, id: UuidType) -> Result<User, AppError> {
+        query_as::<_, User>(&format!("SELECT {USER_COLUMNS} FROM users WHERE id = $1"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("用户"))
+    }
+
+    // 根据邮箱获取用户
+    pub async fn find_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        let user = query_as::<_, User>(&format!("SELECT {USER_COLUMNS} FROM users WHERE email = $1"))
+            .bind(normalize_email(email))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(user)
+    }
+
+    // 获取用户及其密码哈希（仅用于登录校验）
+    pub async fn find_credentials(
+        &self,
+        email: &str,
+    ) -> Result<Option<(User, Option<String>)>, AppError> {
+        let Some(user) = self.find_by_email(email).await? else {
+            return Ok(None);
+        };
+
+        let password_hash: Option<String> =
+            query_scalar("SELECT password_hash FROM users WHERE id = $1")
+                .bind(user.id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(Some((user, password_hash)))
+    }
+
+    // 分页列出用户
+    pub async fn list(&self, query_params: UserQuery) -> Result<PaginatedResponse<User>, AppError> {
+        let (limit, offset) =
+            resolve_pagination(query_params.page, query_params.limit, query_params.offset);
+
+        let mut count_builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM users");
+        push_filters(&mut count_builder, &query_params);
+        let total: i64 = count_builder
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await?;
+
+        let mut builder = QueryBuilder::<Postgres>::new(format!("SELECT {USER_COLUMNS} FROM users"));
+        push_filters(&mut builder, &query_params);
+        builder.push(" ORDER BY name, id LIMIT ").push_bind(limit);
+        builder.push(" OFFSET ").push_bind(offset);
+
+        let users = builder
+            .build_query_as::<User>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(PaginatedResponse::new(users, total, limit, offset))
+    }
+
+    // 更新用户
+    pub async fn update(&self, id: UuidType, request: UpdateUserRequest) -> Result<User, AppError> {
+        let email = request.email.as_deref().map(normalize_email);
+        if let Some(ref email) = email {
+            if self.email_exists(email, Some(id)).await? {
+                return Err(AppError::conflict("邮箱"));
+            }
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new("UPDATE users SET updated_at = ");
+        builder.push_bind(chrono::Utc::now());
+
+        if let Some(name) = request.name {
+            builder.push(", name = ").push_bind(name.trim().to_string());
+        }
+
+        if let Some(email) = email {
+            builder.push(", email = ").push_bind(email);
+        }
+
+        if let Some(avatar_color) = request.avatar_color {
+            builder.push(", avatar_color = ").push_bind(avatar_color);
+        }
+
+        if let Some(password) = request.password {
+            builder
+                .push(", password_hash = ")
+                .push_bind(hash_password(&password)?);
+        }
+
+        if let Some(role) = request.role {
+            builder.push(", role = ").push_bind(role);
+        }
+
+        builder
+            .push(" WHERE id = ")
+            .push_bind(id)
+            .push(format!(" RETURNING {USER_COLUMNS}"));
+
+        builder
+            .build_query_as::<User>()
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("用户"))
+    }
+
+    // 停用用户（保留历史关联，不再允许被指派）
+    pub async fn deactivate(&self, id: UuidType) -> Result<User, AppError> {
+        query_as::<_, User>(&format!(
+            "UPDATE users SET is_active = FALSE, updated_at = $1 WHERE id = $2 RETURNING {USER_COLUMNS}"
+        ))
+        .bind(chrono::Utc::now())
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("用户"))
+    }
+
+    // 检查用户存在且处于启用状态
+    pub async fn ensure_active(&self, id: UuidType, role: &str) -> Result<(), AppError> {
+        let active: Option<bool> = query_scalar("SELECT is_active FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match active {
+            Some(true) => Ok(()),
+            Some(false) => Err(AppError::bad_request(format!("{}已停用", role))),
+            None => Err(AppError::bad_request(format!("{}不存在", role))),
+        }
+    }
+
+    // 批量获取用户摘要
+    pub async fn get_summaries(
+        &self,
+        ids: &[UuidType],
+    ) -> Result<HashMap<UuidType, UserSummary>, AppError> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let users = query_as::<_, UserSummary>(
+            "SELECT id, name, email, avatar_color FROM users WHERE id = ANY($1)",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users.into_iter().map(|user| (user.id, user)).collect())
+    }
+
+    async fn email_exists(&self, email: &str, exclude_id: Option<UuidType>) -> Result<bool, AppError> {
+        let exists: bool = query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 AND ($2::uuid IS NULL OR id != $2))",
+        )
+        .bind(email)
+        .bind(exclude_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+}
+
+fn push_filters(builder: &mut QueryBuilder<'_, Postgres>, query_params: &UserQuery) {
+    builder.push(" WHERE 1=1");
+
+    if !query_params.include_inactive.unwrap_or(false) {
+        builder.push(" AND is_active");
+    }
+
+    if let Some(role) = query_params.role {
+        builder.push(" AND role = ").push_bind(role);
+    }
+
+    if let Some(ref search) = query_params.search {
+        let search = search.trim();
+        if !search.is_empty() {
+            let pattern = format!("%{}%", search);
+            builder
+                .push(" AND (name ILIKE ")
+                .push_bind(pattern.clone())
+                .push(" OR email ILIKE ")
+                .push_bind(pattern)
+                .push(")");
+        }
+    }
+}
+
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn default_avatar_color(email: &str) -> &'static str {
+    let hash = email
+        .bytes()
+        .fold(0usize, |acc, b| acc.wrapping_mul(31).wrapping_add(b as usize));
+    AVATAR_COLORS[hash % AVATAR_COLORS.len()]
+}
@@ -0,0 +1,405 @@
+use crate::{
+    database::DbPool,
+    error::AppError,
+    models::{
+        CreateWebhookRequest, CreatedWebhook, PaginatedResponse, UpdateWebhookRequest, Webhook,
+        WebhookDelivery, WebhookDeliveryAttempt, WebhookDeliveryDetail, WebhookDeliveryQuery,
+        WebhookDeliveryStatus,
+    },
+    services::webhooks::{generate_secret, WEBHOOK_EVENTS},
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{query, query_as, FromRow, PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid as UuidType;
+
+const WEBHOOK_COLUMNS: &str = "id, url, secret, events, active, created_by, created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str = "id, webhook_id, event, payload, status, attempt_count, \
+                                next_attempt_at, response_status, response_body, error, \
+                                delivered_at, created_at, updated_at";
+
+// 已取出待发送的投递任务
+#[derive(Debug, Clone, FromRow)]
+pub struct DueDelivery {
+    pub id: UuidType,
+    pub event: String,
+    pub payload: Value,
+    pub attempt_count: i32,
+    pub url: String,
+    pub secret: String,
+}
+
+// 单次发送的结果
+#[derive(Debug, Clone)]
+pub struct AttemptOutcome {
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+}
+
+impl AttemptOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.response_status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+pub struct WebhookRepository {
+    pool: DbPool,
+}
+
+impl WebhookRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<Webhook>, AppError> {
+        let webhooks = query_as::<_, Webhook>(&format!(
+            "SELECT {WEBHOOK_COLUMNS} FROM webhooks ORDER BY created_at"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(webhooks)
+    }
+
+    pub async fn get(&self, id: UuidType) -> Result<Webhook, AppError> {
+        query_as::<_, Webhook>(&format!(
+            "SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("Webhook"))
+    }
+
+    pub async fn create(
+        &self,
+        request: CreateWebhookRequest,
+        created_by: UuidType,
+    ) -> Result<CreatedWebhook, AppError> {
+        let events = normalize_events(request.events)?;
+        let secret = request.secret.unwrap_or_else(generate_secret);
+        let now = chrono::Utc::now();
+
+        let webhook = query_as::<_, Webhook>(&format!(
+            r#"
+            INSERT INTO webhooks (id, url, secret, events, active, created_by, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+            RETURNING {WEBHOOK_COLUMNS}
+            "#
+        ))
+        .bind(UuidType::new_v4())
+        .bind(request.url.trim())
+        .bind(&secret)
+        .bind(events)
+        .bind(request.active.unwrap_or(true))
+        .bind(created_by)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(CreatedWebhook { webhook, secret })
+    }
+
+    pub async fn update(
+        &self,
+        id: UuidType,
+        request: UpdateWebhookRequest,
+    ) -> Result<Webhook, AppError> {
+        let mut builder = QueryBuilder::<Postgres>::new("UPDATE webhooks SET updated_at = ");
+        builder.push_bind(chrono::Utc::now());
+
+        if let Some(url) = request.url {
+            builder.push(", url = ").push_bind(url.trim().to_string());
+        }
+
+        if let Some(secret) = request.secret {
+            builder.push(", secret = ").push_bind(secret);
+        }
+
+        if let Some(events) = request.events {
+            builder
+                .push(", events = ")
+                .push_bind(normalize_events(events)?);
+        }
+
+        if let Some(active) = request.active {
+            builder.push(", active = ").push_bind(active);
+        }
+
+        builder
+            .push(" WHERE id = ")
+            .push_bind(id)
+            .push(format!(" RETURNING {WEBHOOK_COLUMNS}"));
+
+        builder
+            .build_query_as::<Webhook>()
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::not_found("Webhook"))
+    }
+
+    // 删除 Webhook（投递记录级联删除）
+    pub async fn delete(&self, id: UuidType) -> Result<(), AppError> {
+        let result = query("DELETE FROM webhooks WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::not_found("Webhook"));
+        }
+
+        Ok(())
+    }
+
+    // 分页列出投递记录（新的在前）
+    pub async fn list_deliveries(
+        &self,
+        webhook_id: UuidType,
+        query_params: &WebhookDeliveryQuery,
+        limit: i64,
+        offset: i64,
+    ) -> Result<PaginatedResponse<WebhookDelivery>, AppError> {
+        self.get(webhook_id).await?;
+
+        let push_filters = |builder: &mut QueryBuilder<'_, Postgres>| {
+            builder.push(" WHERE webhook_id = ").push_bind(webhook_id);
+            if let Some(status) = query_params.status {
+                builder.push(" AND status = ").push_bind(status);
+            }
+            if let Some(ref event) = query_params.event {
+                builder.push(" AND event = ").push_bind(event.clone());
+            }
+        };
+
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM webhook_deliveries");
+        push_filters(&mut builder);
+        let total: i64 = builder.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries"
+        ));
+        push_filters(&mut builder);
+        builder.push(" ORDER BY created_at DESC, id DESC LIMIT ");
+        builder.push_bind(limit);
+        builder.push(" OFFSET ").push_bind(offset);
+        let deliveries = builder
+            .build_query_as::<WebhookDelivery>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(PaginatedResponse::new(deliveries, total, limit, offset))
+    }
+
+    // 获取投递记录及全部尝试
+    pub async fn get_delivery(
+        &self,
+        webhook_id: UuidType,
+        id: UuidType,
+    ) -> Result<WebhookDeliveryDetail, AppError> {
+        let delivery = query_as::<_, WebhookDelivery>(&format!(
+            "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries WHERE id = $1 AND webhook_id = $2"
+        ))
+        .bind(id)
+        .bind(webhook_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("投递记录"))?;
+
+        let attempts = query_as::<_, WebhookDeliveryAttempt>(
+            r#"
+            SELECT id, attempt, response_status, response_body, error, duration_ms, attempted_at
+            FROM webhook_delivery_attempts
+            WHERE delivery_id = $1
+            ORDER BY attempt
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(WebhookDeliveryDetail { delivery, attempts })
+    }
+
+    // 立即重新投递（包括已成功或已放弃的记录）
+    pub async fn retry_delivery(
+        &self,
+        webhook_id: UuidType,
+        id: UuidType,
+    ) -> Result<WebhookDelivery, AppError> {
+        query_as::<_, WebhookDelivery>(&format!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'pending', next_attempt_at = $3
+            WHERE id = $1 AND webhook_id = $2
+            RETURNING {DELIVERY_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .bind(webhook_id)
+        .bind(chrono::Utc::now())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("投递记录"))
+    }
+
+    // 取出到期的投递任务，并将 next_attempt_at 推迟到租约结束，
+    // 发送过程中进程退出时，租约到期后会被重新取出
+    pub async fn claim_due(
+        &self,
+        limit: i64,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Vec<DueDelivery>, AppError> {
+        let deliveries = query_as::<_, DueDelivery>(
+            r#"
+            WITH due AS (
+                SELECT d.id
+                FROM webhook_deliveries d
+                INNER JOIN webhooks w ON w.id = d.webhook_id AND w.active
+                WHERE d.status = 'pending' AND d.next_attempt_at <= now()
+                ORDER BY d.next_attempt_at
+                LIMIT $1
+                FOR UPDATE OF d SKIP LOCKED
+            )
+            UPDATE webhook_deliveries d
+            SET next_attempt_at = $2
+            FROM due, webhooks w
+            WHERE d.id = due.id AND w.id = d.webhook_id
+            RETURNING d.id, d.event, d.payload, d.attempt_count, w.url, w.secret
+            "#,
+        )
+        .bind(limit)
+        .bind(lease_until)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    // 记录一次发送尝试；retry_at 为空表示不再重试
+    pub async fn record_attempt(
+        &self,
+        delivery: &DueDelivery,
+        outcome: &AttemptOutcome,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), AppError> {
+        let attempt = delivery.attempt_count + 1;
+        let status = match (outcome.succeeded(), retry_at) {
+            (true, _) => WebhookDeliveryStatus::Succeeded,
+            (false, Some(_)) => WebhookDeliveryStatus::Pending,
+            (false, None) => WebhookDeliveryStatus::Failed,
+        };
+        let now = chrono::Utc::now();
+
+        let mut tx = self.pool.begin().await?;
+
+        query(
+            r#"
+            INSERT INTO webhook_delivery_attempts (
+                id, delivery_id, attempt, response_status, response_body, error, duration_ms,
+                attempted_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(UuidType::new_v4())
+        .bind(delivery.id)
+        .bind(attempt)
+        .bind(outcome.response_status)
+        .bind(&outcome.response_body)
+        .bind(&outcome.error)
+        .bind(outcome.duration_ms)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        query(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = $2,
+                attempt_count = $3,
+                next_attempt_at = $4,
+                response_status = $5,
+                response_body = $6,
+                error = $7,
+                delivered_at = CASE WHEN $2 = 'succeeded' THEN $8 ELSE delivered_at END
+            WHERE id = $1
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(status)
+        .bind(attempt)
+        .bind(if status == WebhookDeliveryStatus::Pending {
+            retry_at
+        } else {
+            None
+        })
+        .bind(outcome.response_status)
+        .bind(&outcome.response_body)
+        .bind(&outcome.error)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+// 在调用方事务中为订阅了该事件的 Webhook 创建投递任务
+pub(crate) async fn enqueue(
+    conn: &mut PgConnection,
+    event: &str,
+    payload: &Value,
+) -> Result<(), AppError> {
+    let now = chrono::Utc::now();
+
+    query(
+        r#"
+        INSERT INTO webhook_deliveries (id, webhook_id, event, payload, next_attempt_at, created_at, updated_at)
+        SELECT gen_random_uuid(), w.id, $1, $2, $3, $3, $3
+        FROM webhooks w
+        WHERE w.active
+          AND ($1 = ANY(w.events) OR '*' = ANY(w.events) OR split_part($1, '.', 1) || '.*' = ANY(w.events))
+        "#,
+    )
+    .bind(event)
+    .bind(payload)
+    .bind(now)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// 校验并去重订阅的事件，支持 "*" 与 "ticket.*" 形式的通配
+fn normalize_events(events: Vec<String>) -> Result<Vec<String>, AppError> {
+    let mut normalized = Vec::with_capacity(events.len());
+    for event in events {
+        let event = event.trim().to_lowercase();
+        let known = event == "*"
+            || WEBHOOK_EVENTS.contains(&event.as_str())
+            || event.strip_suffix(".*").is_some_and(|prefix| {
+                WEBHOOK_EVENTS
+                    .iter()
+                    .any(|known| known.split('.').next() == Some(prefix))
+            });
+        if !known {
+            return Err(AppError::bad_request(format!(
+                "未知的事件: {}，可选值: *, ticket.*, tag.*, {}",
+                event,
+                WEBHOOK_EVENTS.join(", ")
+            )));
+        }
+        if !normalized.contains(&event) {
+            normalized.push(event);
+        }
+    }
+
+    Ok(normalized)
+}
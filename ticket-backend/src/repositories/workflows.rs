@@ -0,0 +1,443 @@
+use crate::{
+    database::DbPool,
+    error::AppError,
+    models::{
+        CreateWorkflowRequest, StatusCategory, UpdateWorkflowRequest, Workflow, WorkflowState,
+        WorkflowTransition, WorkflowWithStates,
+    },
+};
+use sqlx::{query, query_as, query_scalar, PgConnection, Postgres, QueryBuilder};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid as UuidType;
+
+const WORKFLOW_COLUMNS: &str = "id, name, description, is_default, created_at, updated_at";
+
+pub struct WorkflowRepository {
+    pool: DbPool,
+}
+
+impl WorkflowRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    // 创建工作流
+    pub async fn create(
+        &self,
+        request: CreateWorkflowRequest,
+    ) -> Result<WorkflowWithStates, AppError> {
+        validate_definition(&request.states, &request.transitions)?;
+        if self.name_exists(&request.name, None).await? {
+            return Err(AppError::conflict("工作流名称"));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        if request.is_default {
+            clear_default(&mut tx).await?;
+        }
+
+        let id = UuidType::new_v4();
+        let now = chrono::Utc::now();
+        query(
+            r#"
+            INSERT INTO workflows (id, name, description, is_default, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $5)
+            "#,
+        )
+        .bind(id)
+        .bind(request.name.trim())
+        .bind(request.description)
+        .bind(request.is_default)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        insert_definition(&mut tx, id, &request.states, &request.transitions).await?;
+
+        tx.commit().await?;
+
+        self.get(id).await
+    }
+
+    // 获取工作流及其状态与流转规则
+    pub async fn get(&self, id: UuidType) -> Result<WorkflowWithStates, AppError> {
+        let workflow = query_as::<_, Workflow>(&format!(
+            "SELECT {WORKFLOW_COLUMNS} FROM workflows WHERE id = $1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::not_found("工作流"))?;
+
+        let mut loaded = self.load_definitions(vec![workflow]).await?;
+        loaded
+            .pop()
+            .ok_or_else(|| AppError::internal("工作流加载失败"))
+    }
+
+    // 获取默认工作流
+    pub async fn get_default(&self) -> Result<WorkflowWithStates, AppError> {
+        let id: UuidType = query_scalar("SELECT id FROM workflows WHERE is_default")
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::internal("未配置默认工作流"))?;
+
+        self.get(id).await
+    }
+
+    // 批量获取工作流，按ID索引
+    pub async fn get_many(
+        &self,
+        ids: &[UuidType],
+    ) -> Result<HashMap<UuidType, WorkflowWithStates>, AppError> {
+        let workflows = query_as::<_, Workflow>(&format!(
+            "SELECT {WORKFLOW_COLUMNS} FROM workflows WHERE id = ANY($1)"
+        ))
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let loaded = self.load_definitions(workflows).await?;
+        Ok(loaded
+            .into_iter()
+            .map(|workflow| (workflow.workflow.id, workflow))
+            .collect())
+    }
+
+    // 列出全部工作流（默认工作流在前）
+    pub async fn list(&self) -> Result<Vec<WorkflowWithStates>, AppError> {
+        let workflows = query_as::<_, Workflow>(&format!(
+            "SELECT {WORKFLOW_COLUMNS} FROM workflows ORDER BY is_default DESC, name"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.load_definitions(workflows).await
+    }
+
+    // 更新工作流；状态被移除前须确认没有工单仍处于该状态
+    pub async fn update(
+        &self,
+        id: UuidType,
+        request: UpdateWorkflowRequest,
+    ) -> Result<WorkflowWithStates, AppError> {
+        let current = self.get(id).await?;
+
+        if let Some(ref name) = request.name {
+            if self.name_exists(name, Some(id)).await? {
+                return Err(AppError::conflict("工作流名称"));
+            }
+        }
+
+        if request.is_default == Some(false) && current.workflow.is_default {
+            return Err(AppError::bad_request(
+                "不能取消默认工作流，请将其他工作流设为默认",
+            ));
+        }
+
+        let definition_changed = request.states.is_some() || request.transitions.is_some();
+        let states = request.states.unwrap_or(current.states);
+        let transitions = request.transitions.unwrap_or(current.transitions);
+        if definition_changed {
+            validate_definition(&states, &transitions)?;
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        if request.is_default == Some(true) {
+            clear_default(&mut tx).await?;
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new("UPDATE workflows SET updated_at = ");
+        builder.push_bind(chrono::Utc::now());
+
+        if let Some(name) = request.name {
+            builder.push(", name = ").push_bind(name.trim().to_string());
+        }
+
+        if let Some(description) = request.description {
+            builder.push(", description = ").push_bind(description);
+        }
+
+        if let Some(is_default) = request.is_default {
+            builder.push(", is_default = ").push_bind(is_default);
+        }
+
+        builder.push(" WHERE id = ").push_bind(id);
+        builder.build().execute(&mut *tx).await?;
+
+        if definition_changed {
+            let keys: Vec<&str> = states.iter().map(|state| state.key.as_str()).collect();
+            let orphaned: Vec<(String, i64)> = query_as(
+                r#"
+                SELECT status, COUNT(*)
+                FROM tickets
+                WHERE workflow_id = $1 AND NOT (status = ANY($2))
+                GROUP BY status
+                ORDER BY status
+                "#,
+            )
+            .bind(id)
+            .bind(&keys)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            if !orphaned.is_empty() {
+                let in_use: Vec<String> = orphaned
+                    .iter()
+                    .map(|(status, count)| format!("{}（{}个工单）", status, count))
+                    .collect();
+                return Err(AppError::Conflict(format!(
+                    "以下状态仍在使用，不能移除: {}",
+                    in_use.join(", ")
+                )));
+            }
+
+            query("DELETE FROM workflow_states WHERE workflow_id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            insert_definition(&mut tx, id, &states, &transitions).await?;
+        }
+
+        tx.commit().await?;
+
+        self.get(id).await
+    }
+
+    // 删除工作流；默认工作流或仍有工单使用时不可删除
+    pub async fn delete(&self, id: UuidType) -> Result<(), AppError> {
+        let current = self.get(id).await?;
+        if current.workflow.is_default {
+            return Err(AppError::Conflict("默认工作流不能删除".to_string()));
+        }
+
+        let in_use: i64 = query_scalar("SELECT COUNT(*) FROM tickets WHERE workflow_id = $1")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+        if in_use > 0 {
+            return Err(AppError::Conflict(format!(
+                "仍有 {} 个工单（含回收站）使用该工作流",
+                in_use
+            )));
+        }
+
+        query("DELETE FROM workflows WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn load_definitions(
+        &self,
+        workflows: Vec<Workflow>,
+    ) -> Result<Vec<WorkflowWithStates>, AppError> {
+        let ids: Vec<UuidType> = workflows.iter().map(|workflow| workflow.id).collect();
+
+        let states = query_as::<_, WorkflowStateRow>(
+            r#"
+            SELECT workflow_id, key, name, category
+            FROM workflow_states
+            WHERE workflow_id = ANY($1)
+            ORDER BY position, key
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let transitions = query_as::<_, WorkflowTransitionRow>(
+            r#"
+            SELECT wt.workflow_id, wt.from_state, wt.to_state
+            FROM workflow_transitions wt
+            INNER JOIN workflow_states f
+                ON f.workflow_id = wt.workflow_id AND f.key = wt.from_state
+            INNER JOIN workflow_states t
+                ON t.workflow_id = wt.workflow_id AND t.key = wt.to_state
+            WHERE wt.workflow_id = ANY($1)
+            ORDER BY f.position, t.position
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut states_by_workflow: HashMap<UuidType, Vec<WorkflowState>> = HashMap::new();
+        for row in states {
+            states_by_workflow
+                .entry(row.workflow_id)
+                .or_default()
+                .push(row.state);
+        }
+
+        let mut transitions_by_workflow: HashMap<UuidType, Vec<WorkflowTransition>> =
+            HashMap::new();
+        for row in transitions {
+            transitions_by_workflow
+                .entry(row.workflow_id)
+                .or_default()
+                .push(row.transition);
+        }
+
+        Ok(workflows
+            .into_iter()
+            .map(|workflow| WorkflowWithStates {
+                states: states_by_workflow.remove(&workflow.id).unwrap_or_default(),
+                transitions: transitions_by_workflow
+                    .remove(&workflow.id)
+                    .unwrap_or_default(),
+                workflow,
+            })
+            .collect())
+    }
+
+    async fn name_exists(
+        &self,
+        name: &str,
+        exclude_id: Option<UuidType>,
+    ) -> Result<bool, AppError> {
+        let exists: bool = query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM workflows WHERE name = $1 AND ($2::uuid IS NULL OR id != $2))",
+        )
+        .bind(name.trim())
+        .bind(exclude_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct WorkflowStateRow {
+    workflow_id: UuidType,
+    #[sqlx(flatten)]
+    state: WorkflowState,
+}
+
+#[derive(sqlx::FromRow)]
+struct WorkflowTransitionRow {
+    workflow_id: UuidType,
+    #[sqlx(flatten)]
+    transition: WorkflowTransition,
+}
+
+// 校验工作流定义：状态标识唯一且合法，至少各有一个 open 与 done 状态，流转引用已定义的状态
+fn validate_definition(
+    states: &[WorkflowState],
+    transitions: &[WorkflowTransition],
+) -> Result<(), AppError> {
+    let mut keys = HashSet::new();
+    for state in states {
+        let valid_key = state
+            .key
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase())
+            && state
+                .key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_key {
+            return Err(AppError::bad_request(format!(
+                "状态标识 {} 无效，只能包含小写字母、数字和下划线，且以字母开头",
+                state.key
+            )));
+        }
+        if !keys.insert(state.key.as_str()) {
+            return Err(AppError::bad_request(format!(
+                "状态标识 {} 重复",
+                state.key
+            )));
+        }
+    }
+
+    for category in [StatusCategory::Open, StatusCategory::Done] {
+        if !states.iter().any(|state| state.category == category) {
+            return Err(AppError::bad_request(
+                "工作流至少需要一个 open 分类和一个 done 分类的状态",
+            ));
+        }
+    }
+
+    let mut edges = HashSet::new();
+    for transition in transitions {
+        for key in [&transition.from, &transition.to] {
+            if !keys.contains(key.as_str()) {
+                return Err(AppError::bad_request(format!(
+                    "流转引用了未定义的状态: {}",
+                    key
+                )));
+            }
+        }
+        if transition.from == transition.to {
+            return Err(AppError::bad_request(format!(
+                "流转的起止状态不能相同: {}",
+                transition.from
+            )));
+        }
+        if !edges.insert((transition.from.as_str(), transition.to.as_str())) {
+            return Err(AppError::bad_request(format!(
+                "流转 {} -> {} 重复",
+                transition.from, transition.to
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+async fn clear_default(conn: &mut PgConnection) -> Result<(), AppError> {
+    query("UPDATE workflows SET is_default = FALSE WHERE is_default")
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn insert_definition(
+    conn: &mut PgConnection,
+    workflow_id: UuidType,
+    states: &[WorkflowState],
+    transitions: &[WorkflowTransition],
+) -> Result<(), AppError> {
+    let keys: Vec<&str> = states.iter().map(|state| state.key.as_str()).collect();
+    let names: Vec<&str> = states.iter().map(|state| state.name.trim()).collect();
+    let categories: Vec<&str> = states.iter().map(|state| state.category.as_str()).collect();
+    let positions: Vec<i32> = (0..states.len() as i32).collect();
+
+    query(
+        r#"
+        INSERT INTO workflow_states (workflow_id, key, name, category, position)
+        SELECT $1, * FROM UNNEST($2::varchar[], $3::varchar[], $4::varchar[], $5::int[])
+        "#,
+    )
+    .bind(workflow_id)
+    .bind(&keys)
+    .bind(&names)
+    .bind(&categories)
+    .bind(&positions)
+    .execute(&mut *conn)
+    .await?;
+
+    let from: Vec<&str> = transitions.iter().map(|t| t.from.as_str()).collect();
+    let to: Vec<&str> = transitions.iter().map(|t| t.to.as_str()).collect();
+
+    query(
+        r#"
+        INSERT INTO workflow_transitions (workflow_id, from_state, to_state)
+        SELECT $1, * FROM UNNEST($2::varchar[], $3::varchar[])
+        "#,
+    )
+    .bind(workflow_id)
+    .bind(&from)
+    .bind(&to)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
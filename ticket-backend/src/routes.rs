@@ -1,32 +1,271 @@
 use axum::{
+    extract::DefaultBodyLimit,
+    handler::Handler,
+    middleware,
     routing::{delete, get, post, put},
     Router,
 };
-use sqlx::PgPool;
 use tower_http::cors::CorsLayer;
 
-use crate::handlers;
+use crate::{
+    auth,
+    handlers::{
+        self, attachments, collaboration, comments, events, notifications, saved_views, tag_groups,
+        tags, tickets, users, webhooks, workflows,
+    },
+    state::AppState,
+};
 
-pub fn create_app(pool: PgPool) -> Router {
-    Router::new()
+pub fn create_app(state: AppState) -> Router {
+    // 无需认证的路由
+    let public = Router::new()
         // 健康检查路由
         .route("/", get(handlers::health_check_basic))
         .route("/health", get(handlers::health_check_basic))
         .route("/api/health", get(handlers::health_check_detailed))
+        // 登录
+        .route("/api/v1/auth/login", post(handlers::auth::login));
+
+    // 仅管理员可访问的处理器（在认证中间件之后执行）
+    let admin_only = middleware::from_fn(auth::require_admin);
+    // 修改共享数据的处理器，只读角色不可访问
+    let writer_only = middleware::from_fn(auth::require_writer);
+
+    // 附件上传路由放宽默认的 2MB 请求体限制
+    let upload_limit =
+        DefaultBodyLimit::max(attachments::upload_body_limit(&state.config.attachments));
+
+    // 需要 Bearer 令牌的路由
+    let protected = Router::new()
+        // 认证路由
+        .route("/api/v1/auth/me", get(handlers::auth::me))
+        .route(
+            "/api/v1/auth/tokens",
+            get(handlers::auth::list_tokens).post(handlers::auth::create_token),
+        )
+        .route(
+            "/api/v1/auth/tokens/:id",
+            delete(handlers::auth::revoke_token),
+        )
+        // 数据库维护路由
         .route("/api/db/stats", get(handlers::database_stats))
-        .route("/api/db/optimize", post(handlers::database_optimize))
+        .route(
+            "/api/db/optimize",
+            post(handlers::database_optimize.layer(admin_only.clone())),
+        )
         // 标签路由
-        .route("/api/v1/tags", get(handlers::list_tags))
-        .route("/api/v1/tags", post(handlers::create_tag))
-        .route("/api/v1/tags/:id", get(handlers::get_tag))
-        .route("/api/v1/tags/:id", put(handlers::update_tag))
-        .route("/api/v1/tags/:id", delete(handlers::delete_tag))
+        .route(
+            "/api/v1/tags",
+            get(tags::list_tags).post(tags::create_tag.layer(writer_only.clone())),
+        )
+        .route("/api/v1/tags/search", get(tags::search_tags))
+        .route("/api/v1/tags/popular", get(tags::get_popular_tags))
+        .route("/api/v1/tags/stats", get(tags::get_tag_stats))
+        .route("/api/v1/tags/tree", get(tags::get_tag_tree))
+        .route("/api/v1/tags/palette", get(tags::get_tag_palette))
+        .route("/api/v1/tags/:id/subtree", get(tags::get_tag_subtree))
+        .route(
+            "/api/v1/tags/:id/move",
+            put(tags::move_tag.layer(writer_only.clone())),
+        )
+        .route(
+            "/api/v1/tags/:id/merge",
+            post(tags::merge_tags.layer(admin_only.clone())),
+        )
+        .route("/api/v1/tags/:id/aliases", get(tags::get_tag_aliases))
+        .route(
+            "/api/v1/tags/:id/group",
+            put(tags::set_tag_group.layer(writer_only.clone())),
+        )
+        // 标签分组路由
+        .route(
+            "/api/v1/tag-groups",
+            get(tag_groups::list_tag_groups)
+                .post(tag_groups::create_tag_group.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/tag-groups/:id",
+            get(tag_groups::get_tag_group)
+                .put(tag_groups::update_tag_group.layer(admin_only.clone()))
+                .delete(tag_groups::delete_tag_group.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/tags/:id",
+            get(tags::get_tag)
+                .put(tags::update_tag.layer(writer_only.clone()))
+                .delete(tags::delete_tag.layer(admin_only.clone())),
+        )
         // 工单路由
-        .route("/api/v1/tickets", get(handlers::list_tickets))
-        .route("/api/v1/tickets", post(handlers::create_ticket))
-        .route("/api/v1/tickets/:id", get(handlers::get_ticket))
-        .route("/api/v1/tickets/:id", put(handlers::update_ticket))
-        .route("/api/v1/tickets/:id", delete(handlers::delete_ticket))
+        .route(
+            "/api/v1/tickets",
+            get(tickets::list_tickets).post(tickets::create_ticket.layer(writer_only.clone())),
+        )
+        .route("/api/v1/tickets/search", get(tickets::search_tickets))
+        .route("/api/v1/tickets/stats", get(tickets::get_ticket_stats))
+        .route(
+            "/api/v1/tickets/bulk/status",
+            put(tickets::bulk_update_status.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/tickets/:id",
+            get(tickets::get_ticket)
+                .put(tickets::update_ticket.layer(writer_only.clone()))
+                .delete(tickets::delete_ticket.layer(writer_only.clone())),
+        )
+        .route(
+            "/api/v1/tickets/:id/reopen",
+            post(tickets::reopen_ticket.layer(writer_only.clone())),
+        )
+        .route(
+            "/api/v1/tickets/:id/restore",
+            post(tickets::restore_ticket.layer(writer_only.clone())),
+        )
+        .route(
+            "/api/v1/tickets/:id/history",
+            get(tickets::get_ticket_history),
+        )
+        // 工单关注者
+        .route(
+            "/api/v1/tickets/:id/watchers",
+            get(notifications::list_ticket_watchers),
+        )
+        .route(
+            "/api/v1/tickets/:id/watch",
+            post(notifications::watch_ticket).delete(notifications::unwatch_ticket),
+        )
+        // 工单协作通道（WebSocket）
+        .route("/api/v1/tickets/:id/ws", get(collaboration::ticket_socket))
+        // 视图路由
+        .route(
+            "/api/v1/views",
+            get(saved_views::list_views).post(saved_views::create_view),
+        )
+        .route(
+            "/api/v1/views/:id",
+            get(saved_views::get_view)
+                .put(saved_views::update_view)
+                .delete(saved_views::delete_view),
+        )
+        // 回收站路由
+        .route("/api/v1/trash", get(tickets::list_trash))
+        // 工单标签路由
+        .route(
+            "/api/v1/tickets/:id/tags",
+            get(tags::get_ticket_tags).put(tags::set_ticket_tags.layer(writer_only.clone())),
+        )
+        .route(
+            "/api/v1/tickets/:id/tags/:tag_id",
+            post(tags::add_ticket_tag.layer(writer_only.clone()))
+                .delete(tags::remove_ticket_tag.layer(writer_only.clone())),
+        )
+        // 评论路由
+        .route(
+            "/api/v1/tickets/:id/comments",
+            get(comments::list_comments).post(comments::create_comment.layer(writer_only.clone())),
+        )
+        .route(
+            "/api/v1/tickets/:id/comments/:comment_id",
+            get(comments::get_comment)
+                .put(comments::update_comment.layer(writer_only.clone()))
+                .delete(comments::delete_comment.layer(writer_only.clone())),
+        )
+        // 附件路由
+        .route(
+            "/api/v1/tickets/:id/attachments",
+            get(attachments::list_ticket_attachments).post(
+                attachments::upload_ticket_attachments
+                    .layer(upload_limit)
+                    .layer(writer_only.clone()),
+            ),
+        )
+        .route(
+            "/api/v1/tickets/:id/comments/:comment_id/attachments",
+            get(attachments::list_comment_attachments).post(
+                attachments::upload_comment_attachments
+                    .layer(upload_limit)
+                    .layer(writer_only.clone()),
+            ),
+        )
+        .route(
+            "/api/v1/attachments/:id",
+            delete(attachments::delete_attachment.layer(writer_only.clone())),
+        )
+        .route(
+            "/api/v1/attachments/:id/download",
+            get(attachments::download_attachment),
+        )
+        // 用户路由
+        .route(
+            "/api/v1/users",
+            get(users::list_users).post(users::create_user.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/users/:id",
+            get(users::get_user).put(users::update_user.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/users/:id/deactivate",
+            post(users::deactivate_user.layer(admin_only.clone())),
+        )
+        // 工作流路由
+        .route(
+            "/api/v1/workflows",
+            get(workflows::list_workflows)
+                .post(workflows::create_workflow.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/workflows/:id",
+            get(workflows::get_workflow)
+                .put(workflows::update_workflow.layer(admin_only.clone()))
+                .delete(workflows::delete_workflow.layer(admin_only.clone())),
+        )
+        // 通知路由
+        .route(
+            "/api/v1/notifications",
+            get(notifications::list_notifications),
+        )
+        .route(
+            "/api/v1/notifications/preferences",
+            get(notifications::get_notification_preferences)
+                .put(notifications::update_notification_preferences),
+        )
+        // 实时事件流（SSE）
+        .route("/api/v1/events", get(events::stream_events))
+        // Webhook 路由（仅管理员）
+        .route(
+            "/api/v1/webhooks",
+            get(webhooks::list_webhooks.layer(admin_only.clone()))
+                .post(webhooks::create_webhook.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/webhooks/events",
+            get(webhooks::list_webhook_events.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/webhooks/:id",
+            get(webhooks::get_webhook.layer(admin_only.clone()))
+                .put(webhooks::update_webhook.layer(admin_only.clone()))
+                .delete(webhooks::delete_webhook.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/webhooks/:id/deliveries",
+            get(webhooks::list_webhook_deliveries.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/webhooks/:id/deliveries/:delivery_id",
+            get(webhooks::get_webhook_delivery.layer(admin_only.clone())),
+        )
+        .route(
+            "/api/v1/webhooks/:id/deliveries/:delivery_id/retry",
+            post(webhooks::retry_webhook_delivery.layer(admin_only.clone())),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ));
+
+    public
+        .merge(protected)
         .layer(CorsLayer::permissive())
-        .layer(axum::Extension(pool))
-}
\ No newline at end of file
+        .with_state(state)
+}
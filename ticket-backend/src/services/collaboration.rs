@@ -0,0 +1,324 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::extract::ws::{Message, WebSocket};
+use futures_util::{SinkExt, StreamExt};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    time::Instant,
+};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        CollaborationClientMessage, CollaborationParticipant, CollaborationServerMessage,
+        StreamEvent, User,
+    },
+    state::AppState,
+};
+
+// 每个房间最多缓冲的消息数，客户端处理过慢时丢弃较早的消息
+const ROOM_CAPACITY: usize = 256;
+
+// 进程内协作中心：按工单 ID 划分房间，记录在线参与者并在房间内广播消息
+#[derive(Clone, Default)]
+pub struct CollaborationHub {
+    rooms: Arc<Mutex<HashMap<Uuid, Room>>>,
+}
+
+struct Room {
+    sender: broadcast::Sender<RoomMessage>,
+    participants: Vec<CollaborationParticipant>,
+}
+
+// 房间内广播的消息，只序列化一次；不会发回给产生消息的连接
+#[derive(Clone)]
+struct RoomMessage {
+    origin: Uuid,
+    text: Arc<str>,
+}
+
+impl Room {
+    fn broadcast(&self, origin: Uuid, message: &CollaborationServerMessage) {
+        if let Ok(text) = serde_json::to_string(message) {
+            // 没有其他订阅者时发送失败，忽略即可
+            let _ = self.sender.send(RoomMessage {
+                origin,
+                text: text.into(),
+            });
+        }
+    }
+}
+
+impl CollaborationHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 当前在线的参与者（按加入顺序）
+    pub fn participants(&self, ticket_id: Uuid) -> Vec<CollaborationParticipant> {
+        self.rooms
+            .lock()
+            .unwrap()
+            .get(&ticket_id)
+            .map(|room| room.participants.clone())
+            .unwrap_or_default()
+    }
+
+    // 加入房间并通知其他参与者，返回房间消息的订阅端；离开由返回的 Membership 在 drop 时完成
+    fn join(
+        &self,
+        ticket_id: Uuid,
+        participant: CollaborationParticipant,
+    ) -> (Membership, broadcast::Receiver<RoomMessage>) {
+        let mut rooms = self.rooms.lock().unwrap();
+        let room = rooms.entry(ticket_id).or_insert_with(|| Room {
+            sender: broadcast::channel(ROOM_CAPACITY).0,
+            participants: Vec::new(),
+        });
+
+        let receiver = room.sender.subscribe();
+        let connection_id = participant.connection_id;
+        room.participants.push(participant.clone());
+        room.broadcast(
+            connection_id,
+            &CollaborationServerMessage::Join { participant },
+        );
+
+        let membership = Membership {
+            hub: self.clone(),
+            ticket_id,
+            connection_id,
+        };
+        (membership, receiver)
+    }
+
+    fn leave(&self, ticket_id: Uuid, connection_id: Uuid) {
+        let mut rooms = self.rooms.lock().unwrap();
+        let Some(room) = rooms.get_mut(&ticket_id) else {
+            return;
+        };
+
+        if let Some(index) = room
+            .participants
+            .iter()
+            .position(|participant| participant.connection_id == connection_id)
+        {
+            let participant = room.participants.remove(index);
+            room.broadcast(
+                connection_id,
+                &CollaborationServerMessage::Leave { participant },
+            );
+        }
+
+        if room.participants.is_empty() {
+            rooms.remove(&ticket_id);
+        }
+    }
+
+    // 更新输入状态，有变化时通知其他参与者
+    fn set_typing(&self, ticket_id: Uuid, connection_id: Uuid, typing: bool) {
+        let mut rooms = self.rooms.lock().unwrap();
+        let Some(room) = rooms.get_mut(&ticket_id) else {
+            return;
+        };
+        let Some(participant) = room
+            .participants
+            .iter_mut()
+            .find(|participant| participant.connection_id == connection_id)
+        else {
+            return;
+        };
+
+        if participant.typing != typing {
+            participant.typing = typing;
+            let participant = participant.clone();
+            room.broadcast(
+                connection_id,
+                &CollaborationServerMessage::Typing { participant },
+            );
+        }
+    }
+}
+
+// 连接在房间中的成员身份，drop 时离开房间（连接正常关闭、出错或心跳超时都会走到这里）
+struct Membership {
+    hub: CollaborationHub,
+    ticket_id: Uuid,
+    connection_id: Uuid,
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        self.hub.leave(self.ticket_id, self.connection_id);
+    }
+}
+
+// 处理一个协作连接：加入工单房间，转发房间消息与工单字段变更，并定期发送 Ping 检测连接是否存活
+pub async fn run_session(state: AppState, ticket_id: Uuid, user: User, socket: WebSocket) {
+    let config = &state.config.collaboration;
+    let heartbeat_interval = config
+        .heartbeat_interval
+        .to_std()
+        .unwrap_or(Duration::from_secs(30));
+    let heartbeat_timeout = config
+        .heartbeat_timeout
+        .to_std()
+        .unwrap_or(Duration::from_secs(90));
+
+    let connection_id = Uuid::new_v4();
+    let participant = CollaborationParticipant {
+        connection_id,
+        user_id: user.id,
+        name: user.name,
+        avatar_color: user.avatar_color,
+        typing: false,
+        joined_at: chrono::Utc::now(),
+    };
+
+    // 先订阅事件流再加入房间，避免错过加入后保存的修改
+    let mut events = state.events.subscribe();
+    let (membership, mut room) = state.collaboration.join(ticket_id, participant);
+    let (mut sink, mut stream) = socket.split();
+
+    let presence = CollaborationServerMessage::Presence {
+        connection_id,
+        participants: state.collaboration.participants(ticket_id),
+    };
+    if send(&mut sink, &presence).await.is_err() {
+        return;
+    }
+
+    let mut heartbeat =
+        tokio::time::interval_at(Instant::now() + heartbeat_interval, heartbeat_interval);
+    let mut last_seen = Instant::now();
+
+    loop {
+        tokio::select! {
+            message = stream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Ping 由底层自动回复 Pong，这里只记录活跃时间
+                    Some(Ok(_)) => {
+                        last_seen = Instant::now();
+                        continue;
+                    }
+                };
+                last_seen = Instant::now();
+
+                let reply = match serde_json::from_str::<CollaborationClientMessage>(&text) {
+                    Ok(CollaborationClientMessage::Presence) => {
+                        Some(CollaborationServerMessage::Presence {
+                            connection_id,
+                            participants: state.collaboration.participants(ticket_id),
+                        })
+                    }
+                    Ok(CollaborationClientMessage::Typing { typing }) => {
+                        state.collaboration.set_typing(ticket_id, connection_id, typing);
+                        None
+                    }
+                    Ok(CollaborationClientMessage::Leave) => {
+                        let _ = sink.send(Message::Close(None)).await;
+                        break;
+                    }
+                    Err(e) => Some(CollaborationServerMessage::Error {
+                        message: format!("无法识别的消息: {}", e),
+                    }),
+                };
+                if let Some(reply) = reply {
+                    if send(&mut sink, &reply).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            message = room.recv() => match message {
+                Ok(message) if message.origin == connection_id => {}
+                Ok(message) => {
+                    if sink.send(Message::Text(message.text.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if send_all(&mut sink, &field_changes(&event, ticket_id)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > heartbeat_timeout {
+                    info!("协作连接 {} 心跳超时，已断开", connection_id);
+                    break;
+                }
+                if sink.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    drop(membership);
+}
+
+async fn send<S>(sink: &mut S, message: &CollaborationServerMessage) -> Result<(), ()>
+where
+    S: SinkExt<Message> + Unpin,
+{
+    let text = serde_json::to_string(message).map_err(|_| ())?;
+    sink.send(Message::Text(text)).await.map_err(|_| ())
+}
+
+async fn send_all<S>(sink: &mut S, messages: &[CollaborationServerMessage]) -> Result<(), ()>
+where
+    S: SinkExt<Message> + Unpin,
+{
+    for message in messages {
+        send(sink, message).await?;
+    }
+    Ok(())
+}
+
+// 将实时事件流中本工单的字段变更转换为协作消息，一次修改的每个字段各对应一条
+fn field_changes(event: &StreamEvent, ticket_id: Uuid) -> Vec<CollaborationServerMessage> {
+    if !matches!(
+        event.event.as_str(),
+        "ticket.updated" | "ticket.status_changed"
+    ) {
+        return Vec::new();
+    }
+
+    let data = &event.payload["data"];
+    let is_ticket = data["ticket"]["id"]
+        .as_str()
+        .and_then(|id| id.parse::<Uuid>().ok())
+        .is_some_and(|id| id == ticket_id);
+    if !is_ticket {
+        return Vec::new();
+    }
+
+    data["changes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|change| {
+            Some(CollaborationServerMessage::FieldChanged {
+                field: change["field"].as_str()?.to_string(),
+                old_value: change["old_value"].clone(),
+                new_value: change["new_value"].clone(),
+                actor_id: change["actor_id"].as_str().and_then(|id| id.parse().ok()),
+                occurred_at: event.created_at,
+                ticket: data["ticket"].clone(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Duration,
+};
+
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+use crate::{
+    config::EventStreamConfig,
+    database::DbPool,
+    error::AppError,
+    models::StreamEvent,
+    repositories::{stream_events::STREAM_EVENTS_CHANNEL, StreamEventRepository},
+};
+
+// 每个订阅者最多缓冲的事件数，落后更多时会收到 Lagged 并需要重新同步
+const HUB_CAPACITY: usize = 1024;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+const CATCH_UP_BATCH: i64 = 500;
+
+// 进程内事件分发：监听任务收到数据库通知后广播给本实例的所有 SSE 连接
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<StreamEvent>,
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(HUB_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StreamEvent> {
+        self.sender.subscribe()
+    }
+
+    fn broadcast(&self, event: StreamEvent) {
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 启动事件流后台任务：
+// 监听 stream_events 通知并广播到本实例，连接断开重连后补发期间遗漏的事件；
+// 定期清理超过保留期的事件
+pub fn spawn_event_listener(pool: DbPool, hub: EventHub, config: EventStreamConfig) {
+    let repository = StreamEventRepository::new(pool.clone());
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(PRUNE_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+
+            match repository.prune(config.retention).await {
+                Ok(count) if count > 0 => info!("已清理 {} 条过期的实时事件", count),
+                Ok(_) => {}
+                Err(e) => error!("清理实时事件失败: {}", e),
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let repository = StreamEventRepository::new(pool.clone());
+
+        // 从启动时的最新位置开始，之前的事件只通过 Last-Event-ID 补发
+        let mut last_id = loop {
+            match repository.latest_id().await {
+                Ok(id) => break id,
+                Err(e) => {
+                    error!("获取实时事件位置失败: {}", e);
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        };
+
+        let mut recent = RecentIds::default();
+
+        loop {
+            let mut listener = match connect_listener(&pool).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!("实时事件监听连接失败: {}", e);
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            };
+
+            // 开始监听后补发未收到通知的事件（首次连接前及断线期间写入的）
+            if let Err(e) = catch_up(&repository, &hub, &mut last_id, &mut recent).await {
+                error!("补发实时事件失败: {}", e);
+            }
+
+            loop {
+                match listener.try_recv().await {
+                    Ok(Some(notification)) => {
+                        let Ok(id) = notification.payload().parse::<i64>() else {
+                            warn!("忽略无效的实时事件通知: {}", notification.payload());
+                            continue;
+                        };
+                        // 事务提交顺序可能与 id 顺序不同，只跳过已广播过的事件
+                        if recent.contains(id) {
+                            continue;
+                        }
+                        match repository.get(id).await {
+                            Ok(Some(event)) => {
+                                last_id = last_id.max(id);
+                                recent.insert(id);
+                                hub.broadcast(event);
+                            }
+                            Ok(None) => {}
+                            Err(e) => error!("读取实时事件 {} 失败: {}", id, e),
+                        }
+                    }
+                    // 连接已断开，重新建立连接并补发
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("实时事件监听失败: {}", e);
+                        break;
+                    }
+                }
+            }
+
+            warn!("实时事件监听连接断开，正在重连");
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+async fn connect_listener(pool: &DbPool) -> Result<PgListener, sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(STREAM_EVENTS_CHANNEL).await?;
+    Ok(listener)
+}
+
+async fn catch_up(
+    repository: &StreamEventRepository,
+    hub: &EventHub,
+    last_id: &mut i64,
+    recent: &mut RecentIds,
+) -> Result<(), AppError> {
+    loop {
+        let events = repository.list_after(*last_id, CATCH_UP_BATCH).await?;
+        let done = events.len() < CATCH_UP_BATCH as usize;
+        for event in events {
+            *last_id = event.id;
+            if recent.insert(event.id) {
+                hub.broadcast(event);
+            }
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+// 最近广播过的事件 id，用于去重补发与通知重复的事件
+#[derive(Default)]
+struct RecentIds {
+    order: VecDeque<i64>,
+    ids: HashSet<i64>,
+}
+
+impl RecentIds {
+    fn contains(&self, id: i64) -> bool {
+        self.ids.contains(&id)
+    }
+
+    // 返回是否为新 id
+    fn insert(&mut self, id: i64) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > HUB_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
@@ -1,2 +1,8 @@
-// 服务层模块（如果需要的话）
-// 在这个简单的实现中，我们直接使用Repository层
\ No newline at end of file
+// 服务层模块：后台任务等不属于单个请求的逻辑
+// 请求处理仍直接使用 Repository 层
+
+pub mod collaboration;
+pub mod event_stream;
+pub mod notifications;
+pub mod trash;
+pub mod webhooks;
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use futures_util::future::join_all;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    config::{NotificationConfig, SmtpConfig, SmtpSecurity},
+    database::DbPool,
+    repositories::notifications::{DueNotification, NotificationRepository},
+    utils::email_templates::{self, RenderedEmail},
+};
+
+type Mailer = AsyncSmtpTransport<Tokio1Executor>;
+
+// 每轮最多发送的即时通知数
+const BATCH_SIZE: i64 = 20;
+// 每轮最多发送汇总邮件的用户数
+const DIGEST_BATCH_USERS: i64 = 20;
+// 重试间隔上限
+const MAX_RETRY_DELAY_SECONDS: i64 = 6 * 60 * 60;
+
+// 启动邮件通知发送任务：即时通知逐条发送，汇总模式的通知按用户合并为一封邮件；
+// 未配置 SMTP 时不启动，通知仍会记录
+pub fn spawn_notification_task(pool: DbPool, config: NotificationConfig) {
+    let Some(ref smtp) = config.smtp else {
+        info!("未配置 SMTP_HOST，邮件通知不会发送");
+        return;
+    };
+    let mailer = match build_mailer(smtp) {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            error!("创建 SMTP 客户端失败: {}", e);
+            return;
+        }
+    };
+    let from: Mailbox = match config.from.parse() {
+        Ok(from) => from,
+        Err(e) => {
+            error!("发件人地址无效: {}", e);
+            return;
+        }
+    };
+    let interval = config
+        .poll_interval
+        .to_std()
+        .unwrap_or(std::time::Duration::from_secs(5));
+    // 租约需长于单次发送的超时时间
+    let lease = smtp.timeout * 2 + chrono::Duration::seconds(30);
+
+    tokio::spawn(async move {
+        let repository = NotificationRepository::new(pool);
+        let sender = Sender {
+            mailer,
+            from,
+            repository: &repository,
+            config: &config,
+        };
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            let lease_until = chrono::Utc::now() + lease;
+
+            match repository.claim_due(BATCH_SIZE, lease_until).await {
+                Ok(notifications) => {
+                    join_all(notifications.iter().map(|notification| {
+                        let email = email_templates::render(
+                            notification.language,
+                            notification.kind,
+                            &notification.data,
+                            config.ticket_url.as_deref(),
+                        );
+                        sender.send(std::slice::from_ref(notification), email)
+                    }))
+                    .await;
+                }
+                Err(e) => error!("获取待发送的通知失败: {}", e),
+            }
+
+            let due_before = chrono::Utc::now() - config.digest_interval;
+            match repository
+                .claim_digests(due_before, DIGEST_BATCH_USERS, lease_until)
+                .await
+            {
+                Ok(notifications) => {
+                    let mut by_user: BTreeMap<Uuid, Vec<DueNotification>> = BTreeMap::new();
+                    for notification in notifications {
+                        by_user
+                            .entry(notification.user_id)
+                            .or_default()
+                            .push(notification);
+                    }
+
+                    join_all(by_user.values().map(|notifications| {
+                        let items: Vec<_> = notifications
+                            .iter()
+                            .map(|notification| (notification.kind, &notification.data))
+                            .collect();
+                        let email = email_templates::render_digest(
+                            notifications[0].language,
+                            &items,
+                            config.ticket_url.as_deref(),
+                        );
+                        sender.send(notifications, email)
+                    }))
+                    .await;
+                }
+                Err(e) => error!("获取待汇总的通知失败: {}", e),
+            }
+        }
+    });
+}
+
+fn build_mailer(smtp: &SmtpConfig) -> Result<Mailer, lettre::transport::smtp::Error> {
+    let builder = match smtp.security {
+        SmtpSecurity::None => Mailer::builder_dangerous(&smtp.host),
+        SmtpSecurity::StartTls => Mailer::starttls_relay(&smtp.host)?,
+        SmtpSecurity::Tls => Mailer::relay(&smtp.host)?,
+    };
+    let mut builder = builder.port(smtp.port).timeout(smtp.timeout.to_std().ok());
+
+    if let Some(ref username) = smtp.username {
+        builder = builder.credentials(Credentials::new(
+            username.clone(),
+            smtp.password.clone().unwrap_or_default(),
+        ));
+    }
+
+    Ok(builder.build())
+}
+
+struct Sender<'a> {
+    mailer: Mailer,
+    from: Mailbox,
+    repository: &'a NotificationRepository,
+    config: &'a NotificationConfig,
+}
+
+impl Sender<'_> {
+    // 发送一封邮件（单条通知或同一用户的汇总），并记录结果
+    async fn send(&self, notifications: &[DueNotification], email: RenderedEmail) {
+        let Some(recipient) = notifications.first() else {
+            return;
+        };
+        let ids: Vec<Uuid> = notifications.iter().map(|n| n.id).collect();
+
+        let result = match self.build_message(recipient, email) {
+            Ok(message) => self
+                .mailer
+                .send(message)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+
+        let recorded = match result {
+            Ok(()) => self.repository.mark_sent(&ids).await,
+            Err(e) => {
+                let attempt = notifications
+                    .iter()
+                    .map(|n| n.attempt_count)
+                    .max()
+                    .unwrap_or(0)
+                    + 1;
+                warn!(
+                    "发送通知邮件给 {} 第 {} 次尝试失败: {}",
+                    recipient.email, attempt, e
+                );
+                let retry_at = (attempt < self.config.max_attempts)
+                    .then(|| chrono::Utc::now() + retry_delay(self.config, attempt));
+                self.repository.mark_failed(&ids, &e, retry_at).await
+            }
+        };
+
+        if let Err(e) = recorded {
+            error!("记录通知发送结果失败: {}", e);
+        }
+    }
+
+    fn build_message(
+        &self,
+        recipient: &DueNotification,
+        email: RenderedEmail,
+    ) -> Result<Message, String> {
+        let to = recipient
+            .email
+            .parse()
+            .map(|address| Mailbox::new(Some(recipient.name.clone()), address))
+            .map_err(|e| format!("收件人地址无效: {}", e))?;
+
+        Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)
+            .map_err(|e| e.to_string())
+    }
+}
+
+// 第 n 次失败后的等待时间: retry_base * 2^(n-1)，不超过上限
+fn retry_delay(config: &NotificationConfig, attempt: i32) -> chrono::Duration {
+    let factor = 2i64.saturating_pow(attempt.saturating_sub(1).min(20) as u32);
+    let seconds = config
+        .retry_base
+        .num_seconds()
+        .saturating_mul(factor)
+        .min(MAX_RETRY_DELAY_SECONDS);
+    chrono::Duration::seconds(seconds)
+}
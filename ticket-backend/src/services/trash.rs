@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use tracing::{error, info};
+
+use crate::{
+    config::TrashConfig,
+    database::DbPool,
+    repositories::TicketRepository,
+    storage::{self, AttachmentStorage},
+};
+
+// 启动回收站清理任务：定期永久删除超过保留期的工单及其附件文件
+pub fn spawn_purge_task(pool: DbPool, storage: Arc<dyn AttachmentStorage>, config: TrashConfig) {
+    let interval = config
+        .purge_interval
+        .to_std()
+        .unwrap_or(std::time::Duration::from_secs(3600));
+
+    tokio::spawn(async move {
+        let repository = TicketRepository::new(pool);
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            let deleted_before = chrono::Utc::now() - config.retention;
+            match repository.purge_deleted(deleted_before).await {
+                Ok(purged) => {
+                    storage::delete_all(storage.as_ref(), &purged.attachment_keys).await;
+                    if purged.count > 0 {
+                        info!("回收站清理完成，永久删除 {} 个工单", purged.count);
+                    }
+                }
+                Err(e) => error!("回收站清理失败: {}", e),
+            }
+        }
+    });
+}
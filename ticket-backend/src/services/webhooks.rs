@@ -0,0 +1,204 @@
+use std::time::Instant;
+
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use tracing::{error, warn};
+
+use crate::{
+    config::WebhookConfig,
+    database::DbPool,
+    repositories::webhooks::{AttemptOutcome, DueDelivery, WebhookRepository},
+};
+
+// 可订阅的事件
+pub const WEBHOOK_EVENTS: &[&str] = &[
+    "ticket.created",
+    "ticket.updated",
+    "ticket.status_changed",
+    "ticket.deleted",
+    "ticket.restored",
+    "ticket.purged",
+    "ticket.tag_added",
+    "ticket.tag_removed",
+    "ticket.comment_added",
+    "ticket.comment_updated",
+    "ticket.comment_deleted",
+    "ticket.attachment_added",
+    "ticket.attachment_deleted",
+    "tag.created",
+    "tag.updated",
+    "tag.deleted",
+    "tag.merged",
+];
+
+// 每轮最多取出的投递任务数
+const BATCH_SIZE: i64 = 20;
+// 投递日志中保存的响应体长度上限（字节）
+const MAX_RESPONSE_BODY: usize = 4096;
+// 重试间隔上限
+const MAX_RETRY_DELAY_SECONDS: i64 = 6 * 60 * 60;
+
+// 生成签名密钥
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+// 签名: HMAC-SHA256(secret, "{timestamp}.{body}")，以十六进制放在 X-Webhook-Signature: sha256=...
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC 接受任意长度的密钥");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// 启动 Webhook 投递任务：定期取出到期的投递并发送，失败时按指数退避重试
+pub fn spawn_delivery_task(pool: DbPool, config: WebhookConfig) {
+    let interval = config
+        .poll_interval
+        .to_std()
+        .unwrap_or(std::time::Duration::from_secs(1));
+    let timeout = config
+        .timeout
+        .to_std()
+        .unwrap_or(std::time::Duration::from_secs(10));
+
+    tokio::spawn(async move {
+        let client = match reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent("ticket-backend-webhooks")
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => {
+                error!("创建 Webhook HTTP 客户端失败: {}", e);
+                return;
+            }
+        };
+        let repository = WebhookRepository::new(pool);
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            // 租约需长于单次请求的超时时间
+            let lease_until =
+                chrono::Utc::now() + config.timeout * 2 + chrono::Duration::seconds(30);
+            let deliveries = match repository.claim_due(BATCH_SIZE, lease_until).await {
+                Ok(deliveries) => deliveries,
+                Err(e) => {
+                    error!("获取待投递的 Webhook 失败: {}", e);
+                    continue;
+                }
+            };
+
+            join_all(
+                deliveries
+                    .iter()
+                    .map(|delivery| deliver(&client, &repository, &config, delivery)),
+            )
+            .await;
+        }
+    });
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    repository: &WebhookRepository,
+    config: &WebhookConfig,
+    delivery: &DueDelivery,
+) {
+    let outcome = send(client, delivery).await;
+
+    let attempt = delivery.attempt_count + 1;
+    let retry_at = (!outcome.succeeded() && attempt < config.max_attempts)
+        .then(|| chrono::Utc::now() + retry_delay(config, attempt));
+
+    if !outcome.succeeded() {
+        warn!(
+            "Webhook 投递 {} 第 {} 次尝试失败: {}",
+            delivery.id,
+            attempt,
+            outcome
+                .error
+                .clone()
+                .or_else(|| outcome
+                    .response_status
+                    .map(|status| format!("HTTP {}", status)))
+                .unwrap_or_default()
+        );
+    }
+
+    if let Err(e) = repository
+        .record_attempt(delivery, &outcome, retry_at)
+        .await
+    {
+        error!("记录 Webhook 投递结果失败: {}", e);
+    }
+}
+
+async fn send(client: &reqwest::Client, delivery: &DueDelivery) -> AttemptOutcome {
+    let body = serde_json::to_vec(&delivery.payload).unwrap_or_default();
+    let timestamp = chrono::Utc::now().timestamp();
+    let started = Instant::now();
+
+    let result = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Webhook-Event", &delivery.event)
+        .header("X-Webhook-Delivery", delivery.id.to_string())
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header(
+            "X-Webhook-Signature",
+            sign(&delivery.secret, timestamp, &body),
+        )
+        .body(body)
+        .send()
+        .await;
+
+    let (response_status, response_body, error) = match result {
+        Ok(mut response) => {
+            let status = i32::from(response.status().as_u16());
+            let mut body = Vec::new();
+            // 只读取日志需要的部分响应体
+            while body.len() < MAX_RESPONSE_BODY {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                    _ => break,
+                }
+            }
+            body.truncate(MAX_RESPONSE_BODY);
+            (
+                Some(status),
+                // PostgreSQL 的 TEXT 不能包含 NUL 字符
+                Some(String::from_utf8_lossy(&body).replace('\0', "")),
+                None,
+            )
+        }
+        Err(e) => (None, None, Some(e.to_string())),
+    };
+
+    AttemptOutcome {
+        response_status,
+        response_body,
+        error,
+        duration_ms: i32::try_from(started.elapsed().as_millis()).unwrap_or(i32::MAX),
+    }
+}
+
+// 第 n 次失败后的等待时间: retry_base * 2^(n-1)，不超过上限
+fn retry_delay(config: &WebhookConfig, attempt: i32) -> chrono::Duration {
+    let factor = 2i32.saturating_pow(attempt.saturating_sub(1).min(20) as u32);
+    let seconds = config
+        .retry_base
+        .num_seconds()
+        .saturating_mul(i64::from(factor))
+        .min(MAX_RETRY_DELAY_SECONDS);
+    chrono::Duration::seconds(seconds)
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    database::DbPool,
+    services::{collaboration::CollaborationHub, event_stream::EventHub},
+    storage::{self, AttachmentStorage},
+};
+
+// 应用共享状态
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: DbPool,
+    pub config: Arc<Config>,
+    pub storage: Arc<dyn AttachmentStorage>,
+    pub events: EventHub,
+    pub collaboration: CollaborationHub,
+}
+
+impl AppState {
+    pub fn new(pool: DbPool, config: Config) -> Self {
+        Self {
+            pool,
+            storage: storage::from_config(&config.attachments.storage),
+            events: EventHub::new(),
+            collaboration: CollaborationHub::new(),
+            config: Arc::new(config),
+        }
+    }
+}
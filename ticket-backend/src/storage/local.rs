@@ -0,0 +1,89 @@
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid as UuidType;
+
+use super::{AttachmentStorage, ByteStream, SpooledFile};
+use crate::error::AppError;
+
+// 本地文件系统存储，对象键即相对于根目录的路径
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    // 只接受由普通路径段组成的键，防止越出根目录
+    fn path_for(&self, key: &str) -> Result<PathBuf, AppError> {
+        let relative = Path::new(key);
+        let is_safe = !key.is_empty()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+
+        if !is_safe {
+            return Err(AppError::internal(format!("无效的附件存储键: {}", key)));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl AttachmentStorage for LocalStorage {
+    async fn put(
+        &self,
+        key: &str,
+        _content_type: &str,
+        file: &SpooledFile,
+    ) -> Result<(), AppError> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+
+        // 先复制到同目录的临时文件再重命名，避免读取到写了一半的文件
+        let temp = path.with_extension(format!("{}.tmp", UuidType::new_v4()));
+        if let Err(e) = tokio::fs::copy(file.path(), &temp).await {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(io_error(e));
+        }
+        tokio::fs::rename(&temp, &path).await.map_err(io_error)?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<ByteStream, AppError> {
+        let path = self.path_for(key)?;
+        let file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(AppError::not_found("附件内容"))
+            }
+            Err(e) => return Err(io_error(e)),
+        };
+
+        Ok(ReaderStream::new(file).boxed())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let path = self.path_for(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+}
+
+fn io_error(e: std::io::Error) -> AppError {
+    AppError::internal(format!("附件文件读写失败: {}", e))
+}
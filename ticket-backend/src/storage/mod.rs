@@ -0,0 +1,50 @@
+// 附件存储：可插拔的存储后端（本地文件系统、S3 兼容对象存储）
+// 数据库只记录附件元数据与存储键，文件内容由存储后端保存
+
+mod local;
+mod s3;
+mod spool;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+use futures_util::stream::BoxStream;
+
+use crate::{config::StorageConfig, error::AppError};
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+pub use spool::{SpoolWriter, SpooledFile};
+
+// 下载时按块返回的文件内容
+pub type ByteStream = BoxStream<'static, std::io::Result<Bytes>>;
+
+#[async_trait]
+pub trait AttachmentStorage: Send + Sync {
+    // 写入对象，已存在时覆盖；内容从暂存的临时文件流式读取
+    async fn put(&self, key: &str, content_type: &str, file: &SpooledFile) -> Result<(), AppError>;
+
+    // 以流的形式读取对象，不存在时返回 404
+    async fn get(&self, key: &str) -> Result<ByteStream, AppError>;
+
+    // 删除对象，不存在时视为成功
+    async fn delete(&self, key: &str) -> Result<(), AppError>;
+}
+
+// 根据配置创建存储后端
+pub fn from_config(config: &StorageConfig) -> Arc<dyn AttachmentStorage> {
+    match config {
+        StorageConfig::Local { root } => Arc::new(LocalStorage::new(root.clone())),
+        StorageConfig::S3(s3) => Arc::new(S3Storage::new(s3.clone())),
+    }
+}
+
+// 尽力删除一批对象：数据库记录已删除，失败只记录日志，不影响请求结果
+pub async fn delete_all(storage: &dyn AttachmentStorage, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = storage.delete(key).await {
+            tracing::warn!("删除附件对象 {} 失败: {}", key, e);
+        }
+    }
+}
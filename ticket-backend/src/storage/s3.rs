@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use tokio_util::io::ReaderStream;
+
+use super::{AttachmentStorage, ByteStream, SpooledFile};
+use crate::{config::S3Config, error::AppError};
+
+// SigV4 规定的 URI 编码：除 A-Z a-z 0-9 - _ . ~ 外全部编码
+const URI_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+// 空请求体的 SHA-256
+const EMPTY_PAYLOAD_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+// S3 兼容对象存储，请求使用 AWS Signature Version 4 签名
+pub struct S3Storage {
+    client: reqwest::Client,
+    config: S3Config,
+}
+
+impl S3Storage {
+    pub fn new(config: S3Config) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    // 路径风格的对象 URL: {endpoint}/{bucket}/{key}
+    fn object_url(&self, key: &str) -> Result<Url, AppError> {
+        let path = std::iter::once(self.config.bucket.as_str())
+            .chain(key.split('/'))
+            .map(|segment| utf8_percent_encode(segment, URI_ENCODE_SET).to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Url::parse(&format!("{}/{}", self.config.endpoint, path))
+            .map_err(|e| AppError::internal(format!("无效的对象存储地址: {}", e)))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        content_type: Option<&str>,
+        body: Option<&SpooledFile>,
+    ) -> Result<reqwest::Response, AppError> {
+        let url = self.object_url(key)?;
+        // 暂存时已计算内容摘要，无需为签名再读一遍文件
+        let payload_hash = match body {
+            Some(file) => file.sha256().to_string(),
+            None => EMPTY_PAYLOAD_HASH.to_string(),
+        };
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = self.authorization(method.as_str(), &url, &payload_hash, now);
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(reqwest::header::AUTHORIZATION, authorization);
+        if let Some(content_type) = content_type {
+            request = request.header(reqwest::header::CONTENT_TYPE, content_type);
+        }
+        if let Some(file) = body {
+            let reader = tokio::fs::File::open(file.path())
+                .await
+                .map_err(|e| AppError::internal(format!("附件临时文件读写失败: {}", e)))?;
+            request = request
+                .header(reqwest::header::CONTENT_LENGTH, file.size())
+                .body(reqwest::Body::wrap_stream(ReaderStream::new(reader)));
+        }
+
+        request
+            .send()
+            .await
+            .map_err(|e| AppError::internal(format!("对象存储请求失败: {}", e)))
+    }
+
+    // 计算 Authorization 头，签名 host、x-amz-content-sha256 与 x-amz-date
+    fn authorization(
+        &self,
+        method: &str,
+        url: &Url,
+        payload_hash: &str,
+        now: DateTime<Utc>,
+    ) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.config.region);
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{method}\n{path}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{signed_headers}\n{payload_hash}",
+            path = url.path(),
+        );
+
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [
+            date.as_str(),
+            self.config.region.as_str(),
+            "s3",
+            "aws4_request",
+        ]
+        .iter()
+        .fold(
+            format!("AWS4{}", self.config.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.config.access_key_id, scope, signed_headers, signature
+        )
+    }
+}
+
+#[async_trait]
+impl AttachmentStorage for S3Storage {
+    async fn put(&self, key: &str, content_type: &str, file: &SpooledFile) -> Result<(), AppError> {
+        let response = self
+            .send(Method::PUT, key, Some(content_type), Some(file))
+            .await?;
+        ensure_success(response.status())
+    }
+
+    async fn get(&self, key: &str) -> Result<ByteStream, AppError> {
+        let response = self.send(Method::GET, key, None, None).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(AppError::not_found("附件内容"));
+        }
+        ensure_success(response.status())?;
+
+        Ok(response
+            .bytes_stream()
+            .map_err(std::io::Error::other)
+            .boxed())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AppError> {
+        let response = self.send(Method::DELETE, key, None, None).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        ensure_success(response.status())
+    }
+}
+
+fn ensure_success(status: StatusCode) -> Result<(), AppError> {
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(AppError::internal(format!(
+            "对象存储返回错误状态: {}",
+            status
+        )))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+use tempfile::TempPath;
+use tokio::io::AsyncWriteExt;
+
+use crate::error::AppError;
+
+// 上传内容暂存的本地临时文件，写入存储后端前已得到大小与 SHA-256；
+// 临时文件在值被丢弃时删除
+pub struct SpooledFile {
+    path: TempPath,
+    size: u64,
+    sha256: String,
+}
+
+impl SpooledFile {
+    pub async fn from_bytes(data: &[u8]) -> Result<Self, AppError> {
+        let mut writer = SpoolWriter::new()?;
+        writer.write(data).await?;
+        writer.finish().await
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+}
+
+// 逐块写入临时文件并同时计算 SHA-256，避免整个文件驻留内存
+pub struct SpoolWriter {
+    file: tokio::fs::File,
+    path: TempPath,
+    size: u64,
+    hasher: Sha256,
+}
+
+impl SpoolWriter {
+    pub fn new() -> Result<Self, AppError> {
+        let (file, path) = tempfile::NamedTempFile::new()
+            .map_err(io_error)?
+            .into_parts();
+
+        Ok(Self {
+            file: tokio::fs::File::from_std(file),
+            path,
+            size: 0,
+            hasher: Sha256::new(),
+        })
+    }
+
+    // 已写入的字节数
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<(), AppError> {
+        self.file.write_all(chunk).await.map_err(io_error)?;
+        self.hasher.update(chunk);
+        self.size += chunk.len() as u64;
+        Ok(())
+    }
+
+    pub async fn finish(mut self) -> Result<SpooledFile, AppError> {
+        self.file.flush().await.map_err(io_error)?;
+
+        Ok(SpooledFile {
+            path: self.path,
+            size: self.size,
+            sha256: hex::encode(self.hasher.finalize()),
+        })
+    }
+}
+
+fn io_error(e: std::io::Error) -> AppError {
+    AppError::internal(format!("附件临时文件读写失败: {}", e))
+}
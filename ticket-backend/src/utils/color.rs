@@ -0,0 +1,59 @@
+// 标签颜色：#RRGGBB 校验与归一化、自动分配调色板颜色、根据对比度计算文字颜色
+
+use std::borrow::Cow;
+
+use validator::ValidationError;
+
+// 未指定颜色时按顺序分配，优先选择使用次数最少的颜色
+pub const PALETTE: [&str; 12] = [
+    "#3B82F6", "#EF4444", "#22C55E", "#F59E0B", "#8B5CF6", "#EC4899", "#14B8A6", "#F97316",
+    "#6366F1", "#84CC16", "#06B6D4", "#D946EF",
+];
+
+pub const TEXT_BLACK: &str = "#000000";
+pub const TEXT_WHITE: &str = "#FFFFFF";
+
+// 是否为 #RRGGBB 格式（不区分大小写）
+pub fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// 统一为大写形式存储
+pub fn normalize(color: &str) -> String {
+    color.trim().to_ascii_uppercase()
+}
+
+// validator 自定义校验
+pub fn validate_hex_color(color: &str) -> Result<(), ValidationError> {
+    if is_hex_color(color.trim()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("hex_color")
+            .with_message(Cow::Borrowed("颜色必须是 #RRGGBB 格式的HEX值")))
+    }
+}
+
+// 按 WCAG 相对亮度选择与背景对比度更高的文字颜色（黑或白）
+pub fn text_color(background: &str) -> &'static str {
+    if !is_hex_color(background) {
+        return TEXT_BLACK;
+    }
+
+    let channel = |index: usize| {
+        let value = u8::from_str_radix(&background[index..index + 2], 16).unwrap_or(0);
+        let srgb = f64::from(value) / 255.0;
+        if srgb <= 0.03928 {
+            srgb / 12.92
+        } else {
+            ((srgb + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let luminance = 0.2126 * channel(1) + 0.7152 * channel(3) + 0.0722 * channel(5);
+
+    // 与黑色的对比度 (L + 0.05) / 0.05，与白色的对比度 1.05 / (L + 0.05)
+    if (luminance + 0.05) / 0.05 >= 1.05 / (luminance + 0.05) {
+        TEXT_BLACK
+    } else {
+        TEXT_WHITE
+    }
+}
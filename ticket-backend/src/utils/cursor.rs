@@ -0,0 +1,19 @@
+// 不透明的分页游标：JSON 经 URL 安全的 Base64 编码，客户端只需原样回传
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::AppError;
+
+pub fn encode<T: Serialize>(value: &T) -> String {
+    let json = serde_json::to_vec(value).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+pub fn decode<T: DeserializeOwned>(cursor: &str) -> Result<T, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or_else(|| AppError::bad_request("无效的分页游标"))
+}
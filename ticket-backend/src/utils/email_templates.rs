@@ -0,0 +1,167 @@
+// 通知邮件模板（中文、英文），根据通知生成时保存的快照渲染纯文本邮件
+
+use serde_json::Value;
+
+use crate::models::{NotificationKind, NotificationLanguage};
+
+// 评论摘录的最大长度（字符数）
+pub const EXCERPT_LENGTH: usize = 200;
+
+pub struct RenderedEmail {
+    pub subject: String,
+    pub body: String,
+}
+
+// 单条通知的邮件
+pub fn render(
+    language: NotificationLanguage,
+    kind: NotificationKind,
+    data: &Value,
+    ticket_url: Option<&str>,
+) -> RenderedEmail {
+    let summary = summary(language, kind, data);
+    let mut body = vec![
+        greeting(language).to_string(),
+        String::new(),
+        summary.clone(),
+    ];
+
+    if let Some(excerpt) = data["comment"]["excerpt"].as_str() {
+        body.push(String::new());
+        body.extend(excerpt.lines().map(|line| format!("> {}", line)));
+    }
+    if let Some(link) = ticket_link(data, ticket_url) {
+        body.push(String::new());
+        body.push(match language {
+            NotificationLanguage::Zh => format!("查看工单：{}", link),
+            NotificationLanguage::En => format!("View ticket: {}", link),
+        });
+    }
+    body.push(String::new());
+    body.push(footer(language).to_string());
+
+    RenderedEmail {
+        subject: format!("{} {}", subject_prefix(language), summary),
+        body: body.join("\n"),
+    }
+}
+
+// 汇总邮件：按时间顺序列出期间的所有通知
+pub fn render_digest(
+    language: NotificationLanguage,
+    items: &[(NotificationKind, &Value)],
+    ticket_url: Option<&str>,
+) -> RenderedEmail {
+    let subject = match language {
+        NotificationLanguage::Zh => {
+            format!("{} 你有 {} 条新通知", subject_prefix(language), items.len())
+        }
+        NotificationLanguage::En => format!(
+            "{} You have {} new notification{}",
+            subject_prefix(language),
+            items.len(),
+            if items.len() == 1 { "" } else { "s" }
+        ),
+    };
+
+    let mut body = vec![greeting(language).to_string(), String::new()];
+    body.push(match language {
+        NotificationLanguage::Zh => "以下是最近的工单动态：".to_string(),
+        NotificationLanguage::En => "Here is what happened on your tickets:".to_string(),
+    });
+    body.push(String::new());
+    for (kind, data) in items {
+        body.push(format!("- {}", summary(language, *kind, data)));
+        if let Some(link) = ticket_link(data, ticket_url) {
+            body.push(format!("  {}", link));
+        }
+    }
+    body.push(String::new());
+    body.push(footer(language).to_string());
+
+    RenderedEmail {
+        subject,
+        body: body.join("\n"),
+    }
+}
+
+// 截取评论摘录
+pub fn excerpt(content: &str) -> String {
+    let content = content.trim();
+    match content.char_indices().nth(EXCERPT_LENGTH) {
+        Some((index, _)) => format!("{}…", &content[..index]),
+        None => content.to_string(),
+    }
+}
+
+// 一句话描述通知内容，用于主题和汇总列表
+fn summary(language: NotificationLanguage, kind: NotificationKind, data: &Value) -> String {
+    let title = data["ticket"]["title"].as_str().unwrap_or_default();
+    let actor = data["actor"]["name"].as_str();
+    let old_status = data["old_status"].as_str().unwrap_or_default();
+    let new_status = data["new_status"].as_str().unwrap_or_default();
+
+    match language {
+        NotificationLanguage::Zh => {
+            let actor = actor.unwrap_or("系统");
+            match kind {
+                NotificationKind::Assigned => format!("{} 将工单「{}」指派给了你", actor, title),
+                NotificationKind::StatusChanged => format!(
+                    "{} 将工单「{}」的状态从 {} 改为 {}",
+                    actor, title, old_status, new_status
+                ),
+                NotificationKind::CommentAdded => format!("{} 评论了工单「{}」", actor, title),
+                NotificationKind::Mentioned => {
+                    format!("{} 在工单「{}」的评论中提到了你", actor, title)
+                }
+            }
+        }
+        NotificationLanguage::En => {
+            let actor = actor.unwrap_or("System");
+            match kind {
+                NotificationKind::Assigned => {
+                    format!("{} assigned the ticket \"{}\" to you", actor, title)
+                }
+                NotificationKind::StatusChanged => format!(
+                    "{} changed the status of \"{}\" from {} to {}",
+                    actor, title, old_status, new_status
+                ),
+                NotificationKind::CommentAdded => {
+                    format!("{} commented on \"{}\"", actor, title)
+                }
+                NotificationKind::Mentioned => {
+                    format!("{} mentioned you in a comment on \"{}\"", actor, title)
+                }
+            }
+        }
+    }
+}
+
+// 链接模板中的 {id} 替换为工单 ID
+fn ticket_link(data: &Value, ticket_url: Option<&str>) -> Option<String> {
+    let id = data["ticket"]["id"].as_str()?;
+    Some(ticket_url?.replace("{id}", id))
+}
+
+fn subject_prefix(language: NotificationLanguage) -> &'static str {
+    match language {
+        NotificationLanguage::Zh => "[工单]",
+        NotificationLanguage::En => "[Ticket]",
+    }
+}
+
+fn greeting(language: NotificationLanguage) -> &'static str {
+    match language {
+        NotificationLanguage::Zh => "你好，",
+        NotificationLanguage::En => "Hello,",
+    }
+}
+
+fn footer(language: NotificationLanguage) -> &'static str {
+    match language {
+        NotificationLanguage::Zh => "—— 工单系统（可在通知设置中修改邮件偏好）",
+        NotificationLanguage::En => {
+            "-- Ticket System (you can change your email preferences in notification settings)"
+        }
+    }
+}
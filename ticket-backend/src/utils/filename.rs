@@ -0,0 +1,59 @@
+// 附件文件名：上传时清理客户端提供的文件名，下载时生成 Content-Disposition
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+// 文件名最大长度（字符数，与 attachments.filename 列一致）
+pub const MAX_FILENAME_LENGTH: usize = 255;
+
+// RFC 5987 attr-char 之外的字符都需要编码
+const ATTR_CHAR_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+// 去掉路径部分与控制字符，过长时截断；结果为空时使用 "file"
+pub fn sanitize(filename: &str) -> String {
+    let name = filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>();
+    let name = name.trim().trim_start_matches('.').trim();
+
+    if name.is_empty() {
+        return "file".to_string();
+    }
+
+    name.chars().take(MAX_FILENAME_LENGTH).collect()
+}
+
+// attachment; filename="ASCII 兜底名"; filename*=UTF-8''百分号编码的原名
+pub fn content_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| {
+            if c == ' ' || (c.is_ascii_graphic() && c != '"' && c != '\\') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        utf8_percent_encode(filename, ATTR_CHAR_ENCODE_SET)
+    )
+}
@@ -0,0 +1,28 @@
+// 评论中的 @提及：以 @ 加用户邮箱的形式提及，如 "@alice@example.com 请看一下"
+
+// 提取评论中提及的邮箱（小写、去重）
+pub fn extract_mentions(content: &str) -> Vec<String> {
+    let mut emails: Vec<String> = content
+        .split(|c: char| c.is_whitespace() || "，。；：！？、（）()<>[]{}\"'".contains(c))
+        .filter_map(|word| word.strip_prefix('@'))
+        .map(|email| email.trim_end_matches(|c: char| ".,;:!?".contains(c)))
+        .filter(|email| is_email_like(email))
+        .map(str::to_lowercase)
+        .collect();
+
+    emails.sort();
+    emails.dedup();
+    emails
+}
+
+fn is_email_like(value: &str) -> bool {
+    match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.contains('@')
+        }
+        None => false,
+    }
+}
@@ -1 +1,31 @@
-// 工具模块
\ No newline at end of file
+// 通用工具函数
+
+pub mod color;
+pub mod cursor;
+pub mod email_templates;
+pub mod filename;
+pub mod mentions;
+pub mod query_language;
+pub mod search;
+pub mod sort;
+
+// 默认每页数量
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+// 每页最大数量
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+// 将 page/limit/offset 参数解析为 (limit, offset)，page 优先于 offset；
+// 超大的 page 按饱和乘法计算，避免溢出
+pub fn resolve_pagination(
+    page: Option<i64>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> (i64, i64) {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let offset = match page {
+        Some(page) => (page.max(1) - 1).saturating_mul(limit),
+        None => offset.unwrap_or(0).max(0),
+    };
+
+    (limit, offset)
+}
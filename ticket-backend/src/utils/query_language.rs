@@ -0,0 +1,433 @@
+// 工单查询语言：将形如
+// `status:open,in_progress priority>=high tag:backend -tag:wontfix assignee:me created>2026-01-01 "login bug"`
+// 的查询解析为类型化的语法树，由 TicketRepository 编译为参数化 SQL
+//
+// 语法：
+// - 空白分隔的条件之间为 AND，`-` 前缀表示取反
+// - `字段:值1,值2` 匹配任一取值，含空白的值用双引号包裹
+// - priority 与日期字段支持 `>`、`>=`、`<`、`<=`
+// - 其余词与 "双引号短语" 作为全文检索关键词，词尾 `*` 表示前缀匹配
+
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{Priority, StatusCategory},
+    utils::search::SearchTerm,
+};
+
+const FIELDS: [&str; 9] = [
+    "status", "category", "priority", "tag", "assignee", "reporter", "created", "updated",
+    "resolved",
+];
+
+// 解析错误，position 为出错词在查询中的字符位置（从 1 开始）
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("查询语句第 {position} 个字符处的 `{token}` 有误: {message}")]
+pub struct QueryError {
+    pub position: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl From<QueryError> for AppError {
+    fn from(err: QueryError) -> Self {
+        AppError::bad_request(err.to_string())
+    }
+}
+
+// 条件之间为 AND
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TicketFilter {
+    pub clauses: Vec<Clause>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clause {
+    pub negated: bool,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Status(Vec<String>),
+    Category(Vec<StatusCategory>),
+    // 比较运算在解析时展开为满足条件的优先级集合
+    Priority(Vec<Priority>),
+    // 标签名称，匹配任一
+    Tag(Vec<String>),
+    Assignee(Vec<UserRef>),
+    Reporter(Vec<UserRef>),
+    Date {
+        field: DateField,
+        bounds: Vec<(Comparison, DateTime<Utc>)>,
+    },
+    Text(SearchTerm),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserRef {
+    Id(Uuid),
+    Email(String),
+    // `none`：未设置
+    Nobody,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Created,
+    Updated,
+    Resolved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Comparison {
+    pub fn as_operator(&self) -> &'static str {
+        match self {
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+        }
+    }
+}
+
+// 字段与取值之间的运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Is,
+    Compare(Comparison),
+}
+
+// 查询中的一个词及其位置
+struct Token<'a> {
+    position: usize,
+    text: &'a str,
+}
+
+impl Token<'_> {
+    fn error(&self, message: impl Into<String>) -> QueryError {
+        QueryError {
+            position: self.position,
+            token: self.text.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl TicketFilter {
+    // current_user_id 用于解析 `me`
+    pub fn parse(input: &str, current_user_id: Uuid) -> Result<Self, QueryError> {
+        let mut clauses = Vec::new();
+        for token in tokenize(input)? {
+            if let Some(clause) = parse_clause(&token, current_user_id)? {
+                clauses.push(clause);
+            }
+        }
+
+        Ok(Self { clauses })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+}
+
+// 按空白切分，双引号内的空白不切分
+fn tokenize(input: &str) -> Result<Vec<Token<'_>>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().enumerate().peekable();
+
+    while let Some((position, (start, c))) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+        let mut in_quote = c == '"';
+        while let Some(&(_, (offset, c))) = chars.peek() {
+            if !in_quote && c.is_whitespace() {
+                break;
+            }
+            if c == '"' {
+                in_quote = !in_quote;
+            }
+            end = offset + c.len_utf8();
+            chars.next();
+        }
+
+        let token = Token {
+            position: position + 1,
+            text: &input[start..end],
+        };
+        if in_quote {
+            return Err(token.error("缺少闭合的双引号"));
+        }
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_clause(token: &Token<'_>, current_user_id: Uuid) -> Result<Option<Clause>, QueryError> {
+    let (negated, body) = match token.text.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token.text),
+    };
+
+    let condition = match split_field(body) {
+        Some((field, operator, value)) => {
+            Some(parse_field(token, field, operator, value, current_user_id)?)
+        }
+        None => parse_text(body).map(Condition::Text),
+    };
+
+    Ok(condition.map(|condition| Clause { negated, condition }))
+}
+
+// 拆分 `字段<运算符>取值`，字段名仅由字母和下划线组成
+fn split_field(body: &str) -> Option<(&str, Operator, &str)> {
+    let name_len = body
+        .find(|c: char| !(c.is_ascii_alphabetic() || c == '_'))
+        .filter(|len| *len > 0)?;
+    let (field, rest) = body.split_at(name_len);
+
+    let operators = [
+        (">=", Operator::Compare(Comparison::Ge)),
+        ("<=", Operator::Compare(Comparison::Le)),
+        (">", Operator::Compare(Comparison::Gt)),
+        ("<", Operator::Compare(Comparison::Lt)),
+        (":", Operator::Is),
+    ];
+    operators.into_iter().find_map(|(symbol, operator)| {
+        rest.strip_prefix(symbol)
+            .map(|value| (field, operator, value))
+    })
+}
+
+fn parse_text(body: &str) -> Option<SearchTerm> {
+    if let Some(phrase) = body.strip_prefix('"') {
+        let words: Vec<String> = phrase
+            .trim_end_matches('"')
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        return match words.len() {
+            0 => None,
+            1 => words.into_iter().next().map(SearchTerm::Word),
+            _ => Some(SearchTerm::Phrase(words)),
+        };
+    }
+
+    match body.strip_suffix('*') {
+        Some(prefix) => {
+            let prefix = prefix.trim_end_matches('*');
+            (!prefix.is_empty()).then(|| SearchTerm::Prefix(prefix.to_string()))
+        }
+        None => Some(SearchTerm::Word(body.to_string())),
+    }
+}
+
+fn parse_field(
+    token: &Token<'_>,
+    field: &str,
+    operator: Operator,
+    value: &str,
+    current_user_id: Uuid,
+) -> Result<Condition, QueryError> {
+    let field_name = field.to_ascii_lowercase();
+    if !FIELDS.contains(&field_name.as_str()) {
+        return Err(token.error(format!(
+            "未知的字段 `{}`，可用字段: {}",
+            field,
+            FIELDS.join(", ")
+        )));
+    }
+    let values = split_values(token, value)?;
+
+    match field_name.as_str() {
+        "status" => {
+            expect_is(token, &field_name, operator)?;
+            Ok(Condition::Status(
+                values.iter().map(|v| v.to_lowercase()).collect(),
+            ))
+        }
+        "category" => {
+            expect_is(token, &field_name, operator)?;
+            values
+                .iter()
+                .map(|v| parse_category(token, v))
+                .collect::<Result<_, _>>()
+                .map(Condition::Category)
+        }
+        "priority" => parse_priority(token, operator, &values).map(Condition::Priority),
+        "tag" => {
+            expect_is(token, &field_name, operator)?;
+            Ok(Condition::Tag(values))
+        }
+        "assignee" | "reporter" => {
+            expect_is(token, &field_name, operator)?;
+            let users = values
+                .iter()
+                .map(|v| parse_user(token, v, current_user_id))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(if field_name == "assignee" {
+                Condition::Assignee(users)
+            } else {
+                Condition::Reporter(users)
+            })
+        }
+        _ => {
+            let field = match field_name.as_str() {
+                "created" => DateField::Created,
+                "updated" => DateField::Updated,
+                _ => DateField::Resolved,
+            };
+            let [value] = values.as_slice() else {
+                return Err(token.error("日期条件只能有一个取值"));
+            };
+            parse_date_bounds(token, operator, value)
+                .map(|bounds| Condition::Date { field, bounds })
+        }
+    }
+}
+
+// 逗号分隔的取值；整体由双引号包裹时视为单个取值
+fn split_values(token: &Token<'_>, value: &str) -> Result<Vec<String>, QueryError> {
+    let values: Vec<String> = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(quoted) => vec![quoted.trim().to_string()],
+        None => value.split(',').map(|v| v.trim().to_string()).collect(),
+    };
+
+    if values.iter().any(String::is_empty) {
+        return Err(token.error("缺少取值"));
+    }
+    Ok(values)
+}
+
+fn expect_is(token: &Token<'_>, field: &str, operator: Operator) -> Result<(), QueryError> {
+    match operator {
+        Operator::Is => Ok(()),
+        Operator::Compare(_) => Err(token.error(format!("字段 {} 只支持 `:`", field))),
+    }
+}
+
+fn parse_category(token: &Token<'_>, value: &str) -> Result<StatusCategory, QueryError> {
+    match value.to_lowercase().as_str() {
+        "open" => Ok(StatusCategory::Open),
+        "active" => Ok(StatusCategory::Active),
+        "done" => Ok(StatusCategory::Done),
+        _ => Err(token.error(format!(
+            "未知的状态分类 `{}`，可选: open, active, done",
+            value
+        ))),
+    }
+}
+
+fn parse_priority(
+    token: &Token<'_>,
+    operator: Operator,
+    values: &[String],
+) -> Result<Vec<Priority>, QueryError> {
+    let priorities = values
+        .iter()
+        .map(|value| {
+            Priority::ALL
+                .into_iter()
+                .find(|p| p.as_str().eq_ignore_ascii_case(value))
+                .ok_or_else(|| {
+                    token.error(format!(
+                        "未知的优先级 `{}`，可选: low, medium, high, urgent",
+                        value
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let Operator::Compare(comparison) = operator else {
+        return Ok(priorities);
+    };
+    let [priority] = priorities.as_slice() else {
+        return Err(token.error("优先级比较只能有一个取值"));
+    };
+
+    Ok(Priority::ALL
+        .into_iter()
+        .filter(|p| match comparison {
+            Comparison::Gt => p > priority,
+            Comparison::Ge => p >= priority,
+            Comparison::Lt => p < priority,
+            Comparison::Le => p <= priority,
+        })
+        .collect())
+}
+
+fn parse_user(
+    token: &Token<'_>,
+    value: &str,
+    current_user_id: Uuid,
+) -> Result<UserRef, QueryError> {
+    if value.eq_ignore_ascii_case("me") {
+        return Ok(UserRef::Id(current_user_id));
+    }
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(UserRef::Nobody);
+    }
+    if let Ok(id) = value.parse::<Uuid>() {
+        return Ok(UserRef::Id(id));
+    }
+    if value.contains('@') {
+        return Ok(UserRef::Email(value.to_string()));
+    }
+
+    Err(token.error(format!(
+        "无法识别的用户 `{}`，可用 me、none、用户ID或邮箱",
+        value
+    )))
+}
+
+// 日期（YYYY-MM-DD，按 UTC 整天计算）或 RFC 3339 时间
+fn parse_date_bounds(
+    token: &Token<'_>,
+    operator: Operator,
+    value: &str,
+) -> Result<Vec<(Comparison, DateTime<Utc>)>, QueryError> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let day_start = date.and_time(NaiveTime::MIN).and_utc();
+        let next_day = date
+            .checked_add_days(Days::new(1))
+            .map(|d| d.and_time(NaiveTime::MIN).and_utc())
+            .ok_or_else(|| token.error("日期超出范围"))?;
+
+        return Ok(match operator {
+            Operator::Is => vec![(Comparison::Ge, day_start), (Comparison::Lt, next_day)],
+            Operator::Compare(Comparison::Gt) => vec![(Comparison::Ge, next_day)],
+            Operator::Compare(Comparison::Ge) => vec![(Comparison::Ge, day_start)],
+            Operator::Compare(Comparison::Lt) => vec![(Comparison::Lt, day_start)],
+            Operator::Compare(Comparison::Le) => vec![(Comparison::Lt, next_day)],
+        });
+    }
+
+    let time = DateTime::parse_from_rfc3339(value)
+        .map_err(|_| {
+            token.error(format!(
+                "无效的日期 `{}`，应为 YYYY-MM-DD 或 RFC 3339 时间",
+                value
+            ))
+        })?
+        .with_timezone(&Utc);
+
+    match operator {
+        Operator::Is => Err(token.error("精确到时间的条件请使用 >、>=、< 或 <=")),
+        Operator::Compare(comparison) => Ok(vec![(comparison, time)]),
+    }
+}
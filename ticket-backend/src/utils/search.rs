@@ -0,0 +1,238 @@
+// 全文搜索关键词解析与高亮片段处理
+//
+// 支持的语法：
+// - 空格分隔的词按 AND 组合，`OR` 连接相邻的词
+// - "双引号" 表示短语，词尾 `*` 表示前缀匹配
+// - `-` 前缀表示排除
+
+// ts_headline 使用的高亮标记（控制字符，不会出现在正常文本中），返回前替换为 <mark>
+pub const HIGHLIGHT_START: char = '\u{2}';
+pub const HIGHLIGHT_STOP: char = '\u{3}';
+
+// 描述摘要在回退匹配时保留的字符数
+const SNIPPET_CHARS: usize = 120;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchTerm {
+    Word(String),
+    Prefix(String),
+    Phrase(Vec<String>),
+}
+
+impl SearchTerm {
+    // 对应的 tsquery 片段，词素均加引号，由 to_tsquery 按文本搜索配置归一化
+    fn to_tsquery(&self) -> String {
+        match self {
+            SearchTerm::Word(word) => quote_lexeme(word),
+            SearchTerm::Prefix(word) => format!("{}:*", quote_lexeme(word)),
+            SearchTerm::Phrase(words) => {
+                let words: Vec<String> = words.iter().map(|w| quote_lexeme(w)).collect();
+                format!("({})", words.join(" <-> "))
+            }
+        }
+    }
+
+    // 子串回退匹配使用的文本
+    pub fn text(&self) -> String {
+        match self {
+            SearchTerm::Word(word) | SearchTerm::Prefix(word) => word.clone(),
+            SearchTerm::Phrase(words) => words.join(" "),
+        }
+    }
+}
+
+// 解析后的搜索条件：groups 之间为 AND，组内为 OR
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchTerms {
+    pub groups: Vec<Vec<SearchTerm>>,
+    pub excluded: Vec<SearchTerm>,
+}
+
+impl SearchTerms {
+    pub fn parse(input: &str) -> Self {
+        let mut terms = SearchTerms::default();
+        let mut chars = input.chars().peekable();
+        let mut pending_or = false;
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let Some(&first) = chars.peek() else {
+                break;
+            };
+
+            let negated = first == '-';
+            if negated {
+                chars.next();
+            }
+
+            let term = if chars.next_if_eq(&'"').is_some() {
+                let phrase: String = chars.by_ref().take_while(|c| *c != '"').collect();
+                let words: Vec<String> = phrase.split_whitespace().map(str::to_string).collect();
+                match words.len() {
+                    0 => None,
+                    1 => words.into_iter().next().map(SearchTerm::Word),
+                    _ => Some(SearchTerm::Phrase(words)),
+                }
+            } else {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    word.push(c);
+                }
+
+                if word == "OR" && !negated {
+                    pending_or = !terms.groups.is_empty();
+                    continue;
+                }
+
+                match word.strip_suffix('*') {
+                    Some(prefix) => {
+                        let prefix = prefix.trim_end_matches('*');
+                        (!prefix.is_empty()).then(|| SearchTerm::Prefix(prefix.to_string()))
+                    }
+                    None => (!word.is_empty()).then_some(SearchTerm::Word(word)),
+                }
+            };
+
+            let Some(term) = term else {
+                continue;
+            };
+
+            if negated {
+                terms.excluded.push(term);
+            } else if pending_or {
+                if let Some(group) = terms.groups.last_mut() {
+                    group.push(term);
+                }
+            } else {
+                terms.groups.push(vec![term]);
+            }
+            pending_or = false;
+        }
+
+        terms
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty() && self.excluded.is_empty()
+    }
+
+    // 组合为 to_tsquery 可接受的查询文本
+    pub fn to_tsquery(&self) -> String {
+        let mut parts: Vec<String> = self
+            .groups
+            .iter()
+            .map(|group| {
+                let terms: Vec<String> = group.iter().map(SearchTerm::to_tsquery).collect();
+                if terms.len() == 1 {
+                    terms.into_iter().next().unwrap_or_default()
+                } else {
+                    format!("({})", terms.join(" | "))
+                }
+            })
+            .collect();
+        parts.extend(
+            self.excluded
+                .iter()
+                .map(|term| format!("!{}", term.to_tsquery())),
+        );
+
+        parts.join(" & ")
+    }
+
+    // 回退匹配的全部正向词，用于高亮
+    pub fn highlight_texts(&self) -> Vec<String> {
+        self.groups.iter().flatten().map(SearchTerm::text).collect()
+    }
+
+    // 用于相似度排序的原始文本
+    pub fn plain_text(&self) -> String {
+        self.highlight_texts().join(" ")
+    }
+}
+
+// ILIKE 模式，转义通配符
+pub fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+// 将 ts_headline 输出转换为 HTML：转义原文，高亮标记替换为 <mark>
+pub fn render_highlight(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        match c {
+            HIGHLIGHT_START => html.push_str("<mark>"),
+            HIGHLIGHT_STOP => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+    html
+}
+
+// 全文检索未命中（如中文子串）时，直接在原文中标记匹配的词
+pub fn fallback_highlight(text: &str, terms: &[String], snippet: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lowered: Vec<char> = chars.iter().map(|c| fold_case(*c)).collect();
+
+    let mut marked = vec![false; chars.len()];
+    for term in terms {
+        let needle: Vec<char> = term.chars().map(fold_case).collect();
+        if needle.is_empty() || needle.len() > lowered.len() {
+            continue;
+        }
+        let mut start = 0;
+        while start + needle.len() <= lowered.len() {
+            if lowered[start..start + needle.len()] == needle[..] {
+                marked[start..start + needle.len()].fill(true);
+                start += needle.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+
+    // 摘要以第一个匹配为中心截取
+    let (from, to) = match marked.iter().position(|m| *m) {
+        Some(first) if snippet && chars.len() > SNIPPET_CHARS => {
+            let from = first.saturating_sub(SNIPPET_CHARS / 4);
+            (from, (from + SNIPPET_CHARS).min(chars.len()))
+        }
+        None if snippet => (0, chars.len().min(SNIPPET_CHARS)),
+        _ => (0, chars.len()),
+    };
+
+    let mut headline = String::new();
+    let mut in_mark = false;
+    for index in from..to {
+        if marked[index] != in_mark {
+            headline.push(if marked[index] {
+                HIGHLIGHT_START
+            } else {
+                HIGHLIGHT_STOP
+            });
+            in_mark = marked[index];
+        }
+        headline.push(chars[index]);
+    }
+    if in_mark {
+        headline.push(HIGHLIGHT_STOP);
+    }
+
+    render_highlight(&headline)
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn quote_lexeme(word: &str) -> String {
+    format!("'{}'", word.replace('\\', "\\\\").replace('\'', "''"))
+}
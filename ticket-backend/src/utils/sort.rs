@@ -0,0 +1,127 @@
+// 工单列表排序：`sort=-priority,created_at`，逗号分隔多个排序键，`-` 前缀表示降序
+// 同时兼容 sort_by / sort_order 参数
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+// 可排序字段（白名单）；priority 按严重程度、status 按状态在工作流中的顺序排序，
+// due_date 无论升序降序，未设置截止时间的工单都排在最后
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketSortField {
+    CreatedAt,
+    UpdatedAt,
+    Priority,
+    Status,
+    Title,
+    DueDate,
+}
+
+impl TicketSortField {
+    pub const ALL: [TicketSortField; 6] = [
+        Self::CreatedAt,
+        Self::UpdatedAt,
+        Self::Priority,
+        Self::Status,
+        Self::Title,
+        Self::DueDate,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::UpdatedAt => "updated_at",
+            Self::Priority => "priority",
+            Self::Status => "status",
+            Self::Title => "title",
+            Self::DueDate => "due_date",
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self, AppError> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.as_str() == name)
+            .ok_or_else(|| {
+                let fields: Vec<&str> = Self::ALL.iter().map(Self::as_str).collect();
+                AppError::bad_request(format!(
+                    "未知的排序字段 `{}`，可用字段: {}",
+                    name,
+                    fields.join(", ")
+                ))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketSortKey {
+    pub field: TicketSortField,
+    pub descending: bool,
+}
+
+impl TicketSortKey {
+    // 默认按创建时间倒序
+    pub fn default_keys() -> Vec<Self> {
+        vec![Self {
+            field: TicketSortField::CreatedAt,
+            descending: true,
+        }]
+    }
+}
+
+// 解析排序参数，sort 优先于 sort_by / sort_order；均未指定时返回 None
+pub fn parse_sort(
+    sort: Option<&str>,
+    sort_by: Option<&str>,
+    sort_order: Option<&str>,
+) -> Result<Option<Vec<TicketSortKey>>, AppError> {
+    if let Some(sort) = sort {
+        return parse_sort_list(sort).map(Some);
+    }
+    if sort_by.is_none() && sort_order.is_none() {
+        return Ok(None);
+    }
+
+    let field = match sort_by {
+        Some(name) => TicketSortField::parse(name.trim())?,
+        None => TicketSortField::CreatedAt,
+    };
+    let descending = match sort_order.map(str::trim) {
+        None => true,
+        Some(order) if order.eq_ignore_ascii_case("desc") => true,
+        Some(order) if order.eq_ignore_ascii_case("asc") => false,
+        Some(order) => {
+            return Err(AppError::bad_request(format!(
+                "无效的排序方向 `{}`，可选: asc, desc",
+                order
+            )))
+        }
+    };
+
+    Ok(Some(vec![TicketSortKey { field, descending }]))
+}
+
+fn parse_sort_list(raw: &str) -> Result<Vec<TicketSortKey>, AppError> {
+    let mut keys: Vec<TicketSortKey> = Vec::new();
+    for part in raw.split(',').map(str::trim) {
+        let (descending, name) = match part.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, part.strip_prefix('+').unwrap_or(part)),
+        };
+        if name.is_empty() {
+            return Err(AppError::bad_request(format!("无效的排序参数 `{}`", raw)));
+        }
+
+        let field = TicketSortField::parse(name)?;
+        if keys.iter().any(|key| key.field == field) {
+            return Err(AppError::bad_request(format!(
+                "排序字段 `{}` 重复",
+                field.as_str()
+            )));
+        }
+        keys.push(TicketSortKey { field, descending });
+    }
+
+    Ok(keys)
+}
@@ -1,15 +1,58 @@
 // 基础集成测试
-use reqwest;
 use serde_json::Value;
 
 // 测试配置
 const BASE_URL: &str = "http://localhost:3000";
 
+// 测试使用的管理员账号，需与服务端 ADMIN_EMAIL / ADMIN_PASSWORD 一致
+fn admin_credentials() -> (String, String) {
+    (
+        std::env::var("ADMIN_EMAIL").unwrap_or_else(|_| "admin@example.com".to_string()),
+        std::env::var("ADMIN_PASSWORD").unwrap_or_else(|_| "admin123456".to_string()),
+    )
+}
+
+// 登录并获取会话令牌
+async fn login(email: &str, password: &str) -> String {
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", BASE_URL))
+        .json(&serde_json::json!({ "email": email, "password": password }))
+        .send()
+        .await
+        .expect("Failed to login");
+    assert_eq!(response.status(), 200);
+
+    let body: Value = response
+        .json()
+        .await
+        .expect("Failed to parse login response");
+    body["token"].as_str().unwrap().to_string()
+}
+
+// 携带指定令牌的客户端
+fn client_with_token(token: &str) -> reqwest::Client {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        format!("Bearer {}", token).parse().unwrap(),
+    );
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .expect("Failed to build client")
+}
+
+// 以管理员身份登录的客户端
+async fn authorized_client() -> reqwest::Client {
+    let (email, password) = admin_credentials();
+    client_with_token(&login(&email, &password).await)
+}
+
 #[tokio::test]
 async fn test_health_check() {
     let client = reqwest::Client::new();
     let response = client
-        .get(&format!("{}/health", BASE_URL))
+        .get(format!("{}/health", BASE_URL))
         .send()
         .await
         .expect("Failed to execute request");
@@ -22,9 +65,9 @@ async fn test_health_check() {
 
 #[tokio::test]
 async fn test_database_stats() {
-    let client = reqwest::Client::new();
+    let client = authorized_client().await;
     let response = client
-        .get(&format!("{}/api/db/stats", BASE_URL))
+        .get(format!("{}/api/db/stats", BASE_URL))
         .send()
         .await
         .expect("Failed to execute request");
@@ -39,7 +82,7 @@ async fn test_database_stats() {
 
 #[tokio::test]
 async fn test_create_and_get_tag() {
-    let client = reqwest::Client::new();
+    let client = authorized_client().await;
 
     // 创建标签
     let create_payload = serde_json::json!({
@@ -48,7 +91,7 @@ async fn test_create_and_get_tag() {
     });
 
     let create_response = client
-        .post(&format!("{}/api/v1/tags", BASE_URL))
+        .post(format!("{}/api/v1/tags", BASE_URL))
         .json(&create_payload)
         .send()
         .await
@@ -68,7 +111,7 @@ async fn test_create_and_get_tag() {
 
     // 获取标签
     let get_response = client
-        .get(&format!("{}/api/v1/tags/{}", BASE_URL, tag_id))
+        .get(format!("{}/api/v1/tags/{}", BASE_URL, tag_id))
         .send()
         .await
         .expect("Failed to get tag");
@@ -87,7 +130,7 @@ async fn test_create_and_get_tag() {
 
     // 清理：删除标签
     let delete_response = client
-        .delete(&format!("{}/api/v1/tags/{}", BASE_URL, tag_id))
+        .delete(format!("{}/api/v1/tags/{}", BASE_URL, tag_id))
         .send()
         .await
         .expect("Failed to delete tag");
@@ -97,7 +140,7 @@ async fn test_create_and_get_tag() {
 
 #[tokio::test]
 async fn test_create_and_get_ticket() {
-    let client = reqwest::Client::new();
+    let client = authorized_client().await;
 
     // 创建工单
     let create_payload = serde_json::json!({
@@ -107,7 +150,7 @@ async fn test_create_and_get_ticket() {
     });
 
     let create_response = client
-        .post(&format!("{}/api/v1/tickets", BASE_URL))
+        .post(format!("{}/api/v1/tickets", BASE_URL))
         .json(&create_payload)
         .send()
         .await
@@ -135,7 +178,7 @@ async fn test_create_and_get_ticket() {
 
     // 获取工单
     let get_response = client
-        .get(&format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
+        .get(format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
         .send()
         .await
         .expect("Failed to get ticket");
@@ -157,7 +200,7 @@ async fn test_create_and_get_ticket() {
 
     // 清理：删除工单
     let delete_response = client
-        .delete(&format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
+        .delete(format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
         .send()
         .await
         .expect("Failed to delete ticket");
@@ -167,7 +210,7 @@ async fn test_create_and_get_ticket() {
 
 #[tokio::test]
 async fn test_advanced_search() {
-    let client = reqwest::Client::new();
+    let client = authorized_client().await;
 
     // 测试搜索参数
     let search_params = [
@@ -177,15 +220,11 @@ async fn test_advanced_search() {
         ("limit", "10"),
     ];
 
-    let mut url = format!("{}/api/v1/tickets", BASE_URL);
-    if !search_params.is_empty() {
-        url.push('?');
-        let param_strings: Vec<String> = search_params
-            .iter()
-            .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
-            .collect();
-        url.push_str(&param_strings.join("&"));
-    }
+    let param_strings: Vec<String> = search_params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+        .collect();
+    let url = format!("{}/api/v1/tickets?{}", BASE_URL, param_strings.join("&"));
 
     let response = client
         .get(&url)
@@ -207,10 +246,10 @@ async fn test_advanced_search() {
 
 #[tokio::test]
 async fn test_database_optimization() {
-    let client = reqwest::Client::new();
+    let client = authorized_client().await;
 
     let response = client
-        .post(&format!("{}/api/db/optimize", BASE_URL))
+        .post(format!("{}/api/db/optimize", BASE_URL))
         .send()
         .await
         .expect("Failed to execute optimization request");
@@ -227,3 +266,4123 @@ async fn test_database_optimization() {
     );
     assert!(result.get("created_indexes").unwrap().as_u64().unwrap() > 0);
 }
+
+#[tokio::test]
+async fn test_comment_crud() {
+    let client = authorized_client().await;
+
+    // 创建工单
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "评论测试工单" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse created ticket");
+    let ticket_id = ticket.get("id").unwrap().as_str().unwrap();
+    let comments_url = format!("{}/api/v1/tickets/{}/comments", BASE_URL, ticket_id);
+
+    // 创建评论
+    let create_response = client
+        .post(&comments_url)
+        .json(&serde_json::json!({ "content": "第一条评论" }))
+        .send()
+        .await
+        .expect("Failed to create comment");
+    assert_eq!(create_response.status(), 200);
+
+    let comment: Value = create_response
+        .json()
+        .await
+        .expect("Failed to parse created comment");
+    let comment_id = comment.get("id").unwrap().as_str().unwrap();
+
+    // 空评论应被拒绝
+    let invalid_response = client
+        .post(&comments_url)
+        .json(&serde_json::json!({ "content": "" }))
+        .send()
+        .await
+        .expect("Failed to send invalid comment");
+    assert_eq!(invalid_response.status(), 400);
+
+    // 编辑评论
+    let update_response = client
+        .put(format!("{}/{}", comments_url, comment_id))
+        .json(&serde_json::json!({ "content": "修改后的评论" }))
+        .send()
+        .await
+        .expect("Failed to update comment");
+    assert_eq!(update_response.status(), 200);
+
+    // 分页列出评论
+    let list: Value = client
+        .get(format!("{}?page=1&limit=10", comments_url))
+        .send()
+        .await
+        .expect("Failed to list comments")
+        .json()
+        .await
+        .expect("Failed to parse comment list");
+    assert_eq!(list.get("total").unwrap().as_i64().unwrap(), 1);
+    assert_eq!(
+        list["data"][0].get("content").unwrap().as_str().unwrap(),
+        "修改后的评论"
+    );
+
+    // 删除评论
+    let delete_response = client
+        .delete(format!("{}/{}", comments_url, comment_id))
+        .send()
+        .await
+        .expect("Failed to delete comment");
+    assert_eq!(delete_response.status(), 204);
+
+    let missing_response = client
+        .delete(format!("{}/{}", comments_url, comment_id))
+        .send()
+        .await
+        .expect("Failed to delete missing comment");
+    assert_eq!(missing_response.status(), 404);
+
+    // 清理：删除工单
+    client
+        .delete(format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+}
+
+#[tokio::test]
+async fn test_pagination_bounds() {
+    let client = authorized_client().await;
+
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "分页边界测试工单" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse created ticket");
+    let ticket_id = ticket["id"].as_str().unwrap();
+
+    // 超大的 page / offset 返回空列表，而不是溢出
+    for path in [
+        "/api/v1/tickets".to_string(),
+        format!("/api/v1/tickets/{}/comments", ticket_id),
+        format!("/api/v1/tickets/{}/history", ticket_id),
+        "/api/v1/users".to_string(),
+        "/api/v1/trash".to_string(),
+        "/api/v1/notifications".to_string(),
+    ] {
+        for params in [
+            "page=9223372036854775807",
+            "offset=9223372036854775807&limit=1",
+        ] {
+            let response = client
+                .get(format!("{}{}?{}", BASE_URL, path, params))
+                .send()
+                .await
+                .expect("Failed to list");
+            assert_eq!(response.status(), 200, "{}?{}", path, params);
+
+            let body: Value = response.json().await.expect("Failed to parse list");
+            assert!(body["data"].as_array().unwrap().is_empty());
+        }
+    }
+
+    client
+        .delete(format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+}
+
+#[tokio::test]
+async fn test_ticket_user_references() {
+    let client = authorized_client().await;
+    let email = format!("agent-{}@example.com", uuid::Uuid::new_v4());
+
+    // 创建用户
+    let create_response = client
+        .post(format!("{}/api/v1/users", BASE_URL))
+        .json(&serde_json::json!({ "name": "测试用户", "email": email }))
+        .send()
+        .await
+        .expect("Failed to create user");
+    assert_eq!(create_response.status(), 200);
+
+    let user: Value = create_response
+        .json()
+        .await
+        .expect("Failed to parse created user");
+    let user_id = user.get("id").unwrap().as_str().unwrap();
+
+    // 重复邮箱应返回冲突
+    let duplicate_response = client
+        .post(format!("{}/api/v1/users", BASE_URL))
+        .json(&serde_json::json!({ "name": "重复用户", "email": email.to_uppercase() }))
+        .send()
+        .await
+        .expect("Failed to send duplicate user");
+    assert_eq!(duplicate_response.status(), 409);
+
+    // 工单响应应嵌入指派人摘要
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "指派测试工单", "assignee_id": user_id }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse created ticket");
+    let ticket_id = ticket.get("id").unwrap().as_str().unwrap();
+    assert_eq!(ticket["assignee"]["name"].as_str().unwrap(), "测试用户");
+    assert!(ticket["assignee"].get("avatar_color").is_some());
+    assert_eq!(
+        ticket["reporter"]["email"].as_str().unwrap(),
+        admin_credentials().0
+    );
+
+    // 停用后不可再被指派
+    let deactivate_response = client
+        .post(format!("{}/api/v1/users/{}/deactivate", BASE_URL, user_id))
+        .send()
+        .await
+        .expect("Failed to deactivate user");
+    assert_eq!(deactivate_response.status(), 200);
+
+    let rejected_response = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "无效指派", "assignee_id": user_id }))
+        .send()
+        .await
+        .expect("Failed to send ticket with inactive assignee");
+    assert_eq!(rejected_response.status(), 400);
+
+    // 省略字段时保持不变，显式传 null 时清空
+    let ticket_url = format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id);
+    let update = |body: Value| {
+        let request = client.put(&ticket_url).json(&body);
+        async move {
+            let response = request.send().await.expect("Failed to update ticket");
+            assert_eq!(response.status(), 200);
+            response
+                .json::<Value>()
+                .await
+                .expect("Failed to parse ticket")
+        }
+    };
+
+    let updated = update(serde_json::json!({ "due_date": "2030-01-01T00:00:00Z" })).await;
+    assert_eq!(updated["assignee_id"], user_id);
+    assert!(updated["due_date"].is_string());
+
+    let updated = update(serde_json::json!({ "assignee_id": null })).await;
+    assert!(updated["assignee_id"].is_null());
+    assert!(updated["assignee"].is_null());
+    assert!(updated["due_date"].is_string());
+
+    let updated = update(serde_json::json!({ "due_date": null })).await;
+    assert!(updated["due_date"].is_null());
+
+    // 清理：删除工单
+    client
+        .delete(format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+}
+
+#[tokio::test]
+async fn test_authentication() {
+    // 未携带令牌访问受保护接口
+    let anonymous_response = reqwest::Client::new()
+        .get(format!("{}/api/v1/tickets", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to send anonymous request");
+    assert_eq!(anonymous_response.status(), 401);
+
+    // 错误密码登录失败
+    let (email, _) = admin_credentials();
+    let bad_login_response = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", BASE_URL))
+        .json(&serde_json::json!({ "email": email, "password": "wrong-password" }))
+        .send()
+        .await
+        .expect("Failed to send bad login");
+    assert_eq!(bad_login_response.status(), 401);
+
+    let client = authorized_client().await;
+    let me: Value = client
+        .get(format!("{}/api/v1/auth/me", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to get current user")
+        .json()
+        .await
+        .expect("Failed to parse current user");
+    assert_eq!(me["email"].as_str().unwrap(), email);
+
+    // 创建只读 API 令牌
+    let created: Value = client
+        .post(format!("{}/api/v1/auth/tokens", BASE_URL))
+        .json(&serde_json::json!({ "name": "只读令牌", "scopes": ["read"] }))
+        .send()
+        .await
+        .expect("Failed to create api token")
+        .json()
+        .await
+        .expect("Failed to parse api token");
+    let token_id = created["id"].as_str().unwrap();
+    let secret = created["secret"].as_str().unwrap();
+    assert!(secret.starts_with("tk_"));
+    assert!(secret.starts_with(created["token_prefix"].as_str().unwrap()));
+
+    // 只读令牌可读不可写
+    let read_only = client_with_token(secret);
+    let read_response = read_only
+        .get(format!("{}/api/v1/tickets", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to list tickets with api token");
+    assert_eq!(read_response.status(), 200);
+
+    let write_response = read_only
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "只读令牌创建" }))
+        .send()
+        .await
+        .expect("Failed to send write with read-only token");
+    assert_eq!(write_response.status(), 403);
+
+    // 撤销后令牌失效
+    let revoke_response = client
+        .delete(format!("{}/api/v1/auth/tokens/{}", BASE_URL, token_id))
+        .send()
+        .await
+        .expect("Failed to revoke api token");
+    assert_eq!(revoke_response.status(), 200);
+
+    let revoked_response = read_only
+        .get(format!("{}/api/v1/tickets", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to send request with revoked token");
+    assert_eq!(revoked_response.status(), 401);
+}
+
+#[tokio::test]
+async fn test_role_authorization() {
+    let admin = authorized_client().await;
+
+    // 创建 viewer 与 agent 账号
+    let mut clients = Vec::new();
+    for role in ["viewer", "agent"] {
+        let email = format!("{}-{}@example.com", role, uuid::Uuid::new_v4());
+        let response = admin
+            .post(format!("{}/api/v1/users", BASE_URL))
+            .json(&serde_json::json!({
+                "name": role,
+                "email": email,
+                "password": "password123",
+                "role": role
+            }))
+            .send()
+            .await
+            .expect("Failed to create user");
+        assert_eq!(response.status(), 200);
+
+        let user: Value = response.json().await.expect("Failed to parse user");
+        assert_eq!(user["role"].as_str().unwrap(), role);
+        clients.push(client_with_token(&login(&email, "password123").await));
+    }
+    let (viewer, agent) = (&clients[0], &clients[1]);
+
+    // viewer 只能读取
+    let read_response = viewer
+        .get(format!("{}/api/v1/tickets", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to list tickets as viewer");
+    assert_eq!(read_response.status(), 200);
+
+    let write_response = viewer
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "viewer 创建" }))
+        .send()
+        .await
+        .expect("Failed to send write as viewer");
+    assert_eq!(write_response.status(), 403);
+    let error: Value = write_response.json().await.expect("Failed to parse error");
+    assert_eq!(error["error"]["status"].as_u64().unwrap(), 403);
+
+    // viewer 可以管理属于自己的资源：通知偏好、关注工单、个人视图与 API 令牌
+    let ticket: Value = admin
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "viewer 关注测试" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let viewer_ticket_url = format!(
+        "{}/api/v1/tickets/{}",
+        BASE_URL,
+        ticket["id"].as_str().unwrap()
+    );
+
+    let response = viewer
+        .put(format!("{}/api/v1/notifications/preferences", BASE_URL))
+        .json(&serde_json::json!({ "delivery": "digest" }))
+        .send()
+        .await
+        .expect("Failed to update preferences as viewer");
+    assert_eq!(response.status(), 200);
+    let response = viewer
+        .post(format!("{}/watch", viewer_ticket_url))
+        .send()
+        .await
+        .expect("Failed to watch ticket as viewer");
+    assert_eq!(response.status(), 204);
+    let response = viewer
+        .post(format!("{}/api/v1/views", BASE_URL))
+        .json(&serde_json::json!({ "name": format!("viewer 视图 {}", uuid::Uuid::new_v4()) }))
+        .send()
+        .await
+        .expect("Failed to create view as viewer");
+    assert_eq!(response.status(), 200);
+    let response = viewer
+        .post(format!("{}/api/v1/auth/tokens", BASE_URL))
+        .json(&serde_json::json!({ "name": "viewer 令牌", "scopes": ["read"] }))
+        .send()
+        .await
+        .expect("Failed to create token as viewer");
+    assert_eq!(response.status(), 200);
+
+    // 但不能修改工单的评论与标签
+    let response = viewer
+        .post(format!("{}/comments", viewer_ticket_url))
+        .json(&serde_json::json!({ "content": "viewer 评论" }))
+        .send()
+        .await
+        .expect("Failed to comment as viewer");
+    assert_eq!(response.status(), 403);
+    let response = viewer
+        .put(format!("{}/tags", viewer_ticket_url))
+        .json(&serde_json::json!({ "tag_ids": [] }))
+        .send()
+        .await
+        .expect("Failed to set tags as viewer");
+    assert_eq!(response.status(), 403);
+
+    // agent 可以创建标签，但删除标签、批量更新和数据库优化仅限管理员
+    let tag: Value = agent
+        .post(format!("{}/api/v1/tags", BASE_URL))
+        .json(&serde_json::json!({ "name": format!("角色测试-{}", uuid::Uuid::new_v4()) }))
+        .send()
+        .await
+        .expect("Failed to create tag as agent")
+        .json()
+        .await
+        .expect("Failed to parse tag");
+    let tag_url = format!("{}/api/v1/tags/{}", BASE_URL, tag["id"].as_str().unwrap());
+
+    let delete_response = agent
+        .delete(&tag_url)
+        .send()
+        .await
+        .expect("Failed to delete tag as agent");
+    assert_eq!(delete_response.status(), 403);
+
+    let bulk_response = agent
+        .put(format!("{}/api/v1/tickets/bulk/status", BASE_URL))
+        .json(&serde_json::json!({ "ticket_ids": [], "status": "closed" }))
+        .send()
+        .await
+        .expect("Failed to bulk update as agent");
+    assert_eq!(bulk_response.status(), 403);
+
+    let optimize_response = agent
+        .post(format!("{}/api/db/optimize", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to optimize as agent");
+    assert_eq!(optimize_response.status(), 403);
+
+    // 评论只能由作者或管理员编辑、删除
+    let ticket: Value = admin
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "评论权限测试" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let comments_url = format!(
+        "{}/api/v1/tickets/{}/comments",
+        BASE_URL,
+        ticket["id"].as_str().unwrap()
+    );
+    let mut comment_urls = Vec::new();
+    for client in [&admin, agent] {
+        let comment: Value = client
+            .post(&comments_url)
+            .json(&serde_json::json!({ "content": "权限测试评论" }))
+            .send()
+            .await
+            .expect("Failed to create comment")
+            .json()
+            .await
+            .expect("Failed to parse comment");
+        comment_urls.push(format!(
+            "{}/{}",
+            comments_url,
+            comment["id"].as_str().unwrap()
+        ));
+    }
+    let (admin_comment_url, agent_comment_url) = (&comment_urls[0], &comment_urls[1]);
+
+    let response = agent
+        .put(admin_comment_url)
+        .json(&serde_json::json!({ "content": "篡改" }))
+        .send()
+        .await
+        .expect("Failed to update comment as agent");
+    assert_eq!(response.status(), 403);
+    let response = agent
+        .delete(admin_comment_url)
+        .send()
+        .await
+        .expect("Failed to delete comment as agent");
+    assert_eq!(response.status(), 403);
+
+    let response = agent
+        .put(agent_comment_url)
+        .json(&serde_json::json!({ "content": "作者修改" }))
+        .send()
+        .await
+        .expect("Failed to update own comment");
+    assert_eq!(response.status(), 200);
+    let response = admin
+        .put(agent_comment_url)
+        .json(&serde_json::json!({ "content": "管理员修改" }))
+        .send()
+        .await
+        .expect("Failed to update comment as admin");
+    assert_eq!(response.status(), 200);
+    let response = admin
+        .delete(agent_comment_url)
+        .send()
+        .await
+        .expect("Failed to delete comment as admin");
+    assert_eq!(response.status(), 204);
+
+    let admin_delete_response = admin
+        .delete(&tag_url)
+        .send()
+        .await
+        .expect("Failed to delete tag as admin");
+    assert_eq!(admin_delete_response.status(), 204);
+}
+
+#[tokio::test]
+async fn test_ticket_history() {
+    let client = authorized_client().await;
+
+    let tag: Value = client
+        .post(format!("{}/api/v1/tags", BASE_URL))
+        .json(&serde_json::json!({ "name": format!("历史测试-{}", uuid::Uuid::new_v4()) }))
+        .send()
+        .await
+        .expect("Failed to create tag")
+        .json()
+        .await
+        .expect("Failed to parse tag");
+    let tag_id = tag["id"].as_str().unwrap();
+
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "历史测试工单", "tag_ids": [tag_id] }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let ticket_url = format!(
+        "{}/api/v1/tickets/{}",
+        BASE_URL,
+        ticket["id"].as_str().unwrap()
+    );
+
+    // 修改状态（标题不变不应产生事件）、评论、移除标签
+    client
+        .put(&ticket_url)
+        .json(&serde_json::json!({ "title": "历史测试工单", "status": "in_progress" }))
+        .send()
+        .await
+        .expect("Failed to update ticket");
+    client
+        .post(format!("{}/comments", ticket_url))
+        .json(&serde_json::json!({ "content": "处理中" }))
+        .send()
+        .await
+        .expect("Failed to add comment");
+    client
+        .delete(format!("{}/tags/{}", ticket_url, tag_id))
+        .send()
+        .await
+        .expect("Failed to remove tag");
+
+    let history: Value = client
+        .get(format!("{}/history", ticket_url))
+        .send()
+        .await
+        .expect("Failed to get history")
+        .json()
+        .await
+        .expect("Failed to parse history");
+    let events = history["data"].as_array().unwrap();
+    let event_types: Vec<&str> = events
+        .iter()
+        .map(|event| event["event_type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        event_types,
+        [
+            "created",
+            "tag_added",
+            "field_changed",
+            "comment_added",
+            "tag_removed"
+        ]
+    );
+    assert_eq!(history["total"].as_i64().unwrap(), 5);
+
+    let status_change = &events[2];
+    assert_eq!(status_change["field"].as_str().unwrap(), "status");
+    assert_eq!(status_change["old_value"].as_str().unwrap(), "open");
+    assert_eq!(status_change["new_value"].as_str().unwrap(), "in_progress");
+    assert_eq!(
+        status_change["actor"]["email"].as_str().unwrap(),
+        admin_credentials().0
+    );
+    assert_eq!(
+        events[4]["old_value"]["name"].as_str().unwrap(),
+        tag["name"].as_str().unwrap()
+    );
+
+    // 分页
+    let page: Value = client
+        .get(format!("{}/history?limit=2&offset=4", ticket_url))
+        .send()
+        .await
+        .expect("Failed to get history page")
+        .json()
+        .await
+        .expect("Failed to parse history page");
+    assert_eq!(page["data"].as_array().unwrap().len(), 1);
+
+    // 删除后历史仍保留
+    client
+        .delete(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+    let history: Value = client
+        .get(format!("{}/history", ticket_url))
+        .send()
+        .await
+        .expect("Failed to get history after delete")
+        .json()
+        .await
+        .expect("Failed to parse history after delete");
+    assert_eq!(history["total"].as_i64().unwrap(), 6);
+    assert_eq!(
+        history["data"][5]["event_type"].as_str().unwrap(),
+        "deleted"
+    );
+
+    let missing_response = client
+        .get(format!(
+            "{}/api/v1/tickets/{}/history",
+            BASE_URL,
+            uuid::Uuid::new_v4()
+        ))
+        .send()
+        .await
+        .expect("Failed to get missing history");
+    assert_eq!(missing_response.status(), 404);
+
+    client
+        .delete(format!("{}/api/v1/tags/{}", BASE_URL, tag_id))
+        .send()
+        .await
+        .expect("Failed to delete tag");
+}
+
+#[tokio::test]
+async fn test_status_workflow() {
+    let client = authorized_client().await;
+
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "状态流转测试" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let ticket_id = ticket["id"].as_str().unwrap().to_string();
+    let ticket_url = format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id);
+
+    let set_status = |status: &'static str| {
+        client
+            .put(&ticket_url)
+            .json(&serde_json::json!({ "status": status }))
+            .send()
+    };
+
+    // 进入已解决时记录解决时间，退回处理中时清空
+    let resolved: Value = set_status("resolved")
+        .await
+        .expect("Failed to resolve ticket")
+        .json()
+        .await
+        .expect("Failed to parse resolved ticket");
+    assert!(resolved["resolved_at"].is_string());
+
+    let in_progress: Value = set_status("in_progress")
+        .await
+        .expect("Failed to move ticket back")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    assert!(in_progress["resolved_at"].is_null());
+
+    let closed_response = set_status("closed").await.expect("Failed to close ticket");
+    assert_eq!(closed_response.status(), 200);
+
+    // 已关闭的工单不能直接修改状态
+    let illegal_response = set_status("open").await.expect("Failed to send transition");
+    assert_eq!(illegal_response.status(), 409);
+
+    // 批量更新同样遵守流转规则
+    let bulk: Value = client
+        .put(format!("{}/api/v1/tickets/bulk/status", BASE_URL))
+        .json(&serde_json::json!({ "ticket_ids": [ticket_id], "status": "in_progress" }))
+        .send()
+        .await
+        .expect("Failed to bulk update")
+        .json()
+        .await
+        .expect("Failed to parse bulk result");
+    assert_eq!(bulk["updated_count"].as_u64().unwrap(), 0);
+    assert_eq!(bulk["errors"].as_array().unwrap().len(), 1);
+
+    // 显式重新打开
+    let reopened: Value = client
+        .post(format!("{}/reopen", ticket_url))
+        .send()
+        .await
+        .expect("Failed to reopen ticket")
+        .json()
+        .await
+        .expect("Failed to parse reopened ticket");
+    assert_eq!(reopened["status"].as_str().unwrap(), "open");
+    assert!(reopened["resolved_at"].is_null());
+
+    let reopen_again = client
+        .post(format!("{}/reopen", ticket_url))
+        .send()
+        .await
+        .expect("Failed to send reopen");
+    assert_eq!(reopen_again.status(), 409);
+
+    client
+        .delete(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+}
+
+#[tokio::test]
+async fn test_custom_workflow() {
+    let client = authorized_client().await;
+
+    let name = format!("客服流程-{}", uuid::Uuid::new_v4());
+    let response = client
+        .post(format!("{}/api/v1/workflows", BASE_URL))
+        .json(&serde_json::json!({
+            "name": name,
+            "states": [
+                { "key": "new", "name": "新建", "category": "open" },
+                { "key": "waiting_on_customer", "name": "等待客户", "category": "active" },
+                { "key": "done", "name": "完成", "category": "done" }
+            ],
+            "transitions": [
+                { "from": "new", "to": "waiting_on_customer" },
+                { "from": "waiting_on_customer", "to": "done" }
+            ]
+        }))
+        .send()
+        .await
+        .expect("Failed to create workflow");
+    assert_eq!(response.status(), 200);
+    let workflow: Value = response.json().await.expect("Failed to parse workflow");
+    let workflow_id = workflow["id"].as_str().unwrap().to_string();
+    let workflow_url = format!("{}/api/v1/workflows/{}", BASE_URL, workflow_id);
+
+    // 工作流至少需要一个完成类状态
+    let invalid_response = client
+        .post(format!("{}/api/v1/workflows", BASE_URL))
+        .json(&serde_json::json!({
+            "name": format!("无效流程-{}", uuid::Uuid::new_v4()),
+            "states": [{ "key": "new", "name": "新建", "category": "open" }]
+        }))
+        .send()
+        .await
+        .expect("Failed to send invalid workflow");
+    assert_eq!(invalid_response.status(), 400);
+
+    // 新工单进入工作流的初始状态
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "自定义工作流", "workflow_id": workflow_id }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    assert_eq!(ticket["status"].as_str().unwrap(), "new");
+    let ticket_url = format!(
+        "{}/api/v1/tickets/{}",
+        BASE_URL,
+        ticket["id"].as_str().unwrap()
+    );
+
+    let skip_response = client
+        .put(&ticket_url)
+        .json(&serde_json::json!({ "status": "done" }))
+        .send()
+        .await
+        .expect("Failed to send transition");
+    assert_eq!(skip_response.status(), 409);
+
+    let unknown_response = client
+        .put(&ticket_url)
+        .json(&serde_json::json!({ "status": "in_progress" }))
+        .send()
+        .await
+        .expect("Failed to send transition");
+    assert_eq!(unknown_response.status(), 400);
+
+    let waiting: Value = client
+        .put(&ticket_url)
+        .json(&serde_json::json!({ "status": "waiting_on_customer" }))
+        .send()
+        .await
+        .expect("Failed to update status")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    assert_eq!(waiting["status"].as_str().unwrap(), "waiting_on_customer");
+
+    // 按状态分类过滤
+    let active: Value = client
+        .get(format!(
+            "{}/api/v1/tickets?workflow_id={}&status_category=active",
+            BASE_URL, workflow_id
+        ))
+        .send()
+        .await
+        .expect("Failed to list tickets")
+        .json()
+        .await
+        .expect("Failed to parse tickets");
+    assert_eq!(active["total"].as_i64().unwrap(), 1);
+
+    // 仍有工单使用时不能删除
+    let in_use_response = client
+        .delete(&workflow_url)
+        .send()
+        .await
+        .expect("Failed to send delete");
+    assert_eq!(in_use_response.status(), 409);
+
+    client
+        .delete(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+
+    // 未被使用的工作流可以删除
+    let unused: Value = client
+        .post(format!("{}/api/v1/workflows", BASE_URL))
+        .json(&serde_json::json!({
+            "name": format!("临时流程-{}", uuid::Uuid::new_v4()),
+            "states": [
+                { "key": "todo", "name": "待办", "category": "open" },
+                { "key": "done", "name": "完成", "category": "done" }
+            ],
+            "transitions": [{ "from": "todo", "to": "done" }]
+        }))
+        .send()
+        .await
+        .expect("Failed to create workflow")
+        .json()
+        .await
+        .expect("Failed to parse workflow");
+    let delete_response = client
+        .delete(format!(
+            "{}/api/v1/workflows/{}",
+            BASE_URL,
+            unused["id"].as_str().unwrap()
+        ))
+        .send()
+        .await
+        .expect("Failed to delete workflow");
+    assert_eq!(delete_response.status(), 204);
+}
+
+#[tokio::test]
+async fn test_optimistic_concurrency() {
+    let client = authorized_client().await;
+
+    let response = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "并发编辑测试" }))
+        .send()
+        .await
+        .expect("Failed to create ticket");
+    assert_eq!(response.headers()["etag"], "\"1\"");
+    let ticket: Value = response.json().await.expect("Failed to parse ticket");
+    let ticket_url = format!(
+        "{}/api/v1/tickets/{}",
+        BASE_URL,
+        ticket["id"].as_str().unwrap()
+    );
+
+    // 基于最新版本的修改成功，版本号递增
+    let first = client
+        .put(&ticket_url)
+        .header("If-Match", "\"1\"")
+        .json(&serde_json::json!({ "title": "第一个人的修改" }))
+        .send()
+        .await
+        .expect("Failed to update ticket");
+    assert_eq!(first.status(), 200);
+    assert_eq!(first.headers()["etag"], "\"2\"");
+
+    // 基于过期版本的修改返回 412 及服务端当前副本
+    let stale = client
+        .put(&ticket_url)
+        .header("If-Match", "\"1\"")
+        .json(&serde_json::json!({ "title": "第二个人的修改" }))
+        .send()
+        .await
+        .expect("Failed to send stale update");
+    assert_eq!(stale.status(), 412);
+    assert_eq!(stale.headers()["etag"], "\"2\"");
+    let body: Value = stale.json().await.expect("Failed to parse error");
+    assert_eq!(body["error"]["current"]["title"], "第一个人的修改");
+    assert_eq!(body["error"]["current"]["version"], 2);
+
+    let stale_delete = client
+        .delete(&ticket_url)
+        .header("If-Match", "\"1\"")
+        .send()
+        .await
+        .expect("Failed to send stale delete");
+    assert_eq!(stale_delete.status(), 412);
+
+    let delete_response = client
+        .delete(&ticket_url)
+        .header("If-Match", "\"2\"")
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+    assert_eq!(delete_response.status(), 204);
+
+    // 标签同样支持 If-Match
+    let tag: Value = client
+        .post(format!("{}/api/v1/tags", BASE_URL))
+        .json(&serde_json::json!({ "name": format!("etag-{}", uuid::Uuid::new_v4()) }))
+        .send()
+        .await
+        .expect("Failed to create tag")
+        .json()
+        .await
+        .expect("Failed to parse tag");
+    let tag_url = format!("{}/api/v1/tags/{}", BASE_URL, tag["id"].as_str().unwrap());
+
+    let stale_tag = client
+        .put(&tag_url)
+        .header("If-Match", "\"0\"")
+        .json(&serde_json::json!({ "color": "#10B981" }))
+        .send()
+        .await
+        .expect("Failed to send stale tag update");
+    assert_eq!(stale_tag.status(), 412);
+
+    let updated_tag = client
+        .put(&tag_url)
+        .header("If-Match", "*")
+        .json(&serde_json::json!({ "color": "#10B981" }))
+        .send()
+        .await
+        .expect("Failed to update tag");
+    assert_eq!(updated_tag.status(), 200);
+    assert_eq!(updated_tag.headers()["etag"], "\"2\"");
+
+    // 工单标签变化同样使工单版本递增
+    let response = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "标签并发测试" }))
+        .send()
+        .await
+        .expect("Failed to create ticket");
+    assert_eq!(response.headers()["etag"], "\"1\"");
+    let ticket: Value = response.json().await.expect("Failed to parse ticket");
+    let ticket_url = format!(
+        "{}/api/v1/tickets/{}",
+        BASE_URL,
+        ticket["id"].as_str().unwrap()
+    );
+    let ticket_tag_url = format!("{}/tags/{}", ticket_url, tag["id"].as_str().unwrap());
+    let etag = || async {
+        client
+            .get(&ticket_url)
+            .send()
+            .await
+            .expect("Failed to get ticket")
+            .headers()["etag"]
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+
+    let response = client
+        .post(&ticket_tag_url)
+        .send()
+        .await
+        .expect("Failed to add tag");
+    assert_eq!(response.status(), 200);
+    assert_eq!(etag().await, "\"2\"");
+
+    let stale = client
+        .put(&ticket_url)
+        .header("If-Match", "\"1\"")
+        .json(&serde_json::json!({ "title": "覆盖标签修改" }))
+        .send()
+        .await
+        .expect("Failed to send stale update");
+    assert_eq!(stale.status(), 412);
+
+    // 标签未变化时版本不变
+    client
+        .post(&ticket_tag_url)
+        .send()
+        .await
+        .expect("Failed to add tag");
+    assert_eq!(etag().await, "\"2\"");
+
+    client
+        .delete(&ticket_tag_url)
+        .send()
+        .await
+        .expect("Failed to remove tag");
+    assert_eq!(etag().await, "\"3\"");
+
+    client
+        .put(format!("{}/tags", ticket_url))
+        .json(&serde_json::json!({ "tag_ids": [tag["id"]] }))
+        .send()
+        .await
+        .expect("Failed to set tags");
+    assert_eq!(etag().await, "\"4\"");
+
+    // 工单标签接口同样遵循 If-Match，响应携带工单的新 ETag
+    for stale in [
+        client.delete(&ticket_tag_url),
+        client.post(&ticket_tag_url),
+        client
+            .put(format!("{}/tags", ticket_url))
+            .json(&serde_json::json!({ "tag_ids": [] })),
+    ] {
+        let response = stale
+            .header("If-Match", "\"3\"")
+            .send()
+            .await
+            .expect("Failed to send stale tag change");
+        assert_eq!(response.status(), 412);
+        assert_eq!(response.headers()["etag"], "\"4\"");
+    }
+    assert_eq!(etag().await, "\"4\"");
+
+    let response = client
+        .delete(&ticket_tag_url)
+        .header("If-Match", "\"4\"")
+        .send()
+        .await
+        .expect("Failed to remove tag");
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["etag"], "\"5\"");
+
+    client
+        .delete(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+
+    client
+        .delete(&tag_url)
+        .send()
+        .await
+        .expect("Failed to delete tag");
+}
+
+#[tokio::test]
+async fn test_trash_and_restore() {
+    let client = authorized_client().await;
+
+    let title = format!("回收站测试-{}", uuid::Uuid::new_v4());
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": title }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let ticket_id = ticket["id"].as_str().unwrap().to_string();
+    let ticket_url = format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id);
+
+    let comment: Value = client
+        .post(format!("{}/comments", ticket_url))
+        .json(&serde_json::json!({ "content": "删除前的评论" }))
+        .send()
+        .await
+        .expect("Failed to create comment")
+        .json()
+        .await
+        .expect("Failed to parse comment");
+    let comment_url = format!(
+        "{}/comments/{}",
+        ticket_url,
+        comment["id"].as_str().unwrap()
+    );
+
+    let delete_response = client
+        .delete(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+    assert_eq!(delete_response.status(), 204);
+
+    // 删除后从详情与搜索中消失，出现在回收站中
+    let get_response = client
+        .get(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to get ticket");
+    assert_eq!(get_response.status(), 404);
+
+    let found: Vec<Value> = client
+        .get(format!("{}/api/v1/tickets/search", BASE_URL))
+        .query(&[("q", title.as_str())])
+        .send()
+        .await
+        .expect("Failed to search tickets")
+        .json()
+        .await
+        .expect("Failed to parse search results");
+    assert!(found.is_empty());
+
+    let trash: Value = client
+        .get(format!("{}/api/v1/trash?limit=100", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to list trash")
+        .json()
+        .await
+        .expect("Failed to parse trash");
+    let trashed = trash["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["id"] == ticket_id.as_str())
+        .expect("Deleted ticket should be in trash");
+    assert!(trashed["deleted_at"].is_string());
+
+    // 回收站中工单的评论不能读取、编辑或删除
+    let response = client
+        .get(&comment_url)
+        .send()
+        .await
+        .expect("Failed to get comment");
+    assert_eq!(response.status(), 404);
+    let response = client
+        .put(&comment_url)
+        .json(&serde_json::json!({ "content": "删除后的修改" }))
+        .send()
+        .await
+        .expect("Failed to update comment");
+    assert_eq!(response.status(), 404);
+    let response = client
+        .delete(&comment_url)
+        .send()
+        .await
+        .expect("Failed to delete comment");
+    assert_eq!(response.status(), 404);
+
+    // 恢复后评论仍在
+    let restored: Value = client
+        .post(format!("{}/restore", ticket_url))
+        .send()
+        .await
+        .expect("Failed to restore ticket")
+        .json()
+        .await
+        .expect("Failed to parse restored ticket");
+    assert!(restored["deleted_at"].is_null());
+
+    let details: Value = client
+        .get(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to get ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    assert_eq!(details["comments"].as_array().unwrap().len(), 1);
+    assert_eq!(details["comments"][0]["content"], "删除前的评论");
+
+    let restore_again = client
+        .post(format!("{}/restore", ticket_url))
+        .send()
+        .await
+        .expect("Failed to send restore");
+    assert_eq!(restore_again.status(), 409);
+
+    client
+        .delete(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+}
+
+#[tokio::test]
+async fn test_full_text_search() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let create = |title: String, description: String| {
+        let client = client.clone();
+        async move {
+            let ticket: Value = client
+                .post(format!("{}/api/v1/tickets", BASE_URL))
+                .json(&serde_json::json!({ "title": title, "description": description }))
+                .send()
+                .await
+                .expect("Failed to create ticket")
+                .json()
+                .await
+                .expect("Failed to parse ticket");
+            ticket["id"].as_str().unwrap().to_string()
+        }
+    };
+
+    let title_hit = create(
+        format!("Payment gateway timeout {} <script>", marker),
+        "Checkout requests hang for minutes".to_string(),
+    )
+    .await;
+    let description_hit = create(
+        format!("Checkout follow-up {}", marker),
+        format!("The payment gateway responds slowly {}", marker),
+    )
+    .await;
+    let chinese_hit = create(
+        format!("用户登录失败 {}", marker),
+        "输入正确密码后仍提示登录失败".to_string(),
+    )
+    .await;
+
+    client
+        .post(format!(
+            "{}/api/v1/tickets/{}/comments",
+            BASE_URL, description_hit
+        ))
+        .json(&serde_json::json!({ "content": format!("reproduced by zebrafish{}", marker) }))
+        .send()
+        .await
+        .expect("Failed to create comment");
+
+    let search = |q: String| {
+        let client = client.clone();
+        async move {
+            let hits: Vec<Value> = client
+                .get(format!("{}/api/v1/tickets/search", BASE_URL))
+                .query(&[("q", q.as_str())])
+                .send()
+                .await
+                .expect("Failed to search tickets")
+                .json()
+                .await
+                .expect("Failed to parse search results");
+            hits
+        }
+    };
+    let ids = |hits: &[Value]| -> Vec<String> {
+        hits.iter()
+            .map(|hit| hit["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // 标题命中的权重高于描述命中，且返回高亮片段
+    let hits = search(format!("\"payment gateway\" {}", marker)).await;
+    assert_eq!(ids(&hits), vec![title_hit.clone(), description_hit.clone()]);
+    assert!(hits[0]["rank"].as_f64().unwrap() > hits[1]["rank"].as_f64().unwrap());
+    assert!(hits[0]["highlights"]["title"]
+        .as_str()
+        .unwrap()
+        .contains("<mark>Payment</mark>"));
+    assert!(hits[1]["highlights"]["description"]
+        .as_str()
+        .unwrap()
+        .contains("<mark>"));
+
+    // 高亮片段中的原文会被转义
+    let hits = search(format!("checkout {} -follow", marker)).await;
+    assert_eq!(ids(&hits), vec![title_hit.clone()]);
+    assert!(hits[0]["highlights"]["title"]
+        .as_str()
+        .unwrap()
+        .contains("&lt;script&gt;"));
+
+    // 评论内容参与检索，支持前缀匹配
+    let hits = search(format!("zebrafish{}*", &marker[..8])).await;
+    assert_eq!(ids(&hits), vec![description_hit.clone()]);
+
+    // 中文子串回退匹配
+    let hits = search(format!("登录 {}", marker)).await;
+    assert_eq!(ids(&hits), vec![chinese_hit.clone()]);
+    assert!(hits[0]["highlights"]["title"]
+        .as_str()
+        .unwrap()
+        .contains("<mark>登录</mark>"));
+
+    // 列表接口的 search 参数使用同一套匹配规则
+    let list: Value = client
+        .get(format!("{}/api/v1/tickets", BASE_URL))
+        .query(&[("search", format!("gateway {} -follow", marker))])
+        .send()
+        .await
+        .expect("Failed to list tickets")
+        .json()
+        .await
+        .expect("Failed to parse ticket list");
+    let listed: Vec<&str> = list["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(listed, vec![title_hit.as_str()]);
+}
+
+#[tokio::test]
+async fn test_ticket_query_language() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let me: Value = client
+        .get(format!("{}/api/v1/auth/me", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to get current user")
+        .json()
+        .await
+        .expect("Failed to parse current user");
+    let tag: Value = client
+        .post(format!("{}/api/v1/tags", BASE_URL))
+        .json(&serde_json::json!({ "name": format!("ql-{}", &marker[..12]) }))
+        .send()
+        .await
+        .expect("Failed to create tag")
+        .json()
+        .await
+        .expect("Failed to parse tag");
+
+    let create = |title: &str, priority: &str, assigned: bool, tagged: bool| {
+        let mut body = serde_json::json!({
+            "title": format!("{} {}", title, marker),
+            "priority": priority,
+        });
+        if assigned {
+            body["assignee_id"] = me["id"].clone();
+        }
+        if tagged {
+            body["tag_ids"] = serde_json::json!([tag["id"]]);
+        }
+        let client = client.clone();
+        async move {
+            let ticket: Value = client
+                .post(format!("{}/api/v1/tickets", BASE_URL))
+                .json(&body)
+                .send()
+                .await
+                .expect("Failed to create ticket")
+                .json()
+                .await
+                .expect("Failed to parse ticket");
+            ticket["id"].as_str().unwrap().to_string()
+        }
+    };
+
+    let urgent_mine = create("login bug", "urgent", true, true).await;
+    let high_unassigned = create("login bug", "high", false, true).await;
+    let low_mine = create("login bug", "low", true, true).await;
+    let untagged = create("payment bug", "urgent", true, false).await;
+
+    let list = |q: String| {
+        let client = client.clone();
+        async move {
+            client
+                .get(format!("{}/api/v1/tickets", BASE_URL))
+                .query(&[("q", q.as_str()), ("limit", "100")])
+                .send()
+                .await
+                .expect("Failed to list tickets")
+        }
+    };
+    let ids = |result: Value| -> Vec<String> {
+        let mut ids: Vec<String> = result["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+    let sorted = |mut ids: Vec<String>| {
+        ids.sort();
+        ids
+    };
+
+    let tag_name = tag["name"].as_str().unwrap();
+    let result: Value = list(format!(
+        "{} tag:{} priority>=high status:open,in_progress",
+        marker, tag_name
+    ))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(
+        ids(result),
+        sorted(vec![urgent_mine.clone(), high_unassigned.clone()])
+    );
+
+    // 取反条件包含字段为空的工单
+    let result: Value = list(format!("{} -assignee:me \"login bug\"", marker))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(ids(result), vec![high_unassigned.clone()]);
+
+    let result: Value = list(format!(
+        "{} assignee:me -tag:{} created>=2000-01-01",
+        marker, tag_name
+    ))
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(ids(result), vec![untagged.clone()]);
+
+    let result: Value = list(format!("{} priority:low,medium created<2000-01-01", marker))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(result["total"], 0);
+    let result: Value = list(format!("{} priority:low,medium", marker))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(ids(result), vec![low_mine.clone()]);
+
+    // 解析错误指出出错的词
+    let response = list("status:open priority>=hihg".to_string()).await;
+    assert_eq!(response.status(), 400);
+    let error: Value = response.json().await.unwrap();
+    let message = error["error"]["message"].as_str().unwrap();
+    assert!(message.contains("第 13 个字符"), "{}", message);
+    assert!(message.contains("priority>=hihg"), "{}", message);
+
+    for bad in [
+        "colour:red",
+        "assignee:",
+        "created:yesterday",
+        "\"login bug",
+    ] {
+        let response = list(bad.to_string()).await;
+        assert_eq!(response.status(), 400, "{}", bad);
+    }
+}
+
+#[tokio::test]
+async fn test_saved_views() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let mut ids = Vec::new();
+    for (title, priority) in [("b", "urgent"), ("a", "urgent"), ("c", "low")] {
+        let ticket: Value = client
+            .post(format!("{}/api/v1/tickets", BASE_URL))
+            .json(&serde_json::json!({
+                "title": format!("{} 视图测试 {}", title, marker),
+                "priority": priority,
+            }))
+            .send()
+            .await
+            .expect("Failed to create ticket")
+            .json()
+            .await
+            .expect("Failed to parse ticket");
+        ids.push(ticket["id"].as_str().unwrap().to_string());
+    }
+
+    let response = client
+        .post(format!("{}/api/v1/views", BASE_URL))
+        .json(&serde_json::json!({
+            "name": format!("紧急工单 {}", marker),
+            "filters": {
+                "priority": "urgent",
+                "q": marker,
+                "sort_by": "title",
+                "sort_order": "asc"
+            }
+        }))
+        .send()
+        .await
+        .expect("Failed to create view");
+    assert_eq!(response.status(), 200);
+    let view: Value = response.json().await.expect("Failed to parse view");
+    let view_id = view["id"].as_str().unwrap().to_string();
+    assert_eq!(view["shared"], false);
+    assert_eq!(view["filters"]["priority"], "urgent");
+
+    // 同名视图与无效的查询语言被拒绝
+    let duplicate = client
+        .post(format!("{}/api/v1/views", BASE_URL))
+        .json(&serde_json::json!({ "name": view["name"] }))
+        .send()
+        .await
+        .expect("Failed to create view");
+    assert_eq!(duplicate.status(), 409);
+    let invalid = client
+        .post(format!("{}/api/v1/views", BASE_URL))
+        .json(&serde_json::json!({ "name": "无效视图", "filters": { "q": "priority>=hihg" } }))
+        .send()
+        .await
+        .expect("Failed to create view");
+    assert_eq!(invalid.status(), 400);
+
+    let list_ids = |result: &Value| -> Vec<String> {
+        result["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // 应用视图：筛选与排序均来自视图
+    let result: Value = client
+        .get(format!("{}/api/v1/tickets?view={}", BASE_URL, view_id))
+        .send()
+        .await
+        .expect("Failed to list tickets with view")
+        .json()
+        .await
+        .expect("Failed to parse ticket list");
+    assert_eq!(result["total"], 2);
+    assert_eq!(list_ids(&result), vec![ids[1].clone(), ids[0].clone()]);
+
+    // 显式参数覆盖视图中的同名条件
+    let result: Value = client
+        .get(format!(
+            "{}/api/v1/tickets?view={}&priority=low",
+            BASE_URL, view_id
+        ))
+        .send()
+        .await
+        .expect("Failed to list tickets with view")
+        .json()
+        .await
+        .expect("Failed to parse ticket list");
+    assert_eq!(list_ids(&result), vec![ids[2].clone()]);
+
+    // 其他用户看不到私有视图；共享后可以使用但不能修改
+    let email = format!("views-{}@example.com", uuid::Uuid::new_v4());
+    client
+        .post(format!("{}/api/v1/users", BASE_URL))
+        .json(&serde_json::json!({
+            "name": "视图用户",
+            "email": email,
+            "password": "password123",
+            "role": "agent"
+        }))
+        .send()
+        .await
+        .expect("Failed to create user");
+    let other = client_with_token(&login(&email, "password123").await);
+
+    let hidden = other
+        .get(format!("{}/api/v1/tickets?view={}", BASE_URL, view_id))
+        .send()
+        .await
+        .expect("Failed to list tickets with view");
+    assert_eq!(hidden.status(), 404);
+
+    let updated: Value = client
+        .put(format!("{}/api/v1/views/{}", BASE_URL, view_id))
+        .json(&serde_json::json!({ "shared": true }))
+        .send()
+        .await
+        .expect("Failed to update view")
+        .json()
+        .await
+        .expect("Failed to parse view");
+    assert_eq!(updated["shared"], true);
+
+    let shared: Vec<Value> = other
+        .get(format!("{}/api/v1/views", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to list views")
+        .json()
+        .await
+        .expect("Failed to parse views");
+    assert!(shared.iter().any(|v| v["id"] == view_id.as_str()));
+
+    let result: Value = other
+        .get(format!("{}/api/v1/tickets?view={}", BASE_URL, view_id))
+        .send()
+        .await
+        .expect("Failed to list tickets with view")
+        .json()
+        .await
+        .expect("Failed to parse ticket list");
+    assert_eq!(result["total"], 2);
+
+    let forbidden = other
+        .delete(format!("{}/api/v1/views/{}", BASE_URL, view_id))
+        .send()
+        .await
+        .expect("Failed to delete view");
+    assert_eq!(forbidden.status(), 403);
+
+    let deleted = client
+        .delete(format!("{}/api/v1/views/{}", BASE_URL, view_id))
+        .send()
+        .await
+        .expect("Failed to delete view");
+    assert_eq!(deleted.status(), 204);
+}
+
+#[tokio::test]
+async fn test_cursor_pagination() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let create = |title: String| {
+        let client = client.clone();
+        async move {
+            let ticket: Value = client
+                .post(format!("{}/api/v1/tickets", BASE_URL))
+                .json(&serde_json::json!({ "title": title }))
+                .send()
+                .await
+                .expect("Failed to create ticket")
+                .json()
+                .await
+                .expect("Failed to parse ticket");
+            ticket["id"].as_str().unwrap().to_string()
+        }
+    };
+
+    let mut created = Vec::new();
+    for index in 0..5 {
+        created.push(create(format!("游标分页 {} {}", index, marker)).await);
+    }
+
+    let fetch_page = |params: Vec<(&'static str, String)>| {
+        let client = client.clone();
+        async move {
+            client
+                .get(format!("{}/api/v1/tickets", BASE_URL))
+                .query(&params)
+                .send()
+                .await
+                .expect("Failed to list tickets")
+        }
+    };
+
+    // 按创建时间倒序翻页，翻页期间新建的工单不会造成重复或遗漏
+    let mut seen = Vec::new();
+    let mut cursor = String::new();
+    loop {
+        let page: Value = fetch_page(vec![
+            ("q", marker.clone()),
+            ("limit", "2".to_string()),
+            ("cursor", cursor.clone()),
+            ("count", "false".to_string()),
+        ])
+        .await
+        .json()
+        .await
+        .expect("Failed to parse page");
+        assert!(page.get("total").is_none());
+        for ticket in page["data"].as_array().unwrap() {
+            seen.push(ticket["id"].as_str().unwrap().to_string());
+        }
+        if seen.len() == 2 {
+            create(format!("翻页中新建 {}", marker)).await;
+        }
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = next.to_string(),
+            None => break,
+        }
+    }
+    let mut expected = created.clone();
+    expected.reverse();
+    assert_eq!(seen, expected);
+
+    // 其他排序键同样支持，并可返回总数
+    let first: Value = fetch_page(vec![
+        ("q", marker.clone()),
+        ("limit", "3".to_string()),
+        ("cursor", String::new()),
+        ("sort_by", "title".to_string()),
+        ("sort_order", "asc".to_string()),
+    ])
+    .await
+    .json()
+    .await
+    .expect("Failed to parse page");
+    assert_eq!(first["total"], 6);
+    let next_cursor = first["next_cursor"].as_str().unwrap().to_string();
+    let second: Value = fetch_page(vec![
+        ("q", marker.clone()),
+        ("limit", "3".to_string()),
+        ("cursor", next_cursor.clone()),
+        ("sort_by", "title".to_string()),
+        ("sort_order", "asc".to_string()),
+    ])
+    .await
+    .json()
+    .await
+    .expect("Failed to parse page");
+    assert!(second["next_cursor"].is_null());
+    let titles: Vec<&str> = first["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .chain(second["data"].as_array().unwrap())
+        .map(|t| t["title"].as_str().unwrap())
+        .collect();
+    let mut sorted_titles = titles.clone();
+    sorted_titles.sort();
+    assert_eq!(titles.len(), 6);
+    assert_eq!(titles, sorted_titles);
+
+    // 游标与排序条件不一致或被篡改时返回 400
+    let mismatched = fetch_page(vec![
+        ("q", marker.clone()),
+        ("cursor", next_cursor),
+        ("sort_by", "created_at".to_string()),
+    ])
+    .await;
+    assert_eq!(mismatched.status(), 400);
+    let invalid = fetch_page(vec![("cursor", "not-a-cursor".to_string())]).await;
+    assert_eq!(invalid.status(), 400);
+
+    // 不带 cursor 时仍为偏移分页
+    let offset_page: Value = fetch_page(vec![("q", marker.clone()), ("page", "2".to_string())])
+        .await
+        .json()
+        .await
+        .expect("Failed to parse page");
+    assert_eq!(offset_page["page"], 2);
+    assert_eq!(offset_page["total"], 6);
+}
+
+#[tokio::test]
+async fn test_multi_field_sorting() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let mut ids = Vec::new();
+    for (priority, status, due_date) in [
+        ("low", "resolved", Some("2030-01-03T00:00:00Z")),
+        ("urgent", "open", None),
+        ("high", "in_progress", Some("2030-01-01T00:00:00Z")),
+        ("urgent", "in_progress", None),
+    ] {
+        let ticket: Value = client
+            .post(format!("{}/api/v1/tickets", BASE_URL))
+            .json(&serde_json::json!({
+                "title": format!("排序测试 {}", marker),
+                "priority": priority,
+                "due_date": due_date,
+            }))
+            .send()
+            .await
+            .expect("Failed to create ticket")
+            .json()
+            .await
+            .expect("Failed to parse ticket");
+        let id = ticket["id"].as_str().unwrap().to_string();
+        if status != "open" {
+            client
+                .put(format!("{}/api/v1/tickets/{}", BASE_URL, id))
+                .json(&serde_json::json!({ "status": status }))
+                .send()
+                .await
+                .expect("Failed to update ticket");
+        }
+        ids.push(id);
+    }
+
+    let list = |sort: &str, extra: Vec<(&str, String)>| {
+        let mut params = vec![("q", marker.clone()), ("sort", sort.to_string())];
+        params.extend(extra);
+        let params: Vec<(String, String)> = params
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        let client = client.clone();
+        async move {
+            client
+                .get(format!("{}/api/v1/tickets", BASE_URL))
+                .query(&params)
+                .send()
+                .await
+                .expect("Failed to list tickets")
+        }
+    };
+    let ids_of = |page: &Value| -> Vec<String> {
+        page["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // 优先级按严重程度排序，相同优先级按创建时间
+    let page: Value = list("-priority,created_at", vec![])
+        .await
+        .json()
+        .await
+        .unwrap();
+    let by_priority = vec![
+        ids[1].clone(),
+        ids[3].clone(),
+        ids[2].clone(),
+        ids[0].clone(),
+    ];
+    assert_eq!(ids_of(&page), by_priority);
+
+    // 状态按工作流中的顺序排序（open、in_progress、resolved），而非字母顺序
+    let page: Value = list("status,-created_at", vec![])
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        ids_of(&page),
+        vec![
+            ids[1].clone(),
+            ids[3].clone(),
+            ids[2].clone(),
+            ids[0].clone()
+        ]
+    );
+
+    // 多字段排序同样适用于游标分页
+    let mut seen = Vec::new();
+    let mut cursor = String::new();
+    loop {
+        let page: Value = list(
+            "-priority,created_at",
+            vec![("limit", "1".to_string()), ("cursor", cursor.clone())],
+        )
+        .await
+        .json()
+        .await
+        .unwrap();
+        seen.extend(ids_of(&page));
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = next.to_string(),
+            None => break,
+        }
+    }
+    assert_eq!(seen, by_priority);
+
+    // 截止时间排序：未设置截止时间的工单无论升序降序都排在最后，游标分页结果一致
+    for (sort, expected) in [
+        ("due_date,created_at", [2, 0, 1, 3]),
+        ("-due_date,created_at", [0, 2, 1, 3]),
+        ("-due_date,-created_at", [0, 2, 3, 1]),
+    ] {
+        let expected: Vec<String> = expected.iter().map(|&i| ids[i].clone()).collect();
+        let page: Value = list(sort, vec![]).await.json().await.unwrap();
+        assert_eq!(ids_of(&page), expected, "{}", sort);
+
+        let mut seen = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let page: Value = list(
+                sort,
+                vec![("limit", "1".to_string()), ("cursor", cursor.clone())],
+            )
+            .await
+            .json()
+            .await
+            .unwrap();
+            seen.extend(ids_of(&page));
+            match page["next_cursor"].as_str() {
+                Some(next) => cursor = next.to_string(),
+                None => break,
+            }
+        }
+        assert_eq!(seen, expected, "{}", sort);
+    }
+
+    // 未知字段返回 400
+    for sort in ["-severity", "priority,,title", "title,-title"] {
+        let response = list(sort, vec![]).await;
+        assert_eq!(response.status(), 400, "{}", sort);
+    }
+    let response = list("-severity", vec![]).await;
+    let error: Value = response.json().await.unwrap();
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("severity"));
+
+    let response = client
+        .get(format!("{}/api/v1/tickets?sort_by=severity", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to list tickets");
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_tag_filters() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let mut tags = Vec::new();
+    for name in ["alpha", "beta", "gamma"] {
+        let tag: Value = client
+            .post(format!("{}/api/v1/tags", BASE_URL))
+            .json(&serde_json::json!({ "name": format!("{}-{}", name, marker) }))
+            .send()
+            .await
+            .expect("Failed to create tag")
+            .json()
+            .await
+            .expect("Failed to parse tag");
+        tags.push((
+            tag["id"].as_str().unwrap().to_string(),
+            tag["name"].as_str().unwrap().to_string(),
+        ));
+    }
+
+    // 工单标签: [alpha, beta], [alpha], [beta, gamma], []
+    let mut tickets = Vec::new();
+    for tag_indexes in [vec![0, 1], vec![0], vec![1, 2], vec![]] {
+        let tag_ids: Vec<&str> = tag_indexes.iter().map(|i| tags[*i].0.as_str()).collect();
+        let ticket: Value = client
+            .post(format!("{}/api/v1/tickets", BASE_URL))
+            .json(&serde_json::json!({
+                "title": format!("标签筛选 {}", marker),
+                "tag_ids": tag_ids,
+            }))
+            .send()
+            .await
+            .expect("Failed to create ticket")
+            .json()
+            .await
+            .expect("Failed to parse ticket");
+        tickets.push(ticket["id"].as_str().unwrap().to_string());
+    }
+
+    let list = |params: Vec<(&str, String)>| {
+        let client = client.clone();
+        let mut params: Vec<(String, String)> = params
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        params.push(("q".to_string(), marker.clone()));
+        params.push(("sort".to_string(), "created_at".to_string()));
+        async move {
+            let response = client
+                .get(format!("{}/api/v1/tickets", BASE_URL))
+                .query(&params)
+                .send()
+                .await
+                .expect("Failed to list tickets");
+            assert_eq!(response.status(), 200);
+            let page: Value = response.json().await.unwrap();
+            let ids: Vec<String> = page["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["id"].as_str().unwrap().to_string())
+                .collect();
+            assert_eq!(page["total"].as_i64().unwrap(), ids.len() as i64);
+            ids
+        }
+    };
+
+    // AND：名称不区分大小写，可与ID混用
+    let ids = list(vec![(
+        "tags_all",
+        format!("{},{}", tags[0].1.to_uppercase(), tags[1].0),
+    )])
+    .await;
+    assert_eq!(ids, vec![tickets[0].clone()]);
+
+    // OR：带有多个匹配标签的工单只出现一次
+    let ids = list(vec![("tags_any", format!("{},{}", tags[0].1, tags[1].1))]).await;
+    assert_eq!(ids, tickets[..3].to_vec());
+
+    // NOT：包含未打标签的工单
+    let ids = list(vec![("tags_none", tags[2].1.clone())]).await;
+    assert_eq!(
+        ids,
+        vec![tickets[0].clone(), tickets[1].clone(), tickets[3].clone()]
+    );
+
+    // 组合使用
+    let ids = list(vec![
+        ("tags_any", format!("{},{}", tags[0].0, tags[2].0)),
+        ("tags_none", tags[1].1.clone()),
+    ])
+    .await;
+    assert_eq!(ids, vec![tickets[1].clone()]);
+
+    // 旧参数 tag_ids 与 tags_any 相同
+    let ids = list(vec![("tag_ids", format!("{},{}", tags[0].0, tags[1].0))]).await;
+    assert_eq!(ids, tickets[..3].to_vec());
+
+    // 不存在的标签名称不匹配任何工单
+    let ids = list(vec![("tags_all", format!("missing-{}", marker))]).await;
+    assert!(ids.is_empty());
+}
+
+#[tokio::test]
+async fn test_tag_hierarchy() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let create_tag = |name: &str, parent_id: Option<String>| {
+        let client = client.clone();
+        let payload = serde_json::json!({
+            "name": format!("{}-{}", name, marker),
+            "parent_id": parent_id,
+        });
+        async move {
+            let response = client
+                .post(format!("{}/api/v1/tags", BASE_URL))
+                .json(&payload)
+                .send()
+                .await
+                .expect("Failed to create tag");
+            assert_eq!(response.status(), 200);
+            let tag: Value = response.json().await.expect("Failed to parse tag");
+            tag["id"].as_str().unwrap().to_string()
+        }
+    };
+    let move_tag = |id: &str, parent_id: Option<&str>| {
+        client
+            .put(format!("{}/api/v1/tags/{}/move", BASE_URL, id))
+            .json(&serde_json::json!({ "parent_id": parent_id }))
+            .send()
+    };
+
+    let area = create_tag("area", None).await;
+    let backend = create_tag("backend", Some(area.clone())).await;
+    let db = create_tag("db", Some(backend.clone())).await;
+
+    // 子树包含根节点，路径从根标签开始
+    let subtree: Value = client
+        .get(format!("{}/api/v1/tags/{}/subtree", BASE_URL, area))
+        .send()
+        .await
+        .expect("Failed to get subtree")
+        .json()
+        .await
+        .unwrap();
+    let nodes = subtree.as_array().unwrap();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(nodes[2]["id"], db.as_str());
+    assert_eq!(nodes[2]["depth"], 2);
+    assert_eq!(
+        nodes[2]["path"],
+        format!("area-{m}/backend-{m}/db-{m}", m = marker).as_str()
+    );
+
+    // 不能移动到自身或子标签下
+    assert_eq!(move_tag(&area, Some(&area)).await.unwrap().status(), 400);
+    assert_eq!(move_tag(&area, Some(&db)).await.unwrap().status(), 400);
+    let missing = uuid::Uuid::new_v4().to_string();
+    assert_eq!(move_tag(&db, Some(&missing)).await.unwrap().status(), 404);
+
+    // 按标签筛选工单时可包含子标签
+    let mut tickets = Vec::new();
+    for tag_id in [&db, &area] {
+        let ticket: Value = client
+            .post(format!("{}/api/v1/tickets", BASE_URL))
+            .json(&serde_json::json!({
+                "title": format!("层级标签 {}", marker),
+                "tag_ids": [tag_id],
+            }))
+            .send()
+            .await
+            .expect("Failed to create ticket")
+            .json()
+            .await
+            .unwrap();
+        tickets.push(ticket["id"].as_str().unwrap().to_string());
+    }
+    let count = |params: Vec<(&str, String)>| {
+        let request = client
+            .get(format!("{}/api/v1/tickets", BASE_URL))
+            .query(&params);
+        async move {
+            let page: Value = request.send().await.unwrap().json().await.unwrap();
+            page["total"].as_i64().unwrap()
+        }
+    };
+    assert_eq!(count(vec![("tags_any", backend.clone())]).await, 0);
+    assert_eq!(
+        count(vec![
+            ("tags_any", format!("backend-{}", marker)),
+            ("include_descendant_tags", "true".to_string()),
+        ])
+        .await,
+        1
+    );
+    assert_eq!(
+        count(vec![
+            ("tags_all", area.clone()),
+            ("include_descendant_tags", "true".to_string()),
+        ])
+        .await,
+        2
+    );
+
+    // 移动为根标签后不再属于原子树
+    let moved: Value = move_tag(&db, None).await.unwrap().json().await.unwrap();
+    assert!(moved["parent_id"].is_null());
+    assert_eq!(
+        count(vec![
+            ("tags_all", area.clone()),
+            ("include_descendant_tags", "true".to_string()),
+        ])
+        .await,
+        1
+    );
+
+    // 删除中间标签时，子标签上移一级
+    assert_eq!(move_tag(&db, Some(&backend)).await.unwrap().status(), 200);
+    let response = client
+        .delete(format!("{}/api/v1/tags/{}", BASE_URL, backend))
+        .send()
+        .await
+        .expect("Failed to delete tag");
+    assert_eq!(response.status(), 204);
+    let tag: Value = client
+        .get(format!("{}/api/v1/tags/{}", BASE_URL, db))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(tag["parent_id"], area.as_str());
+}
+
+#[tokio::test]
+async fn test_tag_merge_and_aliases() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let create_tag = |name: String, parent_id: Option<String>| {
+        let request = client
+            .post(format!("{}/api/v1/tags", BASE_URL))
+            .json(&serde_json::json!({ "name": name, "parent_id": parent_id }));
+        async move {
+            let tag: Value = request
+                .send()
+                .await
+                .expect("Failed to create tag")
+                .json()
+                .await
+                .expect("Failed to parse tag");
+            tag["id"].as_str().unwrap().to_string()
+        }
+    };
+
+    let target = create_tag(format!("bug-{}", marker), None).await;
+    let upper = create_tag(format!("Bug-{}", marker), None).await;
+    let chinese = create_tag(format!("缺陷-{}", marker), None).await;
+    let child = create_tag(format!("crash-{}", marker), Some(upper.clone())).await;
+
+    // 工单标签: [bug, Bug], [Bug], [缺陷]
+    let mut tickets = Vec::new();
+    for tag_ids in [
+        vec![target.clone(), upper.clone()],
+        vec![upper.clone()],
+        vec![chinese.clone()],
+    ] {
+        let ticket: Value = client
+            .post(format!("{}/api/v1/tickets", BASE_URL))
+            .json(&serde_json::json!({
+                "title": format!("合并标签 {}", marker),
+                "tag_ids": tag_ids,
+            }))
+            .send()
+            .await
+            .expect("Failed to create ticket")
+            .json()
+            .await
+            .unwrap();
+        tickets.push(ticket["id"].as_str().unwrap().to_string());
+    }
+
+    let ticket_url = format!("{}/api/v1/tickets/{}", BASE_URL, tickets[1]);
+    let ticket_etag = |url: String| {
+        let request = client.get(url);
+        async move {
+            let response = request.send().await.expect("Failed to get ticket");
+            response.headers()["etag"].to_str().unwrap().to_string()
+        }
+    };
+    let etag_before_merge = ticket_etag(ticket_url.clone()).await;
+
+    let merge_url = format!("{}/api/v1/tags/{}/merge", BASE_URL, target);
+    let response = client
+        .post(&merge_url)
+        .json(&serde_json::json!({ "source_ids": [target] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .post(&merge_url)
+        .json(&serde_json::json!({ "source_ids": [upper, chinese] }))
+        .send()
+        .await
+        .expect("Failed to merge tags");
+    assert_eq!(response.status(), 200);
+    let result: Value = response.json().await.unwrap();
+    assert_eq!(result["tag"]["id"], target.as_str());
+    assert_eq!(result["retagged_tickets"], 3);
+    let aliases: Vec<&str> = result["aliases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|alias| alias["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(aliases.len(), 2);
+    assert!(aliases.contains(&format!("Bug-{}", marker).as_str()));
+
+    // 源标签已删除，子标签归入目标标签
+    let response = client
+        .get(format!("{}/api/v1/tags/{}", BASE_URL, upper))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    let child_tag: Value = client
+        .get(format!("{}/api/v1/tags/{}", BASE_URL, child))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(child_tag["parent_id"], target.as_str());
+
+    // 合并改变了工单的标签集合，工单版本随之递增
+    let etag_after_merge = ticket_etag(ticket_url.clone()).await;
+    assert_ne!(etag_after_merge, etag_before_merge);
+
+    // 工单只保留一个目标标签，并记录了历史
+    let ticket_tags: Value = client
+        .get(format!("{}/api/v1/tickets/{}/tags", BASE_URL, tickets[0]))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(ticket_tags.as_array().unwrap().len(), 1);
+    let history: Value = client
+        .get(format!(
+            "{}/api/v1/tickets/{}/history",
+            BASE_URL, tickets[2]
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let event_types: Vec<&str> = history["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["event_type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        event_types,
+        ["created", "tag_added", "tag_removed", "tag_added"]
+    );
+
+    // 旧名称仍可用于筛选与搜索
+    let page: Value = client
+        .get(format!("{}/api/v1/tickets", BASE_URL))
+        .query(&[("tags_any", format!("缺陷-{}", marker))])
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["total"], 3);
+    let found: Value = client
+        .get(format!("{}/api/v1/tags/search", BASE_URL))
+        .query(&[("q", format!("缺陷-{}", marker))])
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(found[0]["id"], target.as_str());
+
+    // 别名占用的名称不能再创建新标签
+    let response = client
+        .post(format!("{}/api/v1/tags", BASE_URL))
+        .json(&serde_json::json!({ "name": format!("Bug-{}", marker) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+
+    // 改名时保留旧名称为别名
+    let response = client
+        .put(format!("{}/api/v1/tags/{}", BASE_URL, target))
+        .json(&serde_json::json!({
+            "name": format!("defect-{}", marker),
+            "keep_alias": true,
+        }))
+        .send()
+        .await
+        .expect("Failed to rename tag");
+    assert_eq!(response.status(), 200);
+    let aliases: Value = client
+        .get(format!("{}/api/v1/tags/{}/aliases", BASE_URL, target))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(aliases.as_array().unwrap().len(), 3);
+
+    // 删除标签同样使受影响工单的旧 ETag 失效
+    let response = client
+        .delete(format!("{}/api/v1/tags/{}", BASE_URL, target))
+        .send()
+        .await
+        .expect("Failed to delete tag");
+    assert_eq!(response.status(), 204);
+    assert_ne!(ticket_etag(ticket_url.clone()).await, etag_after_merge);
+    let response = client
+        .put(&ticket_url)
+        .header("If-Match", etag_after_merge.as_str())
+        .json(&serde_json::json!({ "title": "基于旧版本的修改" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 412);
+}
+
+#[tokio::test]
+async fn test_tag_colors() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let create_tag = |name: &str, color: Option<&str>| {
+        client
+            .post(format!("{}/api/v1/tags", BASE_URL))
+            .json(&serde_json::json!({
+                "name": format!("{}-{}", name, marker),
+                "color": color,
+            }))
+            .send()
+    };
+
+    // 非 #RRGGBB 格式被拒绝
+    for color in ["#12345G", "1234567", "#FFF", "#GGGGGG"] {
+        let response = create_tag("invalid", Some(color)).await.unwrap();
+        assert_eq!(response.status(), 400, "{}", color);
+    }
+
+    // 统一为大写，并根据亮度选择文字颜色
+    let tag: Value = create_tag("light", Some("#ffff00"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(tag["color"], "#FFFF00");
+    assert_eq!(tag["text_color"], "#000000");
+
+    let tag: Value = create_tag("dark", Some("#1e3a8a"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(tag["text_color"], "#FFFFFF");
+
+    let updated: Value = client
+        .put(format!(
+            "{}/api/v1/tags/{}",
+            BASE_URL,
+            tag["id"].as_str().unwrap()
+        ))
+        .json(&serde_json::json!({ "color": "#fde68a" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(updated["color"], "#FDE68A");
+    assert_eq!(updated["text_color"], "#000000");
+
+    // 未指定颜色时从调色板分配
+    let palette: Value = client
+        .get(format!("{}/api/v1/tags/palette", BASE_URL))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let palette: Vec<&str> = palette
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["color"].as_str().unwrap())
+        .collect();
+    assert!(palette.len() >= 8);
+
+    let tag: Value = create_tag("auto", None)
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(palette.contains(&tag["color"].as_str().unwrap()));
+    assert!(tag["text_color"].is_string());
+
+    // 工单详情中的标签同样带有 text_color
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({
+            "title": format!("颜色测试 {}", marker),
+            "tag_ids": [tag["id"]],
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let detail: Value = client
+        .get(format!(
+            "{}/api/v1/tickets/{}",
+            BASE_URL,
+            ticket["id"].as_str().unwrap()
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(detail["tags"][0]["text_color"], tag["text_color"]);
+}
+
+#[tokio::test]
+async fn test_exclusive_tag_groups() {
+    let client = authorized_client().await;
+    let marker = uuid::Uuid::new_v4().simple().to_string();
+
+    let create_group = |name: &str, exclusive: bool| {
+        let request =
+            client
+                .post(format!("{}/api/v1/tag-groups", BASE_URL))
+                .json(&serde_json::json!({
+                    "name": format!("{}-{}", name, marker),
+                    "exclusive": exclusive,
+                }));
+        async move {
+            let group: Value = request
+                .send()
+                .await
+                .expect("Failed to create tag group")
+                .json()
+                .await
+                .expect("Failed to parse tag group");
+            group["id"].as_str().unwrap().to_string()
+        }
+    };
+    let create_tag = |name: &str, group_id: Option<&str>| {
+        let request = client
+            .post(format!("{}/api/v1/tags", BASE_URL))
+            .json(&serde_json::json!({
+                "name": format!("{}-{}", name, marker),
+                "group_id": group_id,
+            }));
+        async move {
+            let tag: Value = request
+                .send()
+                .await
+                .expect("Failed to create tag")
+                .json()
+                .await
+                .expect("Failed to parse tag");
+            tag["id"].as_str().unwrap().to_string()
+        }
+    };
+    let tag_names = |tags: &Value| -> Vec<String> {
+        tags.as_array()
+            .unwrap()
+            .iter()
+            .map(|tag| tag["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let teams = create_group("team", true).await;
+    let team_a = create_tag("team-a", Some(&teams)).await;
+    let team_b = create_tag("team-b", Some(&teams)).await;
+    let other = create_tag("other", None).await;
+
+    let group: Value = client
+        .get(format!("{}/api/v1/tag-groups/{}", BASE_URL, teams))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(group["exclusive"], true);
+    assert_eq!(group["tags"].as_array().unwrap().len(), 2);
+
+    // 创建工单时不能同时选择互斥分组中的多个标签
+    let response = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({
+            "title": format!("互斥分组 {}", marker),
+            "tag_ids": [team_a, team_b],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({
+            "title": format!("互斥分组 {}", marker),
+            "tag_ids": [team_a, other],
+        }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .unwrap();
+    let ticket_url = format!(
+        "{}/api/v1/tickets/{}",
+        BASE_URL,
+        ticket["id"].as_str().unwrap()
+    );
+
+    // 添加同组的另一个标签会替换原有标签
+    let tags: Value = client
+        .post(format!("{}/tags/{}", ticket_url, team_b))
+        .send()
+        .await
+        .expect("Failed to add tag")
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        tag_names(&tags),
+        vec![format!("other-{}", marker), format!("team-b-{}", marker)]
+    );
+
+    let history: Value = client
+        .get(format!("{}/history", ticket_url))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let removed: Vec<&Value> = history["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|event| event["event_type"] == "tag_removed")
+        .collect();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0]["old_value"]["id"], team_a.as_str());
+
+    // 整体替换与更新工单同样校验
+    let response = client
+        .put(format!("{}/tags", ticket_url))
+        .json(&serde_json::json!({ "tag_ids": [team_a, team_b] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let response = client
+        .put(&ticket_url)
+        .json(&serde_json::json!({ "tag_ids": [team_a, team_b] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // 已有工单违反约束时，不能将标签加入互斥分组，也不能将分组设为互斥
+    let response = client
+        .put(format!("{}/api/v1/tags/{}/group", BASE_URL, other))
+        .json(&serde_json::json!({ "group_id": teams }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let areas = create_group("area", false).await;
+    let area_x = create_tag("area-x", Some(&areas)).await;
+    let area_y = create_tag("area-y", Some(&areas)).await;
+    let response = client
+        .put(format!("{}/tags", ticket_url))
+        .json(&serde_json::json!({ "tag_ids": [area_x, area_y] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let response = client
+        .put(format!("{}/api/v1/tag-groups/{}", BASE_URL, areas))
+        .json(&serde_json::json!({ "exclusive": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // 移出分组后不再受约束
+    let tag: Value = client
+        .put(format!("{}/api/v1/tags/{}/group", BASE_URL, team_a))
+        .json(&serde_json::json!({ "group_id": null }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(tag["group_id"].is_null());
+    let response = client
+        .put(format!("{}/tags", ticket_url))
+        .json(&serde_json::json!({ "tag_ids": [team_a, team_b] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_ticket_attachments() {
+    use reqwest::multipart::{Form, Part};
+    use sha2::{Digest, Sha256};
+
+    let client = authorized_client().await;
+
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "附件测试工单" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let ticket_id = ticket["id"].as_str().unwrap().to_string();
+    let attachments_url = format!("{}/api/v1/tickets/{}/attachments", BASE_URL, ticket_id);
+
+    let file_part = |name: &str, content_type: &str, data: Vec<u8>| {
+        Part::bytes(data)
+            .file_name(name.to_string())
+            .mime_str(content_type)
+            .unwrap()
+    };
+
+    // 一次上传截图与日志；日志以通用二进制类型上传，按扩展名识别为 text/plain
+    let png = b"\x89PNG\r\n\x1a\nfake-image".to_vec();
+    let log = "2024-01-01 ERROR 连接超时\n".as_bytes().to_vec();
+    let form = Form::new()
+        .text("note", "ignored")
+        .part(
+            "file",
+            file_part("screenshot.png", "image/png", png.clone()),
+        )
+        .part(
+            "file",
+            file_part("服务器 日志.log", "application/octet-stream", log.clone()),
+        );
+    let response = client
+        .post(&attachments_url)
+        .multipart(form)
+        .send()
+        .await
+        .expect("Failed to upload attachments");
+    assert_eq!(response.status(), 201);
+    let uploaded: Value = response.json().await.expect("Failed to parse attachments");
+    let uploaded = uploaded.as_array().unwrap();
+    assert_eq!(uploaded.len(), 2);
+    assert_eq!(uploaded[0]["filename"], "screenshot.png");
+    assert_eq!(uploaded[0]["content_type"], "image/png");
+    assert_eq!(uploaded[0]["size"], png.len());
+    assert_eq!(
+        uploaded[0]["sha256"].as_str().unwrap(),
+        hex::encode(Sha256::digest(&png))
+    );
+    assert!(uploaded[0]["comment_id"].is_null());
+    assert!(uploaded[0].get("storage_key").is_none());
+    assert_eq!(uploaded[1]["content_type"], "text/plain");
+    let log_id = uploaded[1]["id"].as_str().unwrap().to_string();
+
+    let listed: Value = client
+        .get(&attachments_url)
+        .send()
+        .await
+        .expect("Failed to list attachments")
+        .json()
+        .await
+        .expect("Failed to parse attachments");
+    assert_eq!(listed.as_array().unwrap().len(), 2);
+
+    // 流式下载，非 ASCII 文件名通过 filename* 传递
+    let response = client
+        .get(format!(
+            "{}/api/v1/attachments/{}/download",
+            BASE_URL, log_id
+        ))
+        .send()
+        .await
+        .expect("Failed to download attachment");
+    assert_eq!(response.status(), 200);
+    let headers = response.headers().clone();
+    assert_eq!(headers["content-type"], "text/plain");
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert_eq!(
+        headers["content-disposition"].to_str().unwrap(),
+        "attachment; filename=\"___ __.log\"; \
+         filename*=UTF-8''%E6%9C%8D%E5%8A%A1%E5%99%A8%20%E6%97%A5%E5%BF%97.log"
+    );
+    let body = response.bytes().await.expect("Failed to read attachment");
+    assert_eq!(body.as_ref(), log.as_slice());
+
+    // 类型与大小限制
+    let response = client
+        .post(&attachments_url)
+        .multipart(Form::new().part(
+            "file",
+            file_part("setup.exe", "application/x-msdownload", b"MZ".to_vec()),
+        ))
+        .send()
+        .await
+        .expect("Failed to upload disallowed type");
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .post(&attachments_url)
+        .multipart(Form::new().part(
+            "file",
+            file_part("large.txt", "text/plain", vec![b'a'; 10 * 1024 * 1024 + 1]),
+        ))
+        .send()
+        .await
+        .expect("Failed to upload oversized file");
+    assert_eq!(response.status(), 413);
+
+    let response = client
+        .post(&attachments_url)
+        .multipart(Form::new().text("note", "no file"))
+        .send()
+        .await
+        .expect("Failed to upload without file");
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .post(format!(
+            "{}/api/v1/tickets/{}/attachments",
+            BASE_URL,
+            uuid::Uuid::new_v4()
+        ))
+        .multipart(Form::new().part("file", file_part("a.txt", "text/plain", b"a".to_vec())))
+        .send()
+        .await
+        .expect("Failed to upload to missing ticket");
+    assert_eq!(response.status(), 404);
+
+    // 评论附件随评论一起删除
+    let comment: Value = client
+        .post(format!(
+            "{}/api/v1/tickets/{}/comments",
+            BASE_URL, ticket_id
+        ))
+        .json(&serde_json::json!({ "content": "见附件" }))
+        .send()
+        .await
+        .expect("Failed to create comment")
+        .json()
+        .await
+        .expect("Failed to parse comment");
+    let comment_id = comment["id"].as_str().unwrap().to_string();
+    let comment_url = format!(
+        "{}/api/v1/tickets/{}/comments/{}",
+        BASE_URL, ticket_id, comment_id
+    );
+    let response = client
+        .post(format!("{}/attachments", comment_url))
+        .multipart(Form::new().part(
+            "file",
+            file_part("trace.txt", "text/plain", b"stack trace".to_vec()),
+        ))
+        .send()
+        .await
+        .expect("Failed to upload comment attachment");
+    assert_eq!(response.status(), 201);
+    let comment_attachments: Value = response.json().await.expect("Failed to parse");
+    assert_eq!(comment_attachments[0]["comment_id"], comment_id.as_str());
+    let trace_id = comment_attachments[0]["id"].as_str().unwrap().to_string();
+
+    let listed: Value = client
+        .get(format!("{}/attachments", comment_url))
+        .send()
+        .await
+        .expect("Failed to list comment attachments")
+        .json()
+        .await
+        .expect("Failed to parse attachments");
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    let response = client
+        .delete(&comment_url)
+        .send()
+        .await
+        .expect("Failed to delete comment");
+    assert_eq!(response.status(), 204);
+    let response = client
+        .get(format!(
+            "{}/api/v1/attachments/{}/download",
+            BASE_URL, trace_id
+        ))
+        .send()
+        .await
+        .expect("Failed to download deleted attachment");
+    assert_eq!(response.status(), 404);
+
+    // 只有上传者或管理员可以删除附件
+    let email = format!("agent-{}@example.com", uuid::Uuid::new_v4());
+    client
+        .post(format!("{}/api/v1/users", BASE_URL))
+        .json(&serde_json::json!({
+            "name": "agent",
+            "email": email,
+            "password": "password123",
+            "role": "agent"
+        }))
+        .send()
+        .await
+        .expect("Failed to create user");
+    let agent = client_with_token(&login(&email, "password123").await);
+    let response = agent
+        .delete(format!("{}/api/v1/attachments/{}", BASE_URL, log_id))
+        .send()
+        .await
+        .expect("Failed to delete attachment as agent");
+    assert_eq!(response.status(), 403);
+
+    let response = client
+        .delete(format!("{}/api/v1/attachments/{}", BASE_URL, log_id))
+        .send()
+        .await
+        .expect("Failed to delete attachment");
+    assert_eq!(response.status(), 204);
+    let response = client
+        .get(format!(
+            "{}/api/v1/attachments/{}/download",
+            BASE_URL, log_id
+        ))
+        .send()
+        .await
+        .expect("Failed to download deleted attachment");
+    assert_eq!(response.status(), 404);
+
+    // 上传与删除记录在工单历史中
+    let history: Value = client
+        .get(format!(
+            "{}/api/v1/tickets/{}/history?limit=100",
+            BASE_URL, ticket_id
+        ))
+        .send()
+        .await
+        .expect("Failed to get history")
+        .json()
+        .await
+        .expect("Failed to parse history");
+    let event_types: Vec<&str> = history["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["event_type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        event_types
+            .iter()
+            .filter(|t| **t == "attachment_added")
+            .count(),
+        3
+    );
+    assert!(event_types.contains(&"attachment_deleted"));
+}
+
+#[tokio::test]
+async fn test_s3_storage_backend() {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{
+        body::Bytes,
+        extract::{Path, State},
+        http::{HeaderMap, Method, StatusCode},
+        routing::any,
+        Router,
+    };
+    use futures_util::TryStreamExt;
+    use sha2::{Digest, Sha256};
+    use ticket_backend::{
+        config::S3Config,
+        storage::{AttachmentStorage, S3Storage, SpooledFile},
+        AppError,
+    };
+
+    // 本地 S3 兼容替身：校验签名头后在内存中保存对象
+    type Objects = Arc<Mutex<HashMap<String, Bytes>>>;
+
+    async fn object(
+        State(objects): State<Objects>,
+        Path((bucket, key)): Path<(String, String)>,
+        method: Method,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> (StatusCode, Bytes) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let authorization = header("authorization");
+        let signed = authorization.starts_with("AWS4-HMAC-SHA256 Credential=test-access/")
+            && authorization.contains("/us-east-1/s3/aws4_request")
+            && authorization.contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date")
+            && authorization.contains("Signature=")
+            && !header("x-amz-date").is_empty()
+            && header("x-amz-content-sha256") == hex::encode(Sha256::digest(&body));
+        if bucket != "attachments" || !signed {
+            return (StatusCode::FORBIDDEN, Bytes::new());
+        }
+
+        let mut objects = objects.lock().unwrap();
+        match method {
+            // S3 不接受分块传输的 PUT
+            Method::PUT if header("content-length") != body.len().to_string() => {
+                (StatusCode::LENGTH_REQUIRED, Bytes::new())
+            }
+            Method::PUT => {
+                objects.insert(key, body);
+                (StatusCode::OK, Bytes::new())
+            }
+            Method::GET => match objects.get(&key) {
+                Some(data) => (StatusCode::OK, data.clone()),
+                None => (StatusCode::NOT_FOUND, Bytes::new()),
+            },
+            Method::DELETE => {
+                objects.remove(&key);
+                (StatusCode::NO_CONTENT, Bytes::new())
+            }
+            _ => (StatusCode::METHOD_NOT_ALLOWED, Bytes::new()),
+        }
+    }
+
+    let objects = Objects::default();
+    let app = Router::new()
+        .route("/:bucket/*key", any(object))
+        .with_state(objects.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind stand-in");
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let storage = S3Storage::new(S3Config {
+        endpoint,
+        bucket: "attachments".to_string(),
+        region: "us-east-1".to_string(),
+        access_key_id: "test-access".to_string(),
+        secret_access_key: "test-secret".to_string(),
+    });
+    let key = format!("tickets/{}/{}", uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    let data = Bytes::from_static(b"object contents");
+
+    let file = SpooledFile::from_bytes(&data)
+        .await
+        .expect("Failed to spool object");
+    storage
+        .put(&key, "text/plain", &file)
+        .await
+        .expect("Failed to put object");
+    assert_eq!(objects.lock().unwrap().get(&key), Some(&data));
+
+    let chunks: Vec<Bytes> = storage
+        .get(&key)
+        .await
+        .expect("Failed to get object")
+        .try_collect()
+        .await
+        .expect("Failed to read object");
+    assert_eq!(chunks.concat(), data.to_vec());
+
+    storage.delete(&key).await.expect("Failed to delete object");
+    assert!(objects.lock().unwrap().is_empty());
+    assert!(matches!(
+        storage.get(&key).await,
+        Err(AppError::NotFound(_))
+    ));
+    storage
+        .delete(&key)
+        .await
+        .expect("Deleting a missing object should succeed");
+}
+
+#[tokio::test]
+async fn test_webhooks() {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    };
+
+    use axum::{body::Bytes, extract::State, http::HeaderMap, http::StatusCode, routing::post};
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    // 本地接收端：记录收到的请求，fail 为 true 时返回 500
+    #[derive(Clone, Default)]
+    struct Receiver {
+        requests: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+        fail: Arc<AtomicBool>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> (StatusCode, &'static str) {
+        receiver.requests.lock().unwrap().push((headers, body));
+        if receiver.fail.load(Ordering::SeqCst) {
+            (StatusCode::INTERNAL_SERVER_ERROR, "boom")
+        } else {
+            (StatusCode::OK, "ok")
+        }
+    }
+
+    let receiver = Receiver::default();
+    let app = axum::Router::new()
+        .route("/hook", post(receive))
+        .with_state(receiver.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind receiver");
+    let hook_url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    // 等待接收端收到满足条件的事件，返回请求头与解析后的载荷
+    let wait_for = |event: &'static str, matches: Box<dyn Fn(&Value) -> bool + Send>| {
+        let requests = receiver.requests.clone();
+        async move {
+            for _ in 0..100 {
+                let found = requests.lock().unwrap().iter().find_map(|(headers, body)| {
+                    let payload: Value = serde_json::from_slice(body).ok()?;
+                    (payload["event"] == event && matches(&payload))
+                        .then(|| (headers.clone(), body.clone(), payload))
+                });
+                if let Some(found) = found {
+                    return found;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+            panic!("Webhook event {} was not delivered", event);
+        }
+    };
+
+    let client = authorized_client().await;
+    let secret = "test-webhook-secret-0123456789";
+
+    // 事件名与 URL 校验
+    let response = client
+        .post(format!("{}/api/v1/webhooks", BASE_URL))
+        .json(&serde_json::json!({ "url": hook_url, "events": ["ticket.unknown"] }))
+        .send()
+        .await
+        .expect("Failed to create webhook");
+    assert_eq!(response.status(), 400);
+    let response = client
+        .post(format!("{}/api/v1/webhooks", BASE_URL))
+        .json(&serde_json::json!({ "url": "not a url", "events": ["ticket.created"] }))
+        .send()
+        .await
+        .expect("Failed to create webhook");
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .post(format!("{}/api/v1/webhooks", BASE_URL))
+        .json(&serde_json::json!({
+            "url": hook_url,
+            "secret": secret,
+            "events": ["ticket.created", "ticket.status_changed", "tag.*"],
+        }))
+        .send()
+        .await
+        .expect("Failed to create webhook");
+    assert_eq!(response.status(), 201);
+    let webhook: Value = response.json().await.expect("Failed to parse webhook");
+    assert_eq!(webhook["secret"], secret);
+    let webhook_id = webhook["id"].as_str().unwrap().to_string();
+    let webhook_url = format!("{}/api/v1/webhooks/{}", BASE_URL, webhook_id);
+
+    let fetched: Value = client
+        .get(&webhook_url)
+        .send()
+        .await
+        .expect("Failed to get webhook")
+        .json()
+        .await
+        .expect("Failed to parse webhook");
+    assert!(fetched.get("secret").is_none());
+
+    // 创建工单：签名为 HMAC-SHA256(secret, "{timestamp}.{body}")
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "Webhook 测试工单" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let ticket_id = ticket["id"].as_str().unwrap().to_string();
+
+    let id = ticket_id.clone();
+    let (headers, body, payload) = wait_for(
+        "ticket.created",
+        Box::new(move |payload| payload["data"]["ticket"]["id"] == id.as_str()),
+    )
+    .await;
+    assert_eq!(headers["x-webhook-event"], "ticket.created");
+    assert_eq!(payload["data"]["ticket"]["title"], "Webhook 测试工单");
+    let timestamp = headers["x-webhook-timestamp"].to_str().unwrap();
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(&body);
+    assert_eq!(
+        headers["x-webhook-signature"].to_str().unwrap(),
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    );
+
+    // 一次修改只投递一条事件，data.changes 列出全部变更；含状态变更时为 ticket.status_changed
+    client
+        .put(format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
+        .json(
+            &serde_json::json!({ "title": "Webhook 测试工单（已改名）", "status": "in_progress" }),
+        )
+        .send()
+        .await
+        .expect("Failed to update ticket");
+    let id = ticket_id.clone();
+    let (_, _, payload) = wait_for(
+        "ticket.status_changed",
+        Box::new(move |payload| payload["data"]["ticket"]["id"] == id.as_str()),
+    )
+    .await;
+    let changes = payload["data"]["changes"].as_array().unwrap();
+    let fields: Vec<&str> = changes
+        .iter()
+        .map(|change| change["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, ["title", "status"]);
+    assert_eq!(changes[1]["old_value"], "open");
+    assert_eq!(changes[1]["new_value"], "in_progress");
+    let id = ticket_id.clone();
+    let deliveries: Value = client
+        .get(format!(
+            "{}/deliveries?event=ticket.status_changed&limit=100",
+            webhook_url
+        ))
+        .send()
+        .await
+        .expect("Failed to list deliveries")
+        .json()
+        .await
+        .expect("Failed to parse deliveries");
+    let updates = deliveries["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|delivery| delivery["payload"]["data"]["ticket"]["id"] == id.as_str())
+        .count();
+    assert_eq!(updates, 1);
+
+    // 标签事件
+    let tag_name = format!("webhook-{}", uuid::Uuid::new_v4().simple());
+    let tag: Value = client
+        .post(format!("{}/api/v1/tags", BASE_URL))
+        .json(&serde_json::json!({ "name": tag_name }))
+        .send()
+        .await
+        .expect("Failed to create tag")
+        .json()
+        .await
+        .expect("Failed to parse tag");
+    let tag_id = tag["id"].as_str().unwrap().to_string();
+    client
+        .delete(format!("{}/api/v1/tags/{}", BASE_URL, tag_id))
+        .send()
+        .await
+        .expect("Failed to delete tag");
+    let id = tag_id.clone();
+    wait_for(
+        "tag.created",
+        Box::new(move |payload| payload["data"]["tag"]["id"] == id.as_str()),
+    )
+    .await;
+    let id = tag_id.clone();
+    wait_for(
+        "tag.deleted",
+        Box::new(move |payload| payload["data"]["tag"]["id"] == id.as_str()),
+    )
+    .await;
+
+    // 接收端失败时保留待重试的投递，记录状态码与响应体
+    receiver.fail.store(true, Ordering::SeqCst);
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "Webhook 重试工单" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let failing_ticket_id = ticket["id"].as_str().unwrap().to_string();
+
+    let mut failed_delivery = None;
+    for _ in 0..100 {
+        let deliveries: Value = client
+            .get(format!(
+                "{}/deliveries?event=ticket.created&limit=100",
+                webhook_url
+            ))
+            .send()
+            .await
+            .expect("Failed to list deliveries")
+            .json()
+            .await
+            .expect("Failed to parse deliveries");
+        failed_delivery = deliveries["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|delivery| {
+                delivery["payload"]["data"]["ticket"]["id"] == failing_ticket_id.as_str()
+                    && delivery["attempt_count"] == 1
+            })
+            .cloned();
+        if failed_delivery.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let failed_delivery = failed_delivery.expect("Failed delivery was not recorded");
+    assert_eq!(failed_delivery["status"], "pending");
+    assert_eq!(failed_delivery["response_status"], 500);
+    assert_eq!(failed_delivery["response_body"], "boom");
+    assert!(failed_delivery["next_attempt_at"].is_string());
+    let delivery_url = format!(
+        "{}/deliveries/{}",
+        webhook_url,
+        failed_delivery["id"].as_str().unwrap()
+    );
+
+    // 手动立即重试
+    receiver.fail.store(false, Ordering::SeqCst);
+    let response = client
+        .post(format!("{}/retry", delivery_url))
+        .send()
+        .await
+        .expect("Failed to retry delivery");
+    assert_eq!(response.status(), 200);
+
+    let mut detail = Value::Null;
+    for _ in 0..100 {
+        detail = client
+            .get(&delivery_url)
+            .send()
+            .await
+            .expect("Failed to get delivery")
+            .json()
+            .await
+            .expect("Failed to parse delivery");
+        if detail["status"] == "succeeded" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(detail["status"], "succeeded");
+    assert_eq!(detail["attempt_count"], 2);
+    let attempts = detail["attempts"].as_array().unwrap();
+    assert_eq!(attempts.len(), 2);
+    assert_eq!(attempts[0]["response_status"], 500);
+    assert_eq!(attempts[1]["response_status"], 200);
+    assert_eq!(attempts[1]["response_body"], "ok");
+
+    let deliveries: Value = client
+        .get(format!("{}/deliveries?event=ticket.updated", webhook_url))
+        .send()
+        .await
+        .expect("Failed to list deliveries")
+        .json()
+        .await
+        .expect("Failed to parse deliveries");
+    assert_eq!(deliveries["total"], 0);
+
+    let response = client
+        .delete(&webhook_url)
+        .send()
+        .await
+        .expect("Failed to delete webhook");
+    assert_eq!(response.status(), 204);
+}
+
+#[tokio::test]
+async fn test_event_stream() {
+    use std::time::Duration;
+
+    // 逐条读取 SSE 消息，跳过心跳注释
+    struct EventReader {
+        response: reqwest::Response,
+        buffer: String,
+    }
+
+    impl EventReader {
+        async fn next(&mut self) -> (Option<i64>, String, Value) {
+            loop {
+                if let Some(end) = self.buffer.find("\n\n") {
+                    let message: String = self.buffer.drain(..end + 2).collect();
+                    let (mut id, mut event, mut data) = (None, String::new(), String::new());
+                    for line in message.lines() {
+                        if let Some(value) = line.strip_prefix("id:") {
+                            id = value.trim().parse().ok();
+                        } else if let Some(value) = line.strip_prefix("event:") {
+                            event = value.trim().to_string();
+                        } else if let Some(value) = line.strip_prefix("data:") {
+                            data.push_str(value.trim());
+                        }
+                    }
+                    if event.is_empty() {
+                        continue;
+                    }
+                    let data = serde_json::from_str(&data).expect("Invalid event data");
+                    return (id, event, data);
+                }
+
+                let chunk = tokio::time::timeout(Duration::from_secs(10), self.response.chunk())
+                    .await
+                    .expect("Timed out waiting for event")
+                    .expect("Failed to read event stream")
+                    .expect("Event stream closed");
+                self.buffer.push_str(&String::from_utf8_lossy(&chunk));
+            }
+        }
+
+        // 读取直到出现满足条件的事件，返回其 id
+        async fn wait_for(&mut self, event: &str, matches: impl Fn(&Value) -> bool) -> i64 {
+            loop {
+                let (id, name, data) = self.next().await;
+                if name == event && matches(&data) {
+                    return id.expect("Event without id");
+                }
+            }
+        }
+    }
+
+    let token = login(&admin_credentials().0, &admin_credentials().1).await;
+    let client = client_with_token(&token);
+    let events_url = format!("{}/api/v1/events", BASE_URL);
+
+    let open = |request: reqwest::RequestBuilder| async move {
+        let response = request
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .expect("Failed to open event stream");
+        assert_eq!(response.status(), 200);
+        assert!(response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/event-stream"));
+        EventReader {
+            response,
+            buffer: String::new(),
+        }
+    };
+
+    // 未认证；查询参数中的令牌仅对流式请求有效
+    let response = reqwest::Client::new()
+        .get(&events_url)
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .expect("Failed to open event stream");
+    assert_eq!(response.status(), 401);
+    let response = reqwest::Client::new()
+        .get(format!(
+            "{}/api/v1/auth/me?access_token={}",
+            BASE_URL, token
+        ))
+        .send()
+        .await
+        .expect("Failed to get current user");
+    assert_eq!(response.status(), 401);
+    let mut query_stream = open(
+        reqwest::Client::new()
+            .get(&events_url)
+            .query(&[("access_token", token.as_str())]),
+    )
+    .await;
+
+    let mut stream = open(client.get(&events_url)).await;
+
+    // 工单、评论、标签的变更都会推送
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "事件流测试工单" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let ticket_id = ticket["id"].as_str().unwrap().to_string();
+    let ticket_url = format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id);
+    let is_ticket = |data: &Value| data["data"]["ticket"]["id"] == ticket_id.as_str();
+
+    let created_id = stream.wait_for("ticket.created", is_ticket).await;
+    assert_eq!(
+        query_stream.wait_for("ticket.created", is_ticket).await,
+        created_id
+    );
+
+    let response = client
+        .put(&ticket_url)
+        .json(&serde_json::json!({ "title": "事件流测试工单（已修改）" }))
+        .send()
+        .await
+        .expect("Failed to update ticket");
+    assert_eq!(response.status(), 200);
+    let response = client
+        .post(format!("{}/comments", ticket_url))
+        .json(&serde_json::json!({ "content": "事件流评论" }))
+        .send()
+        .await
+        .expect("Failed to add comment");
+    assert!(response.status().is_success());
+    let tag_name = format!("事件流-{}", &ticket_id[..8]);
+    let tag: Value = client
+        .post(format!("{}/api/v1/tags", BASE_URL))
+        .json(&serde_json::json!({ "name": tag_name }))
+        .send()
+        .await
+        .expect("Failed to create tag")
+        .json()
+        .await
+        .expect("Failed to parse tag");
+    let response = client
+        .delete(&ticket_url)
+        .send()
+        .await
+        .expect("Failed to delete ticket");
+    assert!(response.status().is_success());
+
+    let updated_id = stream
+        .wait_for("ticket.updated", |data| {
+            is_ticket(data) && data["data"]["changes"][0]["field"] == "title"
+        })
+        .await;
+    let comment_id = stream.wait_for("ticket.comment_added", is_ticket).await;
+    stream
+        .wait_for("tag.created", |data| data["data"]["tag"]["id"] == tag["id"])
+        .await;
+    let deleted_id = stream.wait_for("ticket.deleted", is_ticket).await;
+    assert!(created_id < updated_id && updated_id < comment_id && comment_id < deleted_id);
+
+    // 通过 Last-Event-ID 重连：补发之后的事件，不重复发送已收到的
+    let mut resumed = open(
+        client
+            .get(&events_url)
+            .header("Last-Event-ID", created_id.to_string()),
+    )
+    .await;
+    let mut seen = Vec::new();
+    loop {
+        let (id, event, data) = resumed.next().await;
+        let id = id.expect("Replayed event without id");
+        assert!(id > created_id);
+        if is_ticket(&data) {
+            seen.push(event);
+        }
+        if id == deleted_id {
+            break;
+        }
+    }
+    assert!(!seen.contains(&"ticket.created".to_string()));
+    for event in ["ticket.updated", "ticket.comment_added", "ticket.deleted"] {
+        assert!(seen.contains(&event.to_string()), "{} not replayed", event);
+    }
+
+    // 查询参数形式的 last_event_id
+    let mut resumed = open(
+        client
+            .get(&events_url)
+            .query(&[("last_event_id", comment_id)]),
+    )
+    .await;
+    let (id, _, _) = resumed.next().await;
+    assert!(id.unwrap() > comment_id);
+}
+
+#[tokio::test]
+async fn test_ticket_collaboration_socket() {
+    use futures_util::{SinkExt, StreamExt};
+    use std::time::Duration;
+    use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+
+    type Socket = tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >;
+
+    async fn connect(url: &str, token: Option<&str>) -> Result<Socket, u16> {
+        let mut request = url.into_client_request().unwrap();
+        if let Some(token) = token {
+            request.headers_mut().insert(
+                "Authorization",
+                format!("Bearer {}", token).parse().unwrap(),
+            );
+        }
+        match tokio_tungstenite::connect_async(request).await {
+            Ok((socket, _)) => Ok(socket),
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                Err(response.status().as_u16())
+            }
+            Err(e) => panic!("Failed to connect: {}", e),
+        }
+    }
+
+    // 读取下一条指定类型的消息
+    async fn next_message(socket: &mut Socket, kind: &str) -> Value {
+        loop {
+            let message = tokio::time::timeout(Duration::from_secs(10), socket.next())
+                .await
+                .expect("Timed out waiting for message")
+                .expect("Socket closed")
+                .expect("Failed to read message");
+            if let Message::Text(text) = message {
+                let message: Value = serde_json::from_str(&text).expect("Invalid message");
+                if message["type"] == kind {
+                    return message;
+                }
+            }
+        }
+    }
+
+    async fn send(socket: &mut Socket, message: Value) {
+        socket
+            .send(Message::Text(message.to_string()))
+            .await
+            .expect("Failed to send message");
+    }
+
+    let (email, password) = admin_credentials();
+    let admin_token = login(&email, &password).await;
+    let client = client_with_token(&admin_token);
+
+    // 第二个参与者
+    let agent_email = format!("collab-{}@example.com", uuid::Uuid::new_v4().simple());
+    let response = client
+        .post(format!("{}/api/v1/users", BASE_URL))
+        .json(&serde_json::json!({
+            "name": "协作客服",
+            "email": agent_email,
+            "password": "password123",
+            "role": "agent",
+        }))
+        .send()
+        .await
+        .expect("Failed to create user");
+    assert!(response.status().is_success());
+    let agent_token = login(&agent_email, "password123").await;
+
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": "协作测试工单" }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let ticket_id = ticket["id"].as_str().unwrap().to_string();
+    let ws_url = format!("ws://localhost:3000/api/v1/tickets/{}/ws", ticket_id);
+
+    // 未认证与不存在的工单
+    assert_eq!(connect(&ws_url, None).await.err(), Some(401));
+    let missing = format!(
+        "ws://localhost:3000/api/v1/tickets/{}/ws",
+        uuid::Uuid::new_v4()
+    );
+    assert_eq!(connect(&missing, Some(&admin_token)).await.err(), Some(404));
+
+    // 管理员加入，收到只包含自己的 presence
+    let mut admin = connect(&ws_url, Some(&admin_token)).await.unwrap();
+    let presence = next_message(&mut admin, "presence").await;
+    let admin_connection = presence["connection_id"].clone();
+    assert_eq!(presence["participants"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        presence["participants"][0]["connection_id"],
+        admin_connection
+    );
+
+    // 客服通过查询参数中的令牌加入，双方都能看到对方
+    let mut agent = connect(&format!("{}?access_token={}", ws_url, agent_token), None)
+        .await
+        .unwrap();
+    let presence = next_message(&mut agent, "presence").await;
+    let agent_connection = presence["connection_id"].clone();
+    assert_eq!(presence["participants"].as_array().unwrap().len(), 2);
+    let joined = next_message(&mut admin, "join").await;
+    assert_eq!(joined["participant"]["connection_id"], agent_connection);
+    assert_eq!(joined["participant"]["name"], "协作客服");
+
+    // 输入状态
+    send(
+        &mut agent,
+        serde_json::json!({ "type": "typing", "typing": true }),
+    )
+    .await;
+    let typing = next_message(&mut admin, "typing").await;
+    assert_eq!(typing["participant"]["connection_id"], agent_connection);
+    assert_eq!(typing["participant"]["typing"], true);
+    send(&mut admin, serde_json::json!({ "type": "presence" })).await;
+    let presence = next_message(&mut admin, "presence").await;
+    let agent_entry = presence["participants"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["connection_id"] == agent_connection)
+        .unwrap()
+        .clone();
+    assert_eq!(agent_entry["typing"], true);
+
+    // 无效消息
+    send(&mut admin, serde_json::json!({ "type": "dance" })).await;
+    next_message(&mut admin, "error").await;
+
+    // 通过 REST 保存的修改推送给所有参与者
+    let response = client
+        .put(format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id))
+        .json(&serde_json::json!({ "priority": "high" }))
+        .send()
+        .await
+        .expect("Failed to update ticket");
+    assert_eq!(response.status(), 200);
+    for socket in [&mut admin, &mut agent] {
+        let changed = next_message(socket, "field_changed").await;
+        assert_eq!(changed["field"], "priority");
+        assert_eq!(changed["new_value"], "high");
+        assert_eq!(changed["ticket"]["id"], ticket_id.as_str());
+    }
+
+    // 主动离开与连接断开都会通知其他参与者
+    send(&mut agent, serde_json::json!({ "type": "leave" })).await;
+    let left = next_message(&mut admin, "leave").await;
+    assert_eq!(left["participant"]["connection_id"], agent_connection);
+
+    let mut agent = connect(&ws_url, Some(&agent_token)).await.unwrap();
+    next_message(&mut agent, "presence").await;
+    let joined = next_message(&mut admin, "join").await;
+    drop(agent);
+    let left = next_message(&mut admin, "leave").await;
+    assert_eq!(
+        left["participant"]["connection_id"],
+        joined["participant"]["connection_id"]
+    );
+    assert_ne!(left["participant"]["connection_id"], admin_connection);
+}
+
+#[tokio::test]
+async fn test_email_notifications() {
+    use base64::Engine;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    // 收到的邮件（收件人、解码后的主题与正文）
+    #[derive(Debug, Clone)]
+    struct Email {
+        to: String,
+        subject: String,
+        body: String,
+    }
+
+    // 解码 RFC 2047 编码的主题
+    fn decode_header(value: &str) -> String {
+        let mut decoded = String::new();
+        let mut previous_encoded = false;
+        for word in value.split_whitespace() {
+            let parts: Vec<&str> = word.split('?').collect();
+            let encoded = parts.len() == 5 && word.starts_with("=?") && word.ends_with("?=");
+            // 相邻的编码字之间的空白不保留
+            if !(decoded.is_empty() || (encoded && previous_encoded)) {
+                decoded.push(' ');
+            }
+            if encoded {
+                let bytes = if parts[2].eq_ignore_ascii_case("b") {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(parts[3])
+                        .unwrap()
+                } else {
+                    decode_quoted_printable(&parts[3].replace('_', " "))
+                };
+                decoded.push_str(&String::from_utf8(bytes).unwrap());
+            } else {
+                decoded.push_str(word);
+            }
+            previous_encoded = encoded;
+        }
+        decoded
+    }
+
+    fn decode_quoted_printable(value: &str) -> Vec<u8> {
+        let value = value.replace("=\r\n", "").replace("=\n", "");
+        let bytes = value.as_bytes();
+        let mut decoded = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'=' && i + 2 < bytes.len() {
+                if let Ok(byte) = u8::from_str_radix(&value[i + 1..i + 3], 16) {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+            }
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+        decoded
+    }
+
+    fn parse_message(to: String, data: &str) -> Email {
+        let (head, body) = data.split_once("\r\n\r\n").unwrap_or((data, ""));
+        let head = head.replace("\r\n ", " ").replace("\r\n\t", " ");
+        let header = |name: &str| {
+            head.lines()
+                .find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    key.eq_ignore_ascii_case(name)
+                        .then(|| value.trim().to_string())
+                })
+                .unwrap_or_default()
+        };
+        let body = match header("Content-Transfer-Encoding").to_lowercase().as_str() {
+            "base64" => String::from_utf8(
+                base64::engine::general_purpose::STANDARD
+                    .decode(body.split_whitespace().collect::<String>())
+                    .unwrap(),
+            )
+            .unwrap(),
+            "quoted-printable" => String::from_utf8(decode_quoted_printable(body)).unwrap(),
+            _ => body.to_string(),
+        };
+        Email {
+            to,
+            subject: decode_header(&header("Subject")),
+            body: body.replace("\r\n", "\n"),
+        }
+    }
+
+    // 本地 SMTP 接收端（服务端需以 SMTP_HOST=127.0.0.1 SMTP_PORT=2525 SMTP_SECURITY=none 启动）
+    async fn handle_connection(stream: tokio::net::TcpStream, inbox: Arc<Mutex<Vec<Email>>>) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let _ = writer.write_all(b"220 localhost ESMTP test sink\r\n").await;
+        let mut recipients = Vec::new();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let command = line.to_ascii_uppercase();
+            let reply: &[u8] = if command.starts_with("RCPT TO:") {
+                let address = line[8..].trim().trim_matches(|c| c == '<' || c == '>');
+                recipients.push(address.to_lowercase());
+                b"250 OK\r\n"
+            } else if command == "DATA" {
+                let _ = writer
+                    .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                    .await;
+                let mut data = Vec::new();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if line == "." {
+                        break;
+                    }
+                    data.push(line.strip_prefix('.').unwrap_or(&line).to_string());
+                }
+                let data = data.join("\r\n");
+                let mut inbox = inbox.lock().unwrap();
+                for to in recipients.drain(..) {
+                    inbox.push(parse_message(to, &data));
+                }
+                b"250 OK\r\n"
+            } else if command == "QUIT" {
+                let _ = writer.write_all(b"221 Bye\r\n").await;
+                return;
+            } else {
+                if command == "RSET" {
+                    recipients.clear();
+                }
+                b"250 OK\r\n"
+            };
+            if writer.write_all(reply).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    let port = std::env::var("SMTP_PORT").unwrap_or_else(|_| "2525".to_string());
+    let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{}", port))
+        .await
+        .expect("Failed to bind SMTP sink");
+    let inbox: Arc<Mutex<Vec<Email>>> = Arc::default();
+    let sink_inbox = inbox.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle_connection(stream, sink_inbox.clone()));
+        }
+    });
+
+    // 等待收件人收到主题包含指定内容的邮件
+    let wait_for = |to: String, subject: String| {
+        let inbox = inbox.clone();
+        async move {
+            for _ in 0..300 {
+                let found = inbox
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|email| email.to == to && email.subject.contains(&subject))
+                    .cloned();
+                if let Some(email) = found {
+                    return email;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+            panic!(
+                "Email to {} with subject {:?} was not delivered",
+                to, subject
+            );
+        }
+    };
+
+    let client = authorized_client().await;
+
+    // 两个客服：A 使用英文，B 使用默认的中文
+    let mut agents = Vec::new();
+    for name in ["通知客服A", "通知客服B"] {
+        let email = format!("notify-{}@example.com", uuid::Uuid::new_v4().simple());
+        let user: Value = client
+            .post(format!("{}/api/v1/users", BASE_URL))
+            .json(&serde_json::json!({
+                "name": name,
+                "email": email,
+                "password": "password123",
+                "role": "agent",
+            }))
+            .send()
+            .await
+            .expect("Failed to create user")
+            .json()
+            .await
+            .expect("Failed to parse user");
+        let token = login(&email, "password123").await;
+        agents.push((
+            user["id"].as_str().unwrap().to_string(),
+            email,
+            client_with_token(&token),
+        ));
+    }
+    let (a_id, a_email, a_client) = &agents[0];
+    let (b_id, b_email, b_client) = &agents[1];
+    let preferences_url = format!("{}/api/v1/notifications/preferences", BASE_URL);
+
+    let preferences: Value = b_client
+        .get(&preferences_url)
+        .send()
+        .await
+        .expect("Failed to get preferences")
+        .json()
+        .await
+        .expect("Failed to parse preferences");
+    assert_eq!(preferences["language"], "zh");
+    assert_eq!(preferences["delivery"], "immediate");
+    assert_eq!(preferences["mentioned"], true);
+
+    let preferences: Value = a_client
+        .put(&preferences_url)
+        .json(&serde_json::json!({ "language": "en" }))
+        .send()
+        .await
+        .expect("Failed to update preferences")
+        .json()
+        .await
+        .expect("Failed to parse preferences");
+    assert_eq!(preferences["language"], "en");
+    assert_eq!(preferences["delivery"], "immediate");
+
+    // 指派给 A
+    let title = format!(
+        "通知测试 {}",
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let ticket: Value = client
+        .post(format!("{}/api/v1/tickets", BASE_URL))
+        .json(&serde_json::json!({ "title": title, "assignee_id": a_id }))
+        .send()
+        .await
+        .expect("Failed to create ticket")
+        .json()
+        .await
+        .expect("Failed to parse ticket");
+    let ticket_id = ticket["id"].as_str().unwrap().to_string();
+    let ticket_url = format!("{}/api/v1/tickets/{}", BASE_URL, ticket_id);
+
+    let email = wait_for(
+        a_email.clone(),
+        format!("assigned the ticket \"{}\" to you", title),
+    )
+    .await;
+    assert!(email.subject.starts_with("[Ticket]"));
+    assert!(email.body.starts_with("Hello,"));
+
+    // B 关注工单；报告人与指派人自动成为关注者
+    let response = b_client
+        .post(format!("{}/watch", ticket_url))
+        .send()
+        .await
+        .expect("Failed to watch ticket");
+    assert_eq!(response.status(), 204);
+    let response = b_client
+        .post(format!(
+            "{}/api/v1/tickets/{}/watch",
+            BASE_URL,
+            uuid::Uuid::new_v4()
+        ))
+        .send()
+        .await
+        .expect("Failed to watch ticket");
+    assert_eq!(response.status(), 404);
+
+    let watchers: Vec<Value> = client
+        .get(format!("{}/watchers", ticket_url))
+        .send()
+        .await
+        .expect("Failed to list watchers")
+        .json()
+        .await
+        .expect("Failed to parse watchers");
+    assert_eq!(watchers.len(), 3);
+    for id in [a_id, b_id] {
+        assert!(watchers.iter().any(|w| w["id"] == id.as_str()));
+    }
+
+    // 状态变更通知所有关注者（不含操作者本人），各自使用自己的语言
+    let response = client
+        .put(&ticket_url)
+        .json(&serde_json::json!({ "status": "in_progress" }))
+        .send()
+        .await
+        .expect("Failed to update ticket");
+    assert_eq!(response.status(), 200);
+    wait_for(
+        a_email.clone(),
+        format!(
+            "changed the status of \"{}\" from open to in_progress",
+            title
+        ),
+    )
+    .await;
+    let email = wait_for(
+        b_email.clone(),
+        format!("将工单「{}」的状态从 open 改为 in_progress", title),
+    )
+    .await;
+    assert!(email.subject.starts_with("[工单]"));
+    assert!(email.body.starts_with("你好，"));
+
+    // 评论中 @提及 B：B 只收到提及通知，A 收到评论通知
+    let response = client
+        .post(format!("{}/comments", ticket_url))
+        .json(&serde_json::json!({ "content": format!("@{} 请看一下这个问题", b_email) }))
+        .send()
+        .await
+        .expect("Failed to create comment");
+    assert_eq!(response.status(), 200);
+    let email = wait_for(
+        b_email.clone(),
+        format!("在工单「{}」的评论中提到了你", title),
+    )
+    .await;
+    assert!(email.body.contains("请看一下这个问题"));
+    wait_for(a_email.clone(), format!("commented on \"{}\"", title)).await;
+
+    let notifications: Value = b_client
+        .get(format!("{}/api/v1/notifications", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to list notifications")
+        .json()
+        .await
+        .expect("Failed to parse notifications");
+    let kinds: Vec<&str> = notifications["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["mentioned", "status_changed"]);
+
+    // 取消关注后不再收到通知
+    let response = b_client
+        .delete(format!("{}/watch", ticket_url))
+        .send()
+        .await
+        .expect("Failed to unwatch ticket");
+    assert_eq!(response.status(), 204);
+
+    // 汇总模式：通知暂不发送，等待合并
+    let preferences: Value = a_client
+        .put(&preferences_url)
+        .json(&serde_json::json!({ "delivery": "digest" }))
+        .send()
+        .await
+        .expect("Failed to update preferences")
+        .json()
+        .await
+        .expect("Failed to parse preferences");
+    assert_eq!(preferences["delivery"], "digest");
+    assert_eq!(preferences["language"], "en");
+
+    let response = client
+        .put(&ticket_url)
+        .json(&serde_json::json!({ "status": "resolved" }))
+        .send()
+        .await
+        .expect("Failed to update ticket");
+    assert_eq!(response.status(), 200);
+
+    let pending: Value = a_client
+        .get(format!("{}/api/v1/notifications?status=pending", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to list notifications")
+        .json()
+        .await
+        .expect("Failed to parse notifications");
+    assert_eq!(pending["total"], 1);
+    assert_eq!(pending["data"][0]["kind"], "status_changed");
+    assert_eq!(pending["data"][0]["digest"], true);
+    assert_eq!(pending["data"][0]["data"]["new_status"], "resolved");
+
+    let notifications: Value = b_client
+        .get(format!("{}/api/v1/notifications", BASE_URL))
+        .send()
+        .await
+        .expect("Failed to list notifications")
+        .json()
+        .await
+        .expect("Failed to parse notifications");
+    assert_eq!(notifications["total"], 2);
+}